@@ -0,0 +1,50 @@
+(function () {
+  var periods = [
+    ['week', 'Неделя'],
+    ['month', 'Месяц'],
+    ['year', 'Год'],
+  ];
+
+  function query(form) {
+    var from = form.elements.from.value;
+    var to = form.elements.to.value;
+    if (from && to) {
+      return 'from=' + encodeURIComponent(from) + '&to=' + encodeURIComponent(to);
+    }
+    return 'period=' + encodeURIComponent(form.elements.period.value);
+  }
+
+  function switchPeriod(event) {
+    event.preventDefault();
+    var form = event.target;
+    var params = query(form);
+    var request = new XMLHttpRequest();
+    request.open('GET', '/report/data?' + params);
+    request.withCredentials = true;
+    request.onload = function () {
+      if (request.status === 200) {
+        window.location.assign('/report?' + params);
+        return;
+      }
+      var body = JSON.parse(request.responseText);
+      form.elements.status.value = body.error ? body.error.message : request.statusText;
+    };
+    request.send();
+  }
+
+  var form = document.createElement('form');
+  form.className = 'form-inline';
+  form.style.margin = '10px';
+  var select = '<select name="period" class="form-control">';
+  for (var i = 0; i < periods.length; i++) {
+    select += '<option value="' + periods[i][0] + '">' + periods[i][1] + '</option>';
+  }
+  select += '</select>';
+  form.innerHTML = select +
+    ' <input name="from" type="date" class="form-control">' +
+    ' <input name="to" type="date" class="form-control">' +
+    ' <button type="submit" class="btn btn-default">Показать</button>' +
+    ' <output name="status" class="text-danger"></output>';
+  form.addEventListener('submit', switchPeriod);
+  document.body.insertBefore(form, document.body.firstChild);
+})();
@@ -0,0 +1,60 @@
+use chrono::naive::NaiveDateTime;
+
+use std::fmt;
+
+use super::AccessId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AccessStatus {
+    Pending,
+    Approved,
+    Denied,
+    Revoked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Access {
+    pub id: AccessId,
+    pub telegram_id: i64,
+    pub name: String,
+    pub status: AccessStatus,
+    pub requested: NaiveDateTime,
+    pub decided: Option<NaiveDateTime>,
+    pub decided_by: Option<i64>,
+}
+
+impl Access {
+    pub fn request(telegram_id: i64, name: String) -> Access {
+        Access {
+            id: AccessId::generate(),
+            telegram_id,
+            name,
+            status: AccessStatus::Pending,
+            requested: ::chrono::offset::Local::now().naive_local(),
+            decided: None,
+            decided_by: None,
+        }
+    }
+
+    pub fn decide(&mut self, status: AccessStatus, admin: i64) {
+        self.status = status;
+        self.decided = Some(::chrono::offset::Local::now().naive_local());
+        self.decided_by = Some(admin);
+    }
+
+    pub fn can_request_again(&self) -> bool {
+        self.status == AccessStatus::Revoked
+    }
+}
+
+impl fmt::Display for AccessStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccessStatus::Pending => write!(f, "pending"),
+            AccessStatus::Approved => write!(f, "approved"),
+            AccessStatus::Denied => write!(f, "denied"),
+            AccessStatus::Revoked => write!(f, "revoked"),
+        }
+    }
+}
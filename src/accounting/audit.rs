@@ -0,0 +1,81 @@
+use chrono::naive::NaiveDateTime;
+
+use std::fmt;
+
+use super::AuditId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Actor {
+    Telegram(i64),
+    Cli,
+    Web(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditAction {
+    Insert,
+    Update,
+    Delete,
+    Migrate,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub id: AuditId,
+    pub time: NaiveDateTime,
+    pub actor: Actor,
+    pub action: AuditAction,
+    pub table: String,
+    pub entity_id: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub table: Option<String>,
+    pub entity_id: Option<String>,
+    pub from: Option<NaiveDateTime>,
+    pub till: Option<NaiveDateTime>,
+}
+
+impl AuditRecord {
+    pub fn new(
+        actor: Actor,
+        action: AuditAction,
+        table: &str,
+        entity_id: String,
+        before: Option<String>,
+        after: Option<String>,
+    ) -> AuditRecord {
+        AuditRecord {
+            id: AuditId::generate(),
+            time: ::chrono::offset::Local::now().naive_local(),
+            actor,
+            action,
+            table: table.to_owned(),
+            entity_id,
+            before,
+            after,
+        }
+    }
+}
+
+impl AuditFilter {
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.table.as_ref().map_or(true, |t| *t == record.table)
+            && self.entity_id.as_ref().map_or(true, |id| *id == record.entity_id)
+            && self.from.map_or(true, |from| record.time >= from)
+            && self.till.map_or(true, |till| record.time <= till)
+    }
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Actor::Telegram(id) => write!(f, "telegram:{}", id),
+            Actor::Cli => write!(f, "cli"),
+            Actor::Web(ref token) => write!(f, "web:{}", token),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use chrono::prelude::*;
+
+use std::str::FromStr;
+
+use super::{Entry, Product, Tag, Tags, UserId};
+
+#[derive(Debug)]
+pub struct Batch {
+    pub entries: Vec<Entry>,
+    pub errors: Vec<LineError>,
+}
+
+#[derive(Debug)]
+pub struct LineError {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+struct Header {
+    date: Option<NaiveDate>,
+    tags: Vec<String>,
+}
+
+impl Batch {
+    pub fn is_batch(raw: &str) -> bool {
+        raw.lines().filter(|line| !line.trim().is_empty()).count() > 1
+    }
+
+    pub fn parse(raw: &str, user: UserId, now: NaiveDateTime) -> Batch {
+        let mut lines = raw
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|&(_, line)| !line.is_empty())
+            .peekable();
+        let header = match lines.peek().and_then(|&(_, line)| header(line, now)) {
+            Some(header) => {
+                lines.next();
+                header
+            }
+            None => Header::default(),
+        };
+        let time = header.date.map_or(now, |date| date.and_time(now.time()));
+
+        let mut entries = Vec::new();
+        let mut errors = Vec::new();
+        for (line, text) in lines {
+            match Product::from_str(text) {
+                Ok(product) => {
+                    let mut entry = Entry::new(user.clone(), product);
+                    entry.time = time;
+                    entry.tags = Tags {
+                        tags: header
+                            .tags
+                            .iter()
+                            .map(|value| Tag {
+                                value: value.clone(),
+                            }).collect(),
+                    };
+                    entries.push(entry);
+                }
+                Err(e) => errors.push(LineError {
+                    line,
+                    text: text.to_owned(),
+                    reason: format!("{}", e),
+                }),
+            }
+        }
+        Batch { entries, errors }
+    }
+
+    pub fn total(&self) -> i32 {
+        self.entries.iter().map(|entry| entry.product.price).sum()
+    }
+}
+
+fn header(line: &str, now: NaiveDateTime) -> Option<Header> {
+    let mut header = Header::default();
+    for word in line.split_whitespace() {
+        if word.starts_with('#') && word.len() > 1 {
+            header.tags.push(word[1..].to_owned());
+        } else if header.date.is_none() {
+            header.date = Some(date(word, now)?);
+        } else {
+            return None;
+        }
+    }
+    Some(header)
+}
+
+fn date(word: &str, now: NaiveDateTime) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%d.%m.%Y")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{}.{}", word, now.year()), "%d.%m.%Y"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd(2018, 10, 20).and_hms(12, 30, 0)
+    }
+
+    #[test]
+    fn parses_every_line_and_keeps_errors() {
+        let batch = Batch::parse(
+            "хлеб 50\n\nмолоко 80\nкефир\n2*30 яйца",
+            UserId::new("user".to_owned()),
+            now(),
+        );
+        let products: Vec<(&str, i32)> = batch
+            .entries
+            .iter()
+            .map(|e| (e.product.name.as_str(), e.product.price))
+            .collect();
+        assert_eq!(products, vec![("хлеб", 50), ("молоко", 80), ("яйца", 60)]);
+        assert_eq!(batch.total(), 190);
+        assert_eq!(batch.errors.len(), 1);
+        assert_eq!(batch.errors[0].line, 4);
+        assert_eq!(batch.errors[0].text, "кефир");
+    }
+
+    #[test]
+    fn applies_header_date_and_tags() {
+        let batch = Batch::parse(
+            "15.10 #дача #стройка\nдоски 1200\nгвозди 150",
+            UserId::new("user".to_owned()),
+            now(),
+        );
+        assert!(batch.errors.is_empty());
+        assert_eq!(batch.entries.len(), 2);
+        for entry in &batch.entries {
+            assert_eq!(entry.time, NaiveDate::from_ymd(2018, 10, 15).and_hms(12, 30, 0));
+            let tags: Vec<&str> = entry.tags.tags.iter().map(|t| t.value.as_str()).collect();
+            assert_eq!(tags, vec!["дача", "стройка"]);
+        }
+    }
+
+    #[test]
+    fn first_line_without_header_is_an_entry() {
+        let batch = Batch::parse(
+            "хлеб 50\nмолоко 80",
+            UserId::new("user".to_owned()),
+            now(),
+        );
+        assert_eq!(batch.entries.len(), 2);
+        assert_eq!(batch.entries[0].time, now());
+        assert!(batch.entries[0].tags.tags.is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use super::{CategoryId, UserId};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Category {
     pub id: CategoryId,
     pub user_id: UserId,
@@ -0,0 +1,68 @@
+use chrono::prelude::*;
+
+use super::{ConversationId, EntryId, UserId};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Dialog {
+    ConfirmDelete { entry_id: EntryId },
+    AwaitCategory { entry_id: EntryId },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: ConversationId,
+    pub chat_id: i64,
+    pub user_id: UserId,
+    pub dialog: Dialog,
+    pub expires: NaiveDateTime,
+}
+
+impl Conversation {
+    pub fn new(
+        chat_id: i64,
+        user_id: UserId,
+        dialog: Dialog,
+        timeout: ::chrono::Duration,
+    ) -> Conversation {
+        Conversation {
+            id: ConversationId::generate(),
+            chat_id,
+            user_id,
+            dialog,
+            expires: ::chrono::offset::Local::now().naive_local() + timeout,
+        }
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        now < self.expires
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn survives_storage_and_expires() {
+        let conversation = Conversation::new(
+            42,
+            UserId::new("user".to_owned()),
+            Dialog::ConfirmDelete {
+                entry_id: EntryId::new("entry".to_owned()),
+            },
+            ::chrono::Duration::minutes(10),
+        );
+
+        let json = ::serde_json::to_string(&conversation).unwrap();
+        let from_json: Conversation = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json.dialog, conversation.dialog);
+
+        let binary = ::bincode::serialize(&conversation, ::bincode::Infinite).unwrap();
+        let from_binary: Conversation = ::bincode::deserialize(&binary).unwrap();
+        assert_eq!(from_binary.dialog, conversation.dialog);
+
+        let now = ::chrono::offset::Local::now().naive_local();
+        assert!(conversation.is_active(now));
+        assert!(!conversation.is_active(now + ::chrono::Duration::minutes(11)));
+    }
+}
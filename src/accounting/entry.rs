@@ -7,7 +7,7 @@ use super::evaluation::evaluate;
 use super::{EntryId, Tags, UserId};
 use error::AppError;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Entry {
     pub id: EntryId,
     pub user_id: UserId,
@@ -16,7 +16,7 @@ pub struct Entry {
     pub tags: Tags,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Product {
     pub name: String,
     pub price: i32,
@@ -72,9 +72,7 @@ fn price_name(raw: &str) -> Result<(&str, &str), FailureError> {
     }
 
     let (price, name) = match price_first {
-        None => return Err(AppError::Any {
-            text: "В строке должны быть указаны продукт и цена",
-        }.into()),
+        None => return Err(AppError::ProductAndPriceRequired.into()),
         Some(true) => raw.split_at(split_index),
         Some(false) => {
             let (name, price) = raw.split_at(split_index);
@@ -83,9 +81,7 @@ fn price_name(raw: &str) -> Result<(&str, &str), FailureError> {
     };
 
     if name.is_empty() {
-        return Err(AppError::Any {
-            text: "В строке должны быть указаны продукт и цена",
-        }.into());
+        return Err(AppError::ProductAndPriceRequired.into());
     }
 
     Ok((price, name))
@@ -0,0 +1,111 @@
+use chrono::naive::NaiveDateTime;
+use uuid::Uuid;
+
+use std::fmt;
+
+use super::token::{constant_time_eq, hash};
+use super::{HouseholdId, InviteId, MemberId, UserId};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Household {
+    pub id: HouseholdId,
+    pub name: String,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    Owner,
+    Member,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Member {
+    pub id: MemberId,
+    pub household_id: HouseholdId,
+    pub user_id: UserId,
+    pub name: String,
+    pub role: Role,
+    pub joined: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invite {
+    pub id: InviteId,
+    pub household_id: HouseholdId,
+    pub hash: String,
+    pub expires: NaiveDateTime,
+    pub used: bool,
+}
+
+impl Household {
+    pub fn new(name: String) -> Household {
+        Household {
+            id: HouseholdId::generate(),
+            name,
+            created: ::chrono::offset::Local::now().naive_local(),
+        }
+    }
+}
+
+impl Member {
+    pub fn new(household_id: HouseholdId, user_id: UserId, name: String, role: Role) -> Member {
+        Member {
+            id: MemberId::generate(),
+            household_id,
+            user_id,
+            name,
+            role,
+            joined: ::chrono::offset::Local::now().naive_local(),
+        }
+    }
+}
+
+impl Invite {
+    pub fn issue(household_id: HouseholdId, lifetime: ::chrono::Duration) -> (Invite, String) {
+        let code = format!("{}", Uuid::new_v4().simple())[..10].to_owned();
+        let invite = Invite {
+            id: InviteId::generate(),
+            household_id,
+            hash: hash("", &code),
+            expires: ::chrono::offset::Local::now().naive_local() + lifetime,
+            used: false,
+        };
+        (invite, code)
+    }
+
+    pub fn redeemable(&self, code: &str, now: NaiveDateTime) -> bool {
+        !self.used
+            && now < self.expires
+            && constant_time_eq(hash("", code).as_bytes(), self.hash.as_bytes())
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Role::Owner => write!(f, "owner"),
+            Role::Member => write!(f, "member"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redeems_invite_once_before_expiry() {
+        let (invite, code) = Invite::issue(HouseholdId::generate(), ::chrono::Duration::hours(24));
+        let now = ::chrono::offset::Local::now().naive_local();
+        assert_eq!(code.len(), 10);
+        assert!(invite.redeemable(&code, now));
+        assert!(!invite.redeemable("0123456789", now));
+        assert!(!invite.redeemable(&code, now + ::chrono::Duration::hours(25)));
+        assert!(!Invite {
+            used: true,
+            ..invite
+        }.redeemable(&code, now));
+    }
+}
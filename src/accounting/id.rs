@@ -27,3 +27,14 @@ id!(UserId);
 id!(CategoryId);
 id!(EntryId);
 id!(SmsId);
+id!(AuditId);
+id!(TokenId);
+id!(LoginId);
+id!(ConversationId);
+id!(ReceiptId);
+id!(HouseholdId);
+id!(MemberId);
+id!(InviteId);
+id!(SplitId);
+id!(SettlementId);
+id!(AccessId);
@@ -1,16 +1,38 @@
 pub mod statistics;
 
+pub use self::access::{Access, AccessStatus};
+pub use self::audit::{Actor, AuditAction, AuditFilter, AuditRecord};
+pub use self::batch::{Batch, LineError};
 pub use self::category::Category;
+pub use self::conversation::{Conversation, Dialog};
 pub use self::entry::{Entry, Product};
-pub use self::id::{CategoryId, EntryId, SmsId, UserId};
-pub use self::sms::{NewSms, Sms};
+pub use self::evaluation::Error as EvaluationError;
+pub use self::household::{Household, Invite, Member, Role};
+pub use self::id::{
+    AccessId, AuditId, CategoryId, ConversationId, EntryId, HouseholdId, InviteId, LoginId,
+    MemberId, ReceiptId, SettlementId, SmsId, SplitId, TokenId, UserId,
+};
+pub use self::receipt::{FiscalCode, Receipt, ReceiptStatus};
+pub use self::sms::{NewSms, Sms, SmsFilter, SmsStatus};
+pub use self::split::{
+    user_key, user_of_key, Balance, SettleUp, Settlement, Share, Split, Transfer,
+};
 pub use self::tag::{Tag, Tags};
+pub use self::token::{constant_time_eq, ApiToken, LoginCode, Scope};
 pub use self::user::{AndroidAuth, TelegramId, User};
 
+mod access;
+mod audit;
+mod batch;
 mod category;
+mod conversation;
 mod entry;
 mod evaluation;
+mod household;
 mod id;
+mod receipt;
 mod sms;
+mod split;
 mod tag;
+mod token;
 mod user;
@@ -0,0 +1,204 @@
+use chrono::prelude::*;
+use failure::Error as FailureError;
+
+use super::{Entry, EntryId, Product, ReceiptId, UserId};
+use error::AppError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiscalCode {
+    pub time: NaiveDateTime,
+    pub total_kopecks: i64,
+    pub fiscal_drive: String,
+    pub document: String,
+    pub sign: String,
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Receipt {
+    pub id: ReceiptId,
+    pub user_id: UserId,
+    pub entry_id: EntryId,
+    pub code: FiscalCode,
+    pub added: NaiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub enum ReceiptStatus {
+    Stored(Entry),
+    Duplicate(Receipt),
+}
+
+impl FiscalCode {
+    pub fn find(text: &str) -> Option<&str> {
+        text.split_whitespace()
+            .find(|word| word.contains("t=") && word.contains("fn="))
+    }
+
+    pub fn parse(payload: &str) -> Result<FiscalCode, FailureError> {
+        let mut time = None;
+        let mut total_kopecks = None;
+        let mut fiscal_drive = None;
+        let mut document = None;
+        let mut sign = None;
+        let mut kind = None;
+        for pair in payload.trim().split('&') {
+            let mut parts = pair.splitn(2, '=');
+            let key = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("");
+            match key {
+                "t" => time = Some(parse_time(value)?),
+                "s" => total_kopecks = Some(parse_kopecks(value)?),
+                "fn" => fiscal_drive = Some(digits("fn", value)?),
+                "i" => document = Some(digits("i", value)?),
+                "fp" => sign = Some(digits("fp", value)?),
+                "n" => kind = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+        Ok(FiscalCode {
+            time: time.ok_or(missing("t"))?,
+            total_kopecks: total_kopecks.ok_or(missing("s"))?,
+            fiscal_drive: fiscal_drive.ok_or(missing("fn"))?,
+            document: document.ok_or(missing("i"))?,
+            sign: sign.ok_or(missing("fp"))?,
+            kind,
+        })
+    }
+
+    pub fn same_document(&self, other: &FiscalCode) -> bool {
+        self.fiscal_drive == other.fiscal_drive
+            && self.document == other.document
+            && self.sign == other.sign
+    }
+
+    pub fn entry(&self, user_id: UserId, name: String) -> Result<Entry, FailureError> {
+        let price = (self.total_kopecks + 50) / 100;
+        if price <= 0 || price > i64::from(i32::max_value()) {
+            return Err(invalid(format!(
+                "\"s\" is out of range: {} kopecks",
+                self.total_kopecks
+            )));
+        }
+        let mut entry = Entry::new(
+            user_id,
+            Product {
+                name,
+                price: price as i32,
+            },
+        );
+        entry.time = self.time;
+        Ok(entry)
+    }
+}
+
+impl Receipt {
+    pub fn new(user_id: UserId, entry_id: EntryId, code: FiscalCode) -> Receipt {
+        Receipt {
+            id: ReceiptId::generate(),
+            user_id,
+            entry_id,
+            code,
+            added: ::chrono::offset::Local::now().naive_local(),
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveDateTime, FailureError> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(&format!("{}00", value), "%Y%m%dT%H%M%S"))
+        .map_err(|_| invalid(format!("\"t\" is not a receipt time: {}", value)))
+}
+
+fn parse_kopecks(value: &str) -> Result<i64, FailureError> {
+    let mut parts = value.splitn(2, '.');
+    let rubles = parts.next().unwrap_or("");
+    let kopecks = parts.next().unwrap_or("0");
+    let is_number = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    if !is_number(rubles) || !is_number(kopecks) || kopecks.len() > 2 {
+        return Err(invalid(format!("\"s\" is not an amount: {}", value)));
+    }
+    let rubles: i64 = rubles
+        .parse()
+        .map_err(|_| invalid(format!("\"s\" is not an amount: {}", value)))?;
+    let kopecks: i64 = format!("{:0<2}", kopecks)
+        .parse()
+        .map_err(|_| invalid(format!("\"s\" is not an amount: {}", value)))?;
+    let total = rubles
+        .checked_mul(100)
+        .and_then(|total| total.checked_add(kopecks));
+    match total {
+        Some(total) if total > 0 => Ok(total),
+        Some(_) => Err(invalid(format!("\"s\" must be positive: {}", value))),
+        None => Err(invalid(format!("\"s\" is too large: {}", value))),
+    }
+}
+
+fn digits(key: &str, value: &str) -> Result<String, FailureError> {
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid(format!("\"{}\" must be a number: {}", key, value)));
+    }
+    Ok(value.to_owned())
+}
+
+fn missing(key: &str) -> FailureError {
+    invalid(format!("\"{}\" is missing", key))
+}
+
+fn invalid(reason: String) -> FailureError {
+    AppError::InvalidReceipt { reason }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fiscal_qr_payload() {
+        let text =
+            "продукты t=20180518T220500&s=975.88&fn=8710000101125654&i=99456&fp=1250448795&n=1";
+        let code = FiscalCode::parse(FiscalCode::find(text).unwrap()).unwrap();
+        assert_eq!(code.time, NaiveDate::from_ymd(2018, 5, 18).and_hms(22, 5, 0));
+        assert_eq!(code.total_kopecks, 97588);
+        assert_eq!(code.fiscal_drive, "8710000101125654");
+        assert_eq!(code.document, "99456");
+        assert_eq!(code.sign, "1250448795");
+        assert_eq!(code.kind, Some("1".to_owned()));
+
+        let entry = code
+            .entry(UserId::new("user".to_owned()), "продукты".to_owned())
+            .unwrap();
+        assert_eq!(entry.product.price, 976);
+        assert_eq!(entry.time, code.time);
+    }
+
+    #[test]
+    fn accepts_short_time_and_whole_amounts() {
+        let code = FiscalCode::parse("t=20181020T1015&s=120.5&fn=1&i=2&fp=3").unwrap();
+        assert_eq!(code.time, NaiveDate::from_ymd(2018, 10, 20).and_hms(10, 15, 0));
+        assert_eq!(code.total_kopecks, 12050);
+        let whole = FiscalCode::parse("t=20181020T1015&s=120&fn=1&i=2&fp=3").unwrap();
+        assert_eq!(whole.total_kopecks, 12000);
+        assert!(FiscalCode::parse("t=20181020T1015&s=120&fn=1&i=2").is_err());
+        assert!(FiscalCode::parse("t=yesterday&s=120&fn=1&i=2&fp=3").is_err());
+    }
+
+    #[test]
+    fn rejects_amounts_out_of_range() {
+        for amount in &["0", "0.00", "-5", "-0.5", "92233720368547758.08"] {
+            let payload = format!("t=20181020T1015&s={}&fn=1&i=2&fp=3", amount);
+            assert!(FiscalCode::parse(&payload).is_err(), "{}", amount);
+        }
+
+        let user = UserId::new("user".to_owned());
+        let tiny = FiscalCode::parse("t=20181020T1015&s=0.01&fn=1&i=2&fp=3").unwrap();
+        assert!(tiny.entry(user.clone(), "хлеб".to_owned()).is_err());
+        let huge = FiscalCode::parse("t=20181020T1015&s=21474836480&fn=1&i=2&fp=3").unwrap();
+        assert!(huge.entry(user.clone(), "дом".to_owned()).is_err());
+        let largest = FiscalCode::parse("t=20181020T1015&s=2147483647&fn=1&i=2&fp=3").unwrap();
+        assert_eq!(
+            largest.entry(user, "дом".to_owned()).unwrap().product.price,
+            i32::max_value()
+        );
+    }
+}
@@ -1,6 +1,11 @@
-use chrono::naive::NaiveDateTime;
+use chrono::prelude::*;
+use failure::Error as FailureError;
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
 
-use super::{SmsId, UserId};
+use std::str::FromStr;
+
+use super::{SmsId, User, UserId};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sms {
@@ -8,7 +13,9 @@ pub struct Sms {
     pub user: UserId,
     pub from: String,
     pub text: String,
-    pub time: NaiveDateTime,
+    #[serde(deserialize_with = "deserialize_utc")]
+    pub time: DateTime<Utc>,
+    pub client_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,7 +23,57 @@ pub struct NewSms {
     pub user: UserId,
     pub from: String,
     pub text: String,
-    pub time: NaiveDateTime,
+    #[serde(deserialize_with = "deserialize_utc")]
+    pub time: DateTime<Utc>,
+    pub client_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SmsFilter {
+    pub sender: Option<String>,
+    pub text: Option<String>,
+    pub from: Option<NaiveDateTime>,
+    pub till: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SmsStatus {
+    Stored(SmsId),
+    Duplicate(SmsId),
+}
+
+impl Sms {
+    pub fn dedup_key(&self) -> String {
+        dedup_key(&self.client_id, &self.from, &self.text, self.time)
+    }
+}
+
+impl NewSms {
+    pub fn parse_timestamp_millis(raw: &str) -> Result<DateTime<Utc>, FailureError> {
+        let timestamp_millis: i64 = raw.trim().parse()?;
+        Utc.timestamp_millis_opt(timestamp_millis)
+            .single()
+            .ok_or(format_err!("invalid sms timestamp {}", timestamp_millis))
+    }
+
+    pub fn dedup_key(&self) -> String {
+        dedup_key(&self.client_id, &self.from, &self.text, self.time)
+    }
+}
+
+impl SmsFilter {
+    pub fn matches(&self, sms: &Sms, user: &User) -> bool {
+        let local_time = user.local_time(sms.time);
+        self.sender
+            .as_ref()
+            .map_or(true, |sender| sms.from.to_lowercase() == sender.to_lowercase())
+            && self
+                .text
+                .as_ref()
+                .map_or(true, |text| sms.text.to_lowercase().contains(&text.to_lowercase()))
+            && self.from.map_or(true, |from| local_time >= from)
+            && self.till.map_or(true, |till| local_time <= till)
+    }
 }
 
 impl Into<Sms> for NewSms {
@@ -27,6 +84,113 @@ impl Into<Sms> for NewSms {
             from: self.from,
             text: self.text,
             time: self.time,
+            client_id: self.client_id,
+        }
+    }
+}
+
+fn deserialize_utc<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    if let Ok(time) = DateTime::parse_from_rfc3339(&raw) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDateTime::from_str(&raw)
+        .map(|naive| DateTime::from_utc(naive, Utc))
+        .map_err(::serde::de::Error::custom)
+}
+
+fn dedup_key(client_id: &Option<String>, from: &str, text: &str, time: DateTime<Utc>) -> String {
+    match *client_id {
+        Some(ref client_id) => format!("client:{}", client_id),
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.input(from.as_bytes());
+            hasher.input(&[0u8]);
+            hasher.input(text.as_bytes());
+            hasher.input(&[0u8]);
+            hasher.input(format!("{}", time.timestamp_millis()).as_bytes());
+            format!("sha256:{:x}", hasher.result())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_sms(client_id: Option<&str>, text: &str) -> NewSms {
+        NewSms {
+            user: UserId::new("user".to_owned()),
+            from: "bank".to_owned(),
+            text: text.to_owned(),
+            time: Utc.ymd(2018, 10, 1).and_hms_milli(12, 0, 0, 250),
+            client_id: client_id.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn deduplicates_by_client_id_or_content() {
+        assert_eq!(
+            new_sms(Some("42"), "first").dedup_key(),
+            new_sms(Some("42"), "second").dedup_key()
+        );
+        assert_eq!(
+            new_sms(None, "same").dedup_key(),
+            new_sms(None, "same").dedup_key()
+        );
+        assert_ne!(
+            new_sms(None, "first").dedup_key(),
+            new_sms(None, "second").dedup_key()
+        );
+
+        let stored: Sms = new_sms(None, "same").into();
+        assert_eq!(stored.dedup_key(), new_sms(None, "same").dedup_key());
+    }
+
+    #[test]
+    fn filters_in_user_local_time() {
+        let sms: Sms = new_sms(None, "Card payment 100 RUB").into();
+        let mut user = User::with_telegram_id(::accounting::TelegramId(1));
+        user.offset = Some(NaiveDateTime::from_timestamp(3 * 60 * 60, 0));
+        let filter = SmsFilter {
+            sender: Some("BANK".to_owned()),
+            text: Some("payment".to_owned()),
+            from: Some(NaiveDate::from_ymd(2018, 10, 1).and_hms(15, 0, 0)),
+            till: None,
+        };
+        assert!(filter.matches(&sms, &user));
+
+        user.offset = None;
+        assert!(!filter.matches(&sms, &user));
+    }
+
+    #[test]
+    fn keeps_milliseconds_of_uploaded_timestamps() {
+        let first = NewSms::parse_timestamp_millis("1538395200250").unwrap();
+        let second = NewSms::parse_timestamp_millis("1538395200999").unwrap();
+        assert_eq!(first, Utc.ymd(2018, 10, 1).and_hms_milli(12, 0, 0, 250));
+        assert_eq!(first.timestamp_millis(), 1538395200250);
+        assert_ne!(first, second);
+        assert!(NewSms::parse_timestamp_millis("yesterday").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_storage_formats() {
+        let sms: Sms = new_sms(Some("42"), "text").into();
+
+        let json = ::serde_json::to_string(&sms).unwrap();
+        let from_json: Sms = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json.time, sms.time);
+
+        let binary = ::bincode::serialize(&sms, ::bincode::Infinite).unwrap();
+        let from_binary: Sms = ::bincode::deserialize(&binary).unwrap();
+        assert_eq!(from_binary.time, sms.time);
+    }
+
+    #[test]
+    fn reads_legacy_naive_timestamps_as_utc() {
+        let legacy = r#"{"id":"1","user":"user","from":"bank","text":"text","time":"2018-10-01T12:00:00","client_id":null}"#;
+        let sms: Sms = ::serde_json::from_str(legacy).unwrap();
+        assert_eq!(sms.time, Utc.ymd(2018, 10, 1).and_hms(12, 0, 0));
+    }
+}
@@ -0,0 +1,316 @@
+use chrono::naive::NaiveDateTime;
+use failure::Error as FailureError;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::{Entry, EntryId, SettlementId, SplitId, UserId};
+use error::{AppError, SplitError};
+use i18n::Locale;
+
+const USER_KEY_PREFIX: &'static str = "user:";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Share {
+    pub participant: String,
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Split {
+    pub id: SplitId,
+    pub user_id: UserId,
+    pub entry_id: EntryId,
+    pub payer: String,
+    pub amount: i32,
+    pub shares: Vec<Share>,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settlement {
+    pub id: SettlementId,
+    pub user_id: UserId,
+    pub from: String,
+    pub to: String,
+    pub amount: i32,
+    pub time: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Balance {
+    pub participant: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transfer {
+    pub from: String,
+    pub to: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SettleUp {
+    pub balances: Vec<Balance>,
+    pub transfers: Vec<Transfer>,
+}
+
+impl Share {
+    pub fn parse_list(raw: &[&str]) -> Result<Vec<Share>, FailureError> {
+        let shares = raw
+            .iter()
+            .map(|share| Share::from_str(share))
+            .collect::<Result<Vec<Share>, FailureError>>()?;
+        if shares.is_empty() {
+            return Err(invalid(SplitError::ParticipantRequired));
+        }
+        Ok(shares)
+    }
+}
+
+impl FromStr for Share {
+    type Err = FailureError;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let mut parts = raw.trim().splitn(2, ':');
+        let participant = parts.next().unwrap_or("").trim();
+        if participant.is_empty() {
+            return Err(invalid(SplitError::EmptyParticipant));
+        }
+        let weight = match parts.next() {
+            Some(weight) => u32::from_str(weight.trim())
+                .map_err(|_| invalid(SplitError::ShareNotANumber(participant.to_owned())))?,
+            None => 1,
+        };
+        if weight == 0 {
+            return Err(invalid(SplitError::ShareNotPositive(
+                participant.to_owned(),
+            )));
+        }
+        Ok(Share {
+            participant: participant.to_owned(),
+            weight,
+        })
+    }
+}
+
+impl Split {
+    pub fn new(user_id: UserId, entry: &Entry, payer: &str, shares: Vec<Share>) -> Split {
+        Split {
+            id: SplitId::generate(),
+            entry_id: entry.id.clone(),
+            payer: participant_key(payer, &user_id),
+            amount: entry.product.price,
+            shares: shares
+                .into_iter()
+                .map(|share| Share {
+                    participant: participant_key(&share.participant, &user_id),
+                    weight: share.weight,
+                }).collect(),
+            created: ::chrono::offset::Local::now().naive_local(),
+            user_id,
+        }
+    }
+
+    pub fn parts(&self) -> Vec<(&str, i64)> {
+        let total_weight: i64 = self.shares.iter().map(|s| s.weight as i64).sum();
+        let amount = self.amount as i64;
+        let mut parts: Vec<(&str, i64)> = self
+            .shares
+            .iter()
+            .map(|s| (s.participant.as_str(), amount * s.weight as i64 / total_weight))
+            .collect();
+        let mut remainder = amount - parts.iter().map(|&(_, part)| part).sum::<i64>();
+        for part in parts.iter_mut() {
+            if remainder == 0 {
+                break;
+            }
+            part.1 += remainder.signum();
+            remainder -= remainder.signum();
+        }
+        parts
+    }
+}
+
+impl Settlement {
+    pub fn new(user_id: UserId, from: &str, to: &str, amount: i32) -> Settlement {
+        Settlement {
+            id: SettlementId::generate(),
+            from: participant_key(from, &user_id),
+            to: participant_key(to, &user_id),
+            user_id,
+            amount,
+            time: ::chrono::offset::Local::now().naive_local(),
+        }
+    }
+}
+
+impl SettleUp {
+    pub fn new(splits: &[Split], settlements: &[Settlement]) -> SettleUp {
+        let mut net: HashMap<&str, i64> = HashMap::new();
+        for split in splits {
+            *net.entry(split.payer.as_str()).or_insert(0) += split.amount as i64;
+            for (participant, part) in split.parts() {
+                *net.entry(participant).or_insert(0) -= part;
+            }
+        }
+        for settlement in settlements {
+            *net.entry(settlement.from.as_str()).or_insert(0) += settlement.amount as i64;
+            *net.entry(settlement.to.as_str()).or_insert(0) -= settlement.amount as i64;
+        }
+        let mut balances: Vec<Balance> = net
+            .into_iter()
+            .filter(|&(_, amount)| amount != 0)
+            .map(|(participant, amount)| Balance {
+                participant: participant.to_owned(),
+                amount,
+            }).collect();
+        balances.sort_by(|a, b| {
+            b.amount
+                .cmp(&a.amount)
+                .then(a.participant.cmp(&b.participant))
+        });
+        let transfers = transfers(&balances);
+        SettleUp {
+            balances,
+            transfers,
+        }
+    }
+}
+
+pub fn user_key(user: &UserId) -> String {
+    format!("{}{}", USER_KEY_PREFIX, user.0)
+}
+
+pub fn user_of_key(key: &str) -> Option<UserId> {
+    if key.starts_with(USER_KEY_PREFIX) {
+        Some(UserId::new(key[USER_KEY_PREFIX.len()..].to_owned()))
+    } else {
+        None
+    }
+}
+
+fn participant_key(name: &str, user: &UserId) -> String {
+    let name = name.trim();
+    let lowercase = name.to_lowercase();
+    if Locale::all()
+        .iter()
+        .any(|locale| locale.catalogue().me == lowercase)
+    {
+        user_key(user)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn transfers(balances: &[Balance]) -> Vec<Transfer> {
+    let mut creditors: Vec<(String, i64)> = balances
+        .iter()
+        .filter(|b| b.amount > 0)
+        .map(|b| (b.participant.clone(), b.amount))
+        .collect();
+    let mut debtors: Vec<(String, i64)> = balances
+        .iter()
+        .filter(|b| b.amount < 0)
+        .map(|b| (b.participant.clone(), -b.amount))
+        .collect();
+    let mut transfers = Vec::new();
+    while !creditors.is_empty() && !debtors.is_empty() {
+        creditors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        debtors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let amount = creditors[0].1.min(debtors[0].1);
+        transfers.push(Transfer {
+            from: debtors[0].0.clone(),
+            to: creditors[0].0.clone(),
+            amount,
+        });
+        creditors[0].1 -= amount;
+        debtors[0].1 -= amount;
+        creditors.retain(|c| c.1 > 0);
+        debtors.retain(|d| d.1 > 0);
+    }
+    transfers
+}
+
+fn invalid(reason: SplitError) -> FailureError {
+    AppError::InvalidSplit { reason }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use accounting::Product;
+
+    fn split(payer: &str, price: i32, shares: &[&str]) -> Split {
+        let user = UserId::new("user".to_owned());
+        let entry = Entry::new(
+            user.clone(),
+            Product {
+                name: "ужин".to_owned(),
+                price,
+            },
+        );
+        Split::new(user, &entry, payer, Share::parse_list(shares).unwrap())
+    }
+
+    #[test]
+    fn splits_by_shares_without_losing_rubles() {
+        let equally = split("я", 100, &["я", "Петя", "Вася"]);
+        assert_eq!(
+            equally.parts(),
+            vec![("user:user", 34), ("Петя", 33), ("Вася", 33)]
+        );
+        let by_shares = split("Me", 300, &["я:2", "Петя"]);
+        assert_eq!(by_shares.payer, "user:user");
+        assert_eq!(by_shares.parts(), vec![("user:user", 200), ("Петя", 100)]);
+        assert!(Share::parse_list(&["Петя:0"]).is_err());
+        assert!(Share::parse_list(&[]).is_err());
+    }
+
+    #[test]
+    fn suggests_transfers_that_settle_all_debts() {
+        let splits = vec![
+            split("я", 300, &["я", "Петя", "Вася"]),
+            split("Петя", 90, &["я", "Петя", "Вася"]),
+        ];
+        let settlements = vec![Settlement::new(
+            UserId::new("user".to_owned()),
+            "Вася",
+            "me",
+            50,
+        )];
+        let settle_up = SettleUp::new(&splits, &settlements);
+        assert_eq!(
+            settle_up.balances,
+            vec![
+                Balance {
+                    participant: "user:user".to_owned(),
+                    amount: 120,
+                },
+                Balance {
+                    participant: "Петя".to_owned(),
+                    amount: -40,
+                },
+                Balance {
+                    participant: "Вася".to_owned(),
+                    amount: -80,
+                },
+            ]
+        );
+        assert_eq!(
+            settle_up.transfers,
+            vec![
+                Transfer {
+                    from: "Вася".to_owned(),
+                    to: "user:user".to_owned(),
+                    amount: 80,
+                },
+                Transfer {
+                    from: "Петя".to_owned(),
+                    to: "user:user".to_owned(),
+                    amount: 40,
+                },
+            ]
+        );
+    }
+}
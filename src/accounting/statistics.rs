@@ -82,16 +82,24 @@ impl<'r> Report<'r> {
 
 impl Statistics {
     pub fn new(entries: Vec<Entry>, categories: Vec<Category>) -> Statistics {
+        Statistics::at(entries, categories, ::chrono::offset::Local::now().naive_local())
+    }
+
+    pub fn at(entries: Vec<Entry>, categories: Vec<Category>, now: NaiveDateTime) -> Statistics {
         Statistics {
             entries,
             categories: categories
                 .into_iter()
                 .map(|c| (c.product, c.category))
                 .collect(),
-            now: ::chrono::offset::Local::now().naive_local(),
+            now,
         }
     }
 
+    pub fn add(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
     pub fn report(&self, period: TimePeriod) -> Result<Option<Report>, FailureError> {
         debug!("report for {:?}", &period);
         let (from, till) = self.period(period);
@@ -182,36 +190,38 @@ impl Statistics {
     }
 
     fn period(&self, period: TimePeriod) -> (NaiveDateTime, NaiveDateTime) {
-        match period {
+        period.bounds(self.now)
+    }
+}
+
+impl TimePeriod {
+    pub fn bounds(&self, now: NaiveDateTime) -> (NaiveDateTime, NaiveDateTime) {
+        match *self {
             TimePeriod::Today => (
-                self.now.date().and_time(start_of_day()),
-                self.now.date().and_time(end_of_day()),
+                now.date().and_time(start_of_day()),
+                now.date().and_time(end_of_day()),
             ),
             TimePeriod::ThisWeek => (
-                self.this_week(Weekday::Mon).and_time(start_of_day()),
-                self.this_week(Weekday::Sun).and_time(end_of_day()),
+                this_week(now, Weekday::Mon).and_time(start_of_day()),
+                this_week(now, Weekday::Sun).and_time(end_of_day()),
             ),
             TimePeriod::ThisMonth => (
-                self.now
-                    .date()
-                    .with_day(1)
-                    .unwrap()
-                    .and_time(start_of_day()),
-                last_day_of_month(self.now.date()).and_time(end_of_day()),
+                now.date().with_day(1).unwrap().and_time(start_of_day()),
+                last_day_of_month(now.date()).and_time(end_of_day()),
             ),
             TimePeriod::ThisYear => (
-                NaiveDate::from_ymd(self.now.year(), 1, 1).and_time(start_of_day()),
-                NaiveDate::from_ymd(self.now.year() + 1, 1, 1)
+                NaiveDate::from_ymd(now.year(), 1, 1).and_time(start_of_day()),
+                NaiveDate::from_ymd(now.year() + 1, 1, 1)
                     .pred()
                     .and_time(end_of_day()),
             ),
             TimePeriod::Any(from, to) => (from.and_time(start_of_day()), to.and_time(end_of_day())),
         }
     }
+}
 
-    fn this_week(&self, day: Weekday) -> NaiveDate {
-        NaiveDate::from_isoywd(self.now.year(), self.now.iso_week().week(), day)
-    }
+fn this_week(now: NaiveDateTime, day: Weekday) -> NaiveDate {
+    NaiveDate::from_isoywd(now.year(), now.iso_week().week(), day)
 }
 
 fn subperiods(from: NaiveDate, to: NaiveDate) -> Option<Vec<(NaiveDate, NaiveDate)>> {
@@ -0,0 +1,190 @@
+use chrono::naive::NaiveDateTime;
+use failure::Error as FailureError;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{LoginId, TokenId, UserId};
+use error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    SmsUpload,
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiToken {
+    pub id: TokenId,
+    pub user_id: UserId,
+    pub name: String,
+    pub salt: String,
+    pub hash: String,
+    pub scopes: Vec<Scope>,
+    pub created: NaiveDateTime,
+    pub last_used: Option<NaiveDateTime>,
+    pub revoked: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginCode {
+    pub id: LoginId,
+    pub user_id: UserId,
+    pub hash: String,
+    pub expires: NaiveDateTime,
+    pub used: bool,
+}
+
+impl ApiToken {
+    pub fn issue(user_id: UserId, name: String, scopes: Vec<Scope>) -> (ApiToken, String) {
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let token = ApiToken::with_secret(user_id, name, scopes, &secret);
+        (token, secret)
+    }
+
+    pub fn with_secret(
+        user_id: UserId,
+        name: String,
+        scopes: Vec<Scope>,
+        secret: &str,
+    ) -> ApiToken {
+        let salt = format!("{}", Uuid::new_v4().simple());
+        let hash = hash(&salt, secret);
+        ApiToken {
+            id: TokenId::generate(),
+            user_id,
+            name,
+            salt,
+            hash,
+            scopes,
+            created: ::chrono::offset::Local::now().naive_local(),
+            last_used: None,
+            revoked: false,
+        }
+    }
+
+    pub fn verify(&self, secret: &str) -> bool {
+        !self.revoked && constant_time_eq(hash(&self.salt, secret).as_bytes(), self.hash.as_bytes())
+    }
+
+    pub fn needs_touch(&self, now: NaiveDateTime) -> bool {
+        self.last_used
+            .map(|last_used| now - last_used > ::chrono::Duration::minutes(1))
+            .unwrap_or(true)
+    }
+
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+impl LoginCode {
+    pub fn issue(user_id: UserId, lifetime: ::chrono::Duration) -> (LoginCode, String) {
+        let code = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let login = LoginCode {
+            id: LoginId::generate(),
+            user_id,
+            hash: hash("", &code),
+            expires: ::chrono::offset::Local::now().naive_local() + lifetime,
+            used: false,
+        };
+        (login, code)
+    }
+
+    pub fn redeemable(&self, code: &str, now: NaiveDateTime) -> bool {
+        !self.used
+            && now < self.expires
+            && constant_time_eq(hash("", code).as_bytes(), self.hash.as_bytes())
+    }
+}
+
+impl Scope {
+    pub fn all() -> Vec<Scope> {
+        vec![Scope::SmsUpload, Scope::Read, Scope::Write]
+    }
+
+    pub fn parse_list(raw: &str) -> Result<Vec<Scope>, FailureError> {
+        raw.split(',')
+            .map(str::trim)
+            .filter(|scope| !scope.is_empty())
+            .map(Scope::from_str)
+            .collect()
+    }
+}
+
+impl FromStr for Scope {
+    type Err = FailureError;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw {
+            "sms-upload" => Ok(Scope::SmsUpload),
+            "read" => Ok(Scope::Read),
+            "write" => Ok(Scope::Write),
+            _ => Err(AppError::InvalidEnumVariant.into()),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Scope::SmsUpload => write!(f, "sms-upload"),
+            Scope::Read => write!(f, "read"),
+            Scope::Write => write!(f, "write"),
+        }
+    }
+}
+
+pub fn hash(salt: &str, secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(salt.as_bytes());
+    hasher.input(secret.as_bytes());
+    format!("{:x}", hasher.result())
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_only_issued_secret() {
+        let (token, secret) = ApiToken::issue(UserId::generate(), "phone".to_owned(), Scope::all());
+        assert!(token.verify(&secret));
+        assert!(!token.verify("wrong"));
+        assert!(!token.hash.contains(&secret));
+
+        let revoked = ApiToken {
+            revoked: true,
+            ..token
+        };
+        assert!(!revoked.verify(&secret));
+    }
+
+    #[test]
+    fn redeems_login_code_once_before_expiry() {
+        let (login, code) = LoginCode::issue(UserId::generate(), ::chrono::Duration::minutes(10));
+        let now = ::chrono::offset::Local::now().naive_local();
+        assert!(login.redeemable(&code, now));
+        assert!(!login.redeemable(&code, now + ::chrono::Duration::minutes(11)));
+        assert!(!LoginCode { used: true, ..login }.redeemable(&code, now));
+    }
+
+    #[test]
+    fn parses_scopes() {
+        assert_eq!(
+            Scope::parse_list("sms-upload, read").unwrap(),
+            vec![Scope::SmsUpload, Scope::Read]
+        );
+        assert!(Scope::parse_list("admin").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use chrono::naive::NaiveDateTime;
+use chrono::{DateTime, Duration, Utc};
 
 use super::UserId;
+use i18n::Locale;
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub struct TelegramId(pub i64);
@@ -14,6 +16,7 @@ pub struct User {
     pub telegram_id: Option<TelegramId>,
     pub android_auth_token: Option<AndroidAuth>,
     pub offset: Option<NaiveDateTime>,
+    pub locale: Option<Locale>,
 }
 
 impl User {
@@ -23,6 +26,12 @@ impl User {
             telegram_id: Some(telegram_id),
             offset: None,
             android_auth_token: None,
+            locale: None,
         }
     }
+
+    pub fn local_time(&self, time: DateTime<Utc>) -> NaiveDateTime {
+        let offset_seconds = self.offset.map(|offset| offset.timestamp()).unwrap_or(0);
+        time.naive_utc() + Duration::seconds(offset_seconds)
+    }
 }
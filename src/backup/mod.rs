@@ -0,0 +1,459 @@
+use chrono::prelude::*;
+use failure::Error as FailureError;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use config::Config;
+use error::AppError;
+
+const MANIFEST_NAME: &'static str = "manifest.json";
+const ARCHIVE_PREFIX: &'static str = "backup-";
+const ARCHIVE_SUFFIX: &'static str = ".tar.gz";
+const ARCHIVE_TIME_FORMAT: &'static str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub created: NaiveDateTime,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub manifest: Manifest,
+}
+
+#[derive(Debug)]
+pub struct DailyBackup {
+    data_path: PathBuf,
+    backup_path: PathBuf,
+    retention: usize,
+}
+
+pub fn backup_path(config: &Config) -> PathBuf {
+    match config.backup_path {
+        Some(ref path) => PathBuf::from(path),
+        None => {
+            let mut path = PathBuf::from(&config.data_path);
+            path.push("backups");
+            path
+        }
+    }
+}
+
+pub fn create(data_path: &Path, backup_path: &Path) -> Result<Backup, FailureError> {
+    info!("creating backup of {:?} in {:?}", data_path, backup_path);
+    let tables = read_tables(data_path)?;
+    let created = ::chrono::offset::Local::now().naive_local();
+    let manifest = Manifest {
+        created,
+        files: tables
+            .iter()
+            .map(|&(ref name, ref content)| ManifestFile {
+                name: name.clone(),
+                size: content.len() as u64,
+                sha256: sha256(content),
+            }).collect(),
+    };
+
+    fs::create_dir_all(backup_path)?;
+    let archive_path = free_archive_path(backup_path, created)?;
+
+    let mut temporary_path = archive_path.clone();
+    temporary_path.set_extension("partial");
+    {
+        let file = File::create(&temporary_path)?;
+        let mut builder = ::tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let manifest_json = ::serde_json::to_vec_pretty(&manifest)?;
+        append(&mut builder, MANIFEST_NAME, &manifest_json, created)?;
+        for (name, content) in &tables {
+            append(&mut builder, name, content, created)?;
+        }
+        let mut encoder = builder.into_inner()?;
+        encoder.flush()?;
+        encoder.finish()?.sync_all()?;
+    }
+    fs::rename(&temporary_path, &archive_path)?;
+
+    info!("backup {:?} created", &archive_path);
+    Ok(Backup {
+        path: archive_path,
+        manifest,
+    })
+}
+
+pub fn list(backup_path: &Path) -> Result<Vec<Backup>, FailureError> {
+    debug!("listing backups in {:?}", backup_path);
+    let mut backups = Vec::new();
+    if !backup_path.is_dir() {
+        return Ok(backups);
+    }
+    for archive_path in archive_paths(backup_path)? {
+        match read_archive(&archive_path) {
+            Ok((manifest, _)) => backups.push(Backup {
+                path: archive_path,
+                manifest,
+            }),
+            Err(e) => warn!("skipping unreadable backup {:?}: {}", &archive_path, e),
+        }
+    }
+    Ok(backups)
+}
+
+pub fn verify(archive_path: &Path) -> Result<Manifest, FailureError> {
+    debug!("verifying backup {:?}", archive_path);
+    let (manifest, _) = read_verified_archive(archive_path)?;
+    Ok(manifest)
+}
+
+pub fn restore(archive_path: &Path, data_path: &Path) -> Result<Manifest, FailureError> {
+    info!("restoring backup {:?} into {:?}", archive_path, data_path);
+    let (manifest, mut files) = read_verified_archive(archive_path)?;
+
+    fs::create_dir_all(data_path)?;
+    for file in &manifest.files {
+        let content = files.remove(&file.name).ok_or(AppError::BackupCorrupted {
+            reason: format!("file \"{}\" is missing", &file.name),
+        })?;
+        let mut table_path = data_path.to_path_buf();
+        table_path.push(&file.name);
+        let mut restored_path = data_path.to_path_buf();
+        restored_path.push(format!("{}.restoring", &file.name));
+        {
+            let mut restored = File::create(&restored_path)?;
+            restored.write_all(&content)?;
+            restored.sync_all()?;
+        }
+        fs::rename(&restored_path, &table_path)?;
+        debug!("restored {:?}", &table_path);
+    }
+    for (name, table_path) in table_paths(data_path)? {
+        if manifest.files.iter().all(|file| file.name != name) {
+            fs::remove_file(&table_path)?;
+            info!("removed {:?}, it is not in the backup", &table_path);
+        }
+    }
+
+    Ok(manifest)
+}
+
+pub fn prune(backup_path: &Path, retention: usize) -> Result<Vec<PathBuf>, FailureError> {
+    debug!("pruning backups in {:?} keeping {}", backup_path, retention);
+    if retention == 0 {
+        return Err(AppError::InvalidBackupRetention.into());
+    }
+    let mut removed = Vec::new();
+    if !backup_path.is_dir() {
+        return Ok(removed);
+    }
+    let archives = archive_paths(backup_path)?;
+    let number_to_remove = archives.len().saturating_sub(retention);
+    for archive_path in archives.into_iter().take(number_to_remove) {
+        fs::remove_file(&archive_path)?;
+        info!("removed old backup {:?}", &archive_path);
+        removed.push(archive_path);
+    }
+    Ok(removed)
+}
+
+impl DailyBackup {
+    pub fn from_config(config: &Config) -> Option<DailyBackup> {
+        if !config.daily_backup.unwrap_or(false) {
+            return None;
+        }
+        Some(DailyBackup {
+            data_path: PathBuf::from(&config.data_path),
+            backup_path: backup_path(config),
+            retention: config.backup_retention.unwrap_or(7),
+        })
+    }
+
+    pub fn run_if_due(&self) -> Result<(), FailureError> {
+        let today = ::chrono::offset::Local::now().naive_local().date();
+        let latest = archive_paths(&self.backup_path)
+            .unwrap_or_default()
+            .last()
+            .and_then(|path| archive_time(path));
+        if let Some(latest) = latest {
+            if latest.date() >= today {
+                return Ok(());
+            }
+        }
+        create(&self.data_path, &self.backup_path)?;
+        prune(&self.backup_path, self.retention)?;
+        Ok(())
+    }
+}
+
+fn read_tables(data_path: &Path) -> Result<Vec<(String, Vec<u8>)>, FailureError> {
+    let mut tables = Vec::new();
+    for (name, path) in table_paths(data_path)? {
+        let mut content = Vec::new();
+        File::open(&path)?.read_to_end(&mut content)?;
+        tables.push((name, content));
+    }
+    Ok(tables)
+}
+
+fn table_paths(data_path: &Path) -> Result<Vec<(String, PathBuf)>, FailureError> {
+    if !data_path.is_dir() {
+        return Err(AppError::InvalidRegistryPath {
+            used_path: data_path.to_path_buf(),
+        }.into());
+    }
+    let mut tables = Vec::new();
+    for dir_entry in fs::read_dir(data_path)? {
+        let path = dir_entry?.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        if !path.is_file() || !name.ends_with(".table") || name.ends_with("_backup.table") {
+            continue;
+        }
+        tables.push((name, path));
+    }
+    tables.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(tables)
+}
+
+fn append<W: Write>(
+    builder: &mut ::tar::Builder<W>,
+    name: &str,
+    content: &[u8],
+    created: NaiveDateTime,
+) -> Result<(), FailureError> {
+    let mut header = ::tar::Header::new_gnu();
+    header.set_path(name)?;
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(created.timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append(&header, content)?;
+    Ok(())
+}
+
+fn read_archive(archive_path: &Path) -> Result<(Manifest, HashMap<String, Vec<u8>>), FailureError> {
+    let file = File::open(archive_path)?;
+    let mut archive = ::tar::Archive::new(GzDecoder::new(file));
+    let mut manifest = None;
+    let mut files = HashMap::new();
+    for archive_entry in archive.entries()? {
+        let mut archive_entry = archive_entry?;
+        let name = archive_entry
+            .path()?
+            .to_str()
+            .ok_or(AppError::BackupCorrupted {
+                reason: "file name is not valid utf-8".to_owned(),
+            })?.to_owned();
+        let mut content = Vec::new();
+        archive_entry.read_to_end(&mut content)?;
+        if name == MANIFEST_NAME {
+            manifest = Some(::serde_json::from_slice::<Manifest>(&content)?);
+        } else {
+            files.insert(name, content);
+        }
+    }
+    let manifest = manifest.ok_or(AppError::BackupCorrupted {
+        reason: "manifest is missing".to_owned(),
+    })?;
+    Ok((manifest, files))
+}
+
+fn read_verified_archive(
+    archive_path: &Path,
+) -> Result<(Manifest, HashMap<String, Vec<u8>>), FailureError> {
+    let (manifest, files) = read_archive(archive_path)?;
+    for file in &manifest.files {
+        if !is_table_name(&file.name) {
+            return Err(AppError::BackupCorrupted {
+                reason: format!("\"{}\" is not a table name", &file.name),
+            }.into());
+        }
+        let content = files.get(&file.name).ok_or(AppError::BackupCorrupted {
+            reason: format!("file \"{}\" is missing", &file.name),
+        })?;
+        if content.len() as u64 != file.size || sha256(content) != file.sha256 {
+            return Err(AppError::BackupCorrupted {
+                reason: format!("checksum mismatch for \"{}\"", &file.name),
+            }.into());
+        }
+    }
+    if files.len() != manifest.files.len() {
+        return Err(AppError::BackupCorrupted {
+            reason: "archive contains files not listed in manifest".to_owned(),
+        }.into());
+    }
+    Ok((manifest, files))
+}
+
+fn is_table_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => name.ends_with(".table"),
+        _ => false,
+    }
+}
+
+fn free_archive_path(backup_path: &Path, created: NaiveDateTime) -> Result<PathBuf, FailureError> {
+    let time = created.format(ARCHIVE_TIME_FORMAT).to_string();
+    for sequence in 0..1000 {
+        let name = match sequence {
+            0 => format!("{}{}{}", ARCHIVE_PREFIX, time, ARCHIVE_SUFFIX),
+            _ => format!("{}{}-{}{}", ARCHIVE_PREFIX, time, sequence, ARCHIVE_SUFFIX),
+        };
+        let mut archive_path = backup_path.to_path_buf();
+        archive_path.push(name);
+        if !archive_path.exists() {
+            return Ok(archive_path);
+        }
+    }
+    let mut archive_path = backup_path.to_path_buf();
+    archive_path.push(format!("{}{}{}", ARCHIVE_PREFIX, time, ARCHIVE_SUFFIX));
+    Err(AppError::BackupAlreadyExists { path: archive_path }.into())
+}
+
+fn archive_paths(backup_path: &Path) -> Result<Vec<PathBuf>, FailureError> {
+    let mut archives = Vec::new();
+    for dir_entry in fs::read_dir(backup_path)? {
+        let path = dir_entry?.path();
+        if let Some(key) = archive_key(&path) {
+            if path.is_file() {
+                archives.push((key, path));
+            }
+        }
+    }
+    archives.sort();
+    Ok(archives.into_iter().map(|(_, path)| path).collect())
+}
+
+fn archive_time(archive_path: &Path) -> Option<NaiveDateTime> {
+    archive_key(archive_path).map(|(time, _)| time)
+}
+
+fn archive_key(archive_path: &Path) -> Option<(NaiveDateTime, u32)> {
+    let name = archive_path.file_name()?.to_str()?;
+    if !name.starts_with(ARCHIVE_PREFIX) || !name.ends_with(ARCHIVE_SUFFIX) {
+        return None;
+    }
+    let stamp = &name[ARCHIVE_PREFIX.len()..name.len() - ARCHIVE_SUFFIX.len()];
+    let (time, sequence) = match stamp.get(15..) {
+        Some("") => (stamp, 0),
+        Some(rest) if rest.starts_with('-') => (&stamp[..15], rest[1..].parse().ok()?),
+        _ => return None,
+    };
+    let time = NaiveDateTime::parse_from_str(time, ARCHIVE_TIME_FORMAT).ok()?;
+    Some((time, sequence))
+}
+
+fn sha256(content: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(content);
+    format!("{:x}", hasher.result())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::TemporaryDir;
+
+    #[test]
+    fn parses_archive_time() {
+        assert_eq!(
+            archive_time(Path::new("/tmp/backup-20181231-235959.tar.gz")),
+            Some(NaiveDate::from_ymd(2018, 12, 31).and_hms(23, 59, 59))
+        );
+        assert_eq!(
+            archive_key(Path::new("/tmp/backup-20181231-235959-2.tar.gz")),
+            Some((NaiveDate::from_ymd(2018, 12, 31).and_hms(23, 59, 59), 2))
+        );
+        assert_eq!(archive_time(Path::new("/tmp/backup-latest.tar.gz")), None);
+        assert_eq!(archive_time(Path::new("/tmp/entries.table")), None);
+    }
+
+    #[test]
+    fn restores_created_backup() {
+        let base = TemporaryDir::new();
+        let mut data_path = base.clone();
+        data_path.push("data");
+        let mut backup_path = base.clone();
+        backup_path.push("backups");
+        fs::create_dir_all(&data_path).unwrap();
+        let mut table_path = data_path.clone();
+        table_path.push("entries.table");
+        fs::write(&table_path, b"{\"id\":\"1\"}\n").unwrap();
+
+        let backup = create(&data_path, &backup_path).unwrap();
+        let same_second = create(&data_path, &backup_path).unwrap();
+        assert!(backup.path != same_second.path);
+        fs::write(&table_path, b"").unwrap();
+        let mut later_table_path = data_path.clone();
+        later_table_path.push("splits.table");
+        fs::write(&later_table_path, b"{\"id\":\"2\"}\n").unwrap();
+        let manifest = restore(&backup.path, &data_path).unwrap();
+
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(fs::read(&table_path).unwrap(), b"{\"id\":\"1\"}\n".to_vec());
+        assert!(!later_table_path.exists());
+    }
+
+    #[test]
+    fn rejects_archives_with_unsafe_file_names() {
+        let base = TemporaryDir::new();
+        let mut data_path = base.clone();
+        data_path.push("data");
+        let mut archive_path = base.clone();
+        archive_path.push("backup-20181231-235959.tar.gz");
+        let created = NaiveDate::from_ymd(2018, 12, 31).and_hms(23, 59, 59);
+        let content = b"{\"id\":\"1\"}\n";
+        let manifest = Manifest {
+            created,
+            files: vec![ManifestFile {
+                name: "../escape.table".to_owned(),
+                size: content.len() as u64,
+                sha256: sha256(content),
+            }],
+        };
+        {
+            let file = File::create(&archive_path).unwrap();
+            let mut builder = ::tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            let manifest_json = ::serde_json::to_vec_pretty(&manifest).unwrap();
+            append(&mut builder, MANIFEST_NAME, &manifest_json, created).unwrap();
+            let mut header = ::tar::Header::new_gnu();
+            header.as_old_mut().name[..15].copy_from_slice(b"../escape.table");
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, &content[..]).unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+
+        assert!(restore(&archive_path, &data_path).is_err());
+        let mut escaped_path = base.clone();
+        escaped_path.push("escape.table");
+        assert!(!escaped_path.exists());
+        assert!(!data_path.exists());
+    }
+
+    #[test]
+    fn requires_positive_retention() {
+        let base = TemporaryDir::new();
+        assert!(prune(&base, 0).is_err());
+        assert!(prune(&base, 1).unwrap().is_empty());
+    }
+}
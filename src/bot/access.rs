@@ -0,0 +1,274 @@
+use failure::Error as FailureError;
+
+use std::str::FromStr;
+
+use super::reply::{Button, Reply};
+use accounting::{Access, AccessStatus, Actor};
+use config::Config;
+use error::AppError;
+use i18n::{fill, Locale};
+use registry::Registry;
+
+pub const PREFIX: &'static str = "access:";
+const REQUEST: &'static str = "access:request";
+
+pub fn admins(config: &Config) -> Vec<i64> {
+    config
+        .admin_telegram_users
+        .clone()
+        .unwrap_or_else(|| vec![config.telegram_user_id])
+}
+
+pub fn is_allowed(
+    config: &Config,
+    registry: &Registry,
+    telegram_id: i64,
+) -> Result<bool, FailureError> {
+    if admins(config).contains(&telegram_id) {
+        return Ok(true);
+    }
+    match registry.access(telegram_id)?.map(|access| access.status) {
+        Some(AccessStatus::Approved) => Ok(true),
+        Some(AccessStatus::Denied) | Some(AccessStatus::Revoked) => Ok(false),
+        Some(AccessStatus::Pending) | None => {
+            Ok(config.allowed_telegram_users.contains(&telegram_id))
+        }
+    }
+}
+
+pub fn unauthorized(
+    registry: &Registry,
+    telegram_id: i64,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    let text = locale.catalogue();
+    match registry.access(telegram_id)?.map(|access| access.status) {
+        Some(AccessStatus::Pending) => Ok(Reply::from(text.access_pending.to_owned())),
+        Some(AccessStatus::Denied) => Ok(Reply::from(text.access_denied.to_owned())),
+        _ => Ok(
+            Reply::from(text.access_greeting.to_owned()).with_buttons(vec![vec![
+                Button::callback(text.request_access, REQUEST.to_owned()),
+            ]]),
+        ),
+    }
+}
+
+pub fn callback(
+    data: &str,
+    author_id: i64,
+    author_name: &str,
+    config: &Config,
+    registry: &Registry,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    if data == REQUEST {
+        return request(author_id, author_name, config, registry, actor, locale);
+    }
+    let mut parts = data[PREFIX.len()..].splitn(2, ':');
+    let status = match parts.next() {
+        Some("approve") => AccessStatus::Approved,
+        Some("deny") => AccessStatus::Denied,
+        _ => return Err(wrong_bot_usage(locale)),
+    };
+    let telegram_id = parts
+        .next()
+        .and_then(|id| i64::from_str(id).ok())
+        .ok_or(wrong_bot_usage(locale))?;
+    decide(
+        telegram_id,
+        status,
+        author_id,
+        config,
+        registry,
+        actor,
+        locale,
+    )
+}
+
+pub fn access<'a, I>(
+    commands: &mut I,
+    config: &Config,
+    registry: &Registry,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<Reply, FailureError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let admin = match *actor {
+        Actor::Telegram(admin) => admin,
+        _ => return Err(not_admin(locale)),
+    };
+    let status = match commands.next() {
+        None => return list(config, registry, admin, locale).map(Reply::from),
+        Some("approve") => AccessStatus::Approved,
+        Some("deny") => AccessStatus::Denied,
+        Some("revoke") => AccessStatus::Revoked,
+        Some(_) => return Err(wrong_bot_usage(locale)),
+    };
+    let telegram_id = commands
+        .next()
+        .and_then(|id| i64::from_str(id).ok())
+        .ok_or(wrong_bot_usage(locale))?;
+    decide(telegram_id, status, admin, config, registry, actor, locale)
+}
+
+fn request(
+    telegram_id: i64,
+    name: &str,
+    config: &Config,
+    registry: &Registry,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    let text = locale.catalogue();
+    if is_allowed(config, registry, telegram_id)? {
+        return Ok(Reply::from(text.access_already_open.to_owned()));
+    }
+    match registry.request_access(telegram_id, name.to_owned(), actor)? {
+        Some(access) => {
+            let mut reply = Reply::from(text.access_request_sent.to_owned());
+            for admin in admins(config) {
+                let admin_locale = super::locale_of(registry, admin)?;
+                reply = reply.with_notification(admin, decision_request(&access, admin_locale));
+            }
+            Ok(reply)
+        }
+        None => unauthorized(registry, telegram_id, locale),
+    }
+}
+
+fn decide(
+    telegram_id: i64,
+    status: AccessStatus,
+    admin: i64,
+    config: &Config,
+    registry: &Registry,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    if !admins(config).contains(&admin) {
+        return Err(not_admin(locale));
+    }
+    let access = registry.decide_access(telegram_id, status, admin, actor)?;
+    let user_text = super::locale_of(registry, telegram_id)?.catalogue();
+    let notice = match status {
+        AccessStatus::Approved => user_text.access_approved,
+        AccessStatus::Denied => user_text.access_denied,
+        _ => user_text.access_closed,
+    };
+    Ok(Reply::from(format!(
+        "{} ({}): {}",
+        access.name, access.telegram_id, access.status
+    )).with_notification(telegram_id, Reply::from(notice.to_owned())))
+}
+
+fn list(
+    config: &Config,
+    registry: &Registry,
+    admin: i64,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    if !admins(config).contains(&admin) {
+        return Err(not_admin(locale));
+    }
+    let access = registry.access_list()?;
+    if access.is_empty() {
+        return Ok(locale.catalogue().no_access_requests.to_owned());
+    }
+    Ok(access
+        .iter()
+        .map(|access| {
+            format!(
+                "{} ({}): {}, {}",
+                access.name,
+                access.telegram_id,
+                access.status,
+                locale.date(access.requested)
+            )
+        }).collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn decision_request(access: &Access, locale: Locale) -> Reply {
+    let text = locale.catalogue();
+    Reply::from(fill(
+        text.asks_for_access,
+        &[&access.name, &access.telegram_id],
+    )).with_buttons(vec![vec![
+        Button::callback(
+            text.allow,
+            format!("{}approve:{}", PREFIX, access.telegram_id),
+        ),
+        Button::callback(
+            text.deny,
+            format!("{}deny:{}", PREFIX, access.telegram_id),
+        ),
+    ]])
+}
+
+fn not_admin(locale: Locale) -> FailureError {
+    AppError::BotUsage {
+        reason: locale.catalogue().admins_only.to_owned(),
+    }.into()
+}
+
+fn wrong_bot_usage(locale: Locale) -> FailureError {
+    AppError::BotUsage {
+        reason: locale.catalogue().access_usage.to_owned(),
+    }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::temporary;
+    use super::*;
+
+    #[test]
+    fn opens_access_after_approval_until_revoked() {
+        let (registry, config, _dir) = temporary();
+        let admin = Actor::Telegram(1);
+        let guest = Actor::Telegram(5);
+        assert!(!is_allowed(&config, &registry, 5).unwrap());
+        let requested =
+            callback(REQUEST, 5, "Петя", &config, &registry, &guest, Locale::Ru).unwrap();
+        assert_eq!(requested.notifications.len(), 1);
+        assert_eq!(requested.notifications[0].chat_id, 1);
+        assert!(!is_allowed(&config, &registry, 5).unwrap());
+
+        let approve = format!("{}approve:5", PREFIX);
+        let approved = callback(&approve, 1, "", &config, &registry, &admin, Locale::Ru).unwrap();
+        assert_eq!(approved.notifications[0].chat_id, 5);
+        assert!(is_allowed(&config, &registry, 5).unwrap());
+
+        let mut words = "revoke 5".split_whitespace();
+        access(&mut words, &config, &registry, &admin, Locale::Ru).unwrap();
+        assert!(!is_allowed(&config, &registry, 5).unwrap());
+    }
+
+    #[test]
+    fn lets_revocation_override_configured_users() {
+        let (registry, mut config, _dir) = temporary();
+        let admin = Actor::Telegram(1);
+        config.allowed_telegram_users.push(2);
+        assert!(is_allowed(&config, &registry, 2).unwrap());
+
+        let mut words = "revoke 2".split_whitespace();
+        access(&mut words, &config, &registry, &admin, Locale::Ru).unwrap();
+        assert!(!is_allowed(&config, &registry, 2).unwrap());
+        assert!(is_allowed(&config, &registry, 1).unwrap());
+    }
+
+    #[test]
+    fn rejects_decisions_of_non_admins() {
+        let (registry, config, _dir) = temporary();
+        let guest = Actor::Telegram(5);
+        let approve = format!("{}approve:5", PREFIX);
+        assert!(callback(&approve, 5, "", &config, &registry, &guest, Locale::Ru).is_err());
+        let mut words = "approve 5".split_whitespace();
+        assert!(access(&mut words, &config, &registry, &guest, Locale::Ru).is_err());
+        assert!(!is_allowed(&config, &registry, 5).unwrap());
+        assert!(registry.access(5).unwrap().is_none());
+    }
+}
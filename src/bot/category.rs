@@ -1,15 +1,247 @@
 use failure::Error as FailureError;
 
-use accounting::UserId;
+use std::collections::HashMap;
+
+use super::reply::{callback_key, Button, Reply};
+use accounting::{Actor, Dialog, Entry, EntryId, UserId};
+use config::Config;
+use error::AppError;
+use i18n::Locale;
 use registry::Registry;
 
+const ASSIGN_PREFIX: &'static str = "cat:";
+const NEW_PREFIX: &'static str = "cat-new:";
+const MAX_SUGGESTIONS: usize = 6;
+
 pub fn category<'a, I>(
-    _commands: &mut I,
-    _registry: &Registry,
-    _user: UserId,
+    commands: &mut I,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
 ) -> Result<String, FailureError>
 where
     I: Iterator<Item = &'a str>,
 {
-    Err(crate::error::AppError::NotImplementedYet.into())
+    let category = commands.next().ok_or(wrong_bot_usage(locale))?;
+    let product: Vec<&str> = commands.collect();
+    if product.is_empty() {
+        return Err(wrong_bot_usage(locale));
+    }
+    let category = registry.add_category(user, product.join(" "), category.to_owned(), actor)?;
+    Ok(format!("{} - {}", category.product, category.category))
+}
+
+pub fn suggest(registry: &Registry, entry: &Entry, locale: Locale) -> Result<Reply, FailureError> {
+    let text = format!(
+        "{} - {}",
+        entry.product.name,
+        locale.amount(entry.product.price)
+    );
+    let mut buttons: Vec<Button> = most_used(registry, entry.user_id.clone())?
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|category| {
+            let data = format!("{}{}:{}", ASSIGN_PREFIX, entry.id, callback_key(&category));
+            Button::callback(category, data)
+        }).collect();
+    buttons.push(Button::callback(
+        locale.catalogue().new_category,
+        format!("{}{}", NEW_PREFIX, entry.id),
+    ));
+    let rows = buttons.chunks(2).map(|row| row.to_vec()).collect();
+    Ok(Reply::from(text).with_buttons(rows))
+}
+
+pub fn callback(
+    data: &str,
+    config: &Config,
+    registry: &Registry,
+    chat_id: i64,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    if data.starts_with(NEW_PREFIX) {
+        let entry = find_entry(registry, user.clone(), &data[NEW_PREFIX.len()..], locale)?;
+        let question = format!(
+            "{} - {}\n{}",
+            entry.product.name,
+            locale.amount(entry.product.price),
+            locale.catalogue().ask_new_category
+        );
+        super::dialog::start(
+            config,
+            registry,
+            chat_id,
+            user,
+            Dialog::AwaitCategory { entry_id: entry.id },
+        )?;
+        return Ok(question);
+    }
+    if !data.starts_with(ASSIGN_PREFIX) {
+        return Err(wrong_bot_usage(locale));
+    }
+    let mut parts = data[ASSIGN_PREFIX.len()..].splitn(2, ':');
+    let entry_id = parts.next().ok_or(wrong_bot_usage(locale))?;
+    let key = parts.next().ok_or(wrong_bot_usage(locale))?;
+    let entry = find_entry(registry, user.clone(), entry_id, locale)?;
+    let category_name = most_used(registry, user.clone())?
+        .into_iter()
+        .find(|category| callback_key(category) == key)
+        .ok_or(wrong_bot_usage(locale))?;
+    let category = registry.add_category(user, entry.product.name.clone(), category_name, actor)?;
+    Ok(format!(
+        "{} ({}) - {}",
+        entry.product.name,
+        category.category,
+        locale.amount(entry.product.price)
+    ))
+}
+
+fn most_used(registry: &Registry, user: UserId) -> Result<Vec<String>, FailureError> {
+    let categories = registry.categories(user.clone())?;
+    let by_product: HashMap<&str, &str> = categories
+        .iter()
+        .map(|c| (c.product.as_str(), c.category.as_str()))
+        .collect();
+    let mut usage: HashMap<&str, usize> = categories
+        .iter()
+        .map(|c| (c.category.as_str(), 0))
+        .collect();
+    for entry in registry.list(user)? {
+        if let Some(category) = by_product.get(entry.product.name.as_str()) {
+            *usage.entry(*category).or_insert(0) += 1;
+        }
+    }
+    let mut usage: Vec<(&str, usize)> = usage.into_iter().collect();
+    usage.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    Ok(usage
+        .into_iter()
+        .map(|(category, _)| category.to_owned())
+        .collect())
+}
+
+pub fn find_entry(
+    registry: &Registry,
+    user: UserId,
+    entry_id: &str,
+    locale: Locale,
+) -> Result<Entry, FailureError> {
+    let entry_id = EntryId::new(entry_id.to_owned());
+    registry
+        .list(user)?
+        .into_iter()
+        .find(|e| e.id == entry_id)
+        .ok_or(
+            AppError::BotUsage {
+                reason: locale.catalogue().entry_not_found.to_owned(),
+            }.into(),
+        )
+}
+
+fn wrong_bot_usage(locale: Locale) -> FailureError {
+    AppError::BotUsage {
+        reason: locale.catalogue().category_usage.to_owned(),
+    }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::temporary;
+    use super::*;
+    use accounting::TelegramId;
+
+    fn add(registry: &Registry, user: &UserId, raw: &str) -> Entry {
+        let entry = Entry::new(user.clone(), raw.parse().unwrap());
+        registry.add_entry(entry.clone(), &Actor::Cli).unwrap();
+        entry
+    }
+
+    #[test]
+    fn orders_categories_by_usage_then_name() {
+        let (registry, _, _dir) = temporary();
+        let user = registry
+            .find_or_create(TelegramId(1), &Actor::Cli)
+            .unwrap()
+            .id;
+        for &(product, category) in &[
+            ("хлеб", "еда"),
+            ("молоко", "еда"),
+            ("такси", "транспорт"),
+            ("кино", "досуг"),
+        ] {
+            registry
+                .add_category(
+                    user.clone(),
+                    product.to_owned(),
+                    category.to_owned(),
+                    &Actor::Cli,
+                )
+                .unwrap();
+        }
+        add(&registry, &user, "хлеб 50");
+        add(&registry, &user, "молоко 70");
+        add(&registry, &user, "такси 300");
+        assert_eq!(
+            most_used(&registry, user).unwrap(),
+            vec!["еда".to_owned(), "транспорт".to_owned(), "досуг".to_owned()]
+        );
+    }
+
+    #[test]
+    fn assigns_suggested_category_with_a_long_name() {
+        let (registry, config, _dir) = temporary();
+        let user = registry
+            .find_or_create(TelegramId(1), &Actor::Cli)
+            .unwrap()
+            .id;
+        let long_name = "хозяйственные товары для дома и сада";
+        registry
+            .add_category(
+                user.clone(),
+                "лопата".to_owned(),
+                long_name.to_owned(),
+                &Actor::Cli,
+            )
+            .unwrap();
+        let entry = add(&registry, &user, "грабли 400");
+
+        let reply = suggest(&registry, &entry, Locale::Ru).unwrap();
+        let buttons: Vec<Button> = reply.buttons.into_iter().flat_map(|row| row).collect();
+        assert!(buttons.iter().all(|b| b.callback_data.len() <= 64));
+        assert_eq!(buttons.len(), 2);
+        assert_eq!(buttons[0].text, long_name);
+        assert!(buttons[1].callback_data.starts_with(NEW_PREFIX));
+
+        let text = callback(
+            &buttons[0].callback_data,
+            &config,
+            &registry,
+            1,
+            user.clone(),
+            &Actor::Cli,
+            Locale::Ru,
+        ).unwrap();
+        assert!(text.contains(long_name));
+        let category = registry
+            .categories(user)
+            .unwrap()
+            .into_iter()
+            .find(|c| c.product == "грабли")
+            .unwrap();
+        assert_eq!(category.category, long_name);
+    }
+
+    #[test]
+    fn rejects_unknown_callback_key() {
+        let (registry, config, _dir) = temporary();
+        let user = registry
+            .find_or_create(TelegramId(1), &Actor::Cli)
+            .unwrap()
+            .id;
+        let entry = add(&registry, &user, "грабли 400");
+        let data = format!("{}{}:{}", ASSIGN_PREFIX, entry.id, callback_key("еда"));
+        assert!(callback(&data, &config, &registry, 1, user, &Actor::Cli, Locale::Ru).is_err());
+    }
 }
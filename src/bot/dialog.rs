@@ -0,0 +1,192 @@
+use failure::Error as FailureError;
+
+use accounting::{Actor, Conversation, Dialog, UserId};
+use config::Config;
+use error::AppError;
+use i18n::{fill, Locale};
+use registry::Registry;
+
+const DEFAULT_TIMEOUT_MINUTES: i64 = 10;
+
+pub fn start(
+    config: &Config,
+    registry: &Registry,
+    chat_id: i64,
+    user: UserId,
+    dialog: Dialog,
+) -> Result<(), FailureError> {
+    let minutes = config
+        .bot_dialog_timeout_minutes
+        .unwrap_or(DEFAULT_TIMEOUT_MINUTES);
+    registry.start_conversation(Conversation::new(
+        chat_id,
+        user,
+        dialog,
+        ::chrono::Duration::minutes(minutes),
+    ))
+}
+
+pub fn cancel(
+    registry: &Registry,
+    chat_id: i64,
+    user: UserId,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    match registry.end_conversation(chat_id, user)? {
+        Some(_) => Ok(locale.catalogue().cancelled.to_owned()),
+        None => Ok(locale.catalogue().nothing_to_cancel.to_owned()),
+    }
+}
+
+pub fn is_cancel(text: &str) -> bool {
+    match text.trim().to_lowercase().as_str() {
+        "/cancel" | "отмена" | "cancel" => true,
+        _ => false,
+    }
+}
+
+pub fn delete_last(
+    config: &Config,
+    registry: &Registry,
+    chat_id: i64,
+    user: UserId,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    let entry = registry
+        .latest_entry(user.clone())?
+        .ok_or(AppError::BotUsage {
+            reason: locale.catalogue().no_entries.to_owned(),
+        })?;
+    let question = fill(
+        locale.catalogue().confirm_delete,
+        &[&describe(&entry.product.name, entry.product.price, locale)],
+    );
+    start(
+        config,
+        registry,
+        chat_id,
+        user,
+        Dialog::ConfirmDelete { entry_id: entry.id },
+    )?;
+    Ok(question)
+}
+
+pub fn answer(
+    text: &str,
+    conversation: Conversation,
+    registry: &Registry,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    let catalogue = locale.catalogue();
+    let Conversation {
+        chat_id,
+        user_id,
+        dialog,
+        ..
+    } = conversation;
+    match dialog {
+        Dialog::ConfirmDelete { entry_id } => match text.trim().to_lowercase().as_str() {
+            "да" | "yes" => {
+                registry.end_conversation(chat_id, user_id.clone())?;
+                match registry.delete_entry(user_id, entry_id, actor)? {
+                    Some(entry) => Ok(fill(
+                        catalogue.deleted,
+                        &[&describe(&entry.product.name, entry.product.price, locale)],
+                    )),
+                    None => Ok(catalogue.already_deleted.to_owned()),
+                }
+            }
+            "нет" | "no" => {
+                registry.end_conversation(chat_id, user_id)?;
+                Ok(catalogue.kept.to_owned())
+            }
+            _ => Ok(catalogue.answer_yes_or_no.to_owned()),
+        },
+        Dialog::AwaitCategory { entry_id } => {
+            let category_name = text.trim();
+            if category_name.is_empty() {
+                return Ok(catalogue.send_category_name.to_owned());
+            }
+            registry.end_conversation(chat_id, user_id.clone())?;
+            let entry =
+                super::category::find_entry(registry, user_id.clone(), &entry_id.0, locale)?;
+            let category = registry.add_category(
+                user_id,
+                entry.product.name.clone(),
+                category_name.to_owned(),
+                actor,
+            )?;
+            Ok(format!(
+                "{} ({}) - {}",
+                entry.product.name,
+                category.category,
+                locale.amount(entry.product.price)
+            ))
+        }
+    }
+}
+
+fn describe(name: &str, price: i32, locale: Locale) -> String {
+    format!("{} - {}", name, locale.amount(price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::temporary;
+    use super::*;
+    use accounting::{Entry, TelegramId};
+
+    #[test]
+    fn deletes_entry_after_confirmation() {
+        let (registry, config, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let entry = Entry::new(user.clone(), "хлеб 50".parse().unwrap());
+        registry.add_entry(entry, &actor).unwrap();
+        delete_last(&config, &registry, 1, user.clone(), Locale::Ru).unwrap();
+
+        let conversation = registry.conversation(1, user.clone()).unwrap().unwrap();
+        let reply = answer("может быть", conversation, &registry, &actor, Locale::Ru).unwrap();
+        assert_eq!(reply, Locale::Ru.catalogue().answer_yes_or_no);
+        assert_eq!(registry.list(user.clone()).unwrap().len(), 1);
+
+        let conversation = registry.conversation(1, user.clone()).unwrap().unwrap();
+        answer("Да", conversation, &registry, &actor, Locale::Ru).unwrap();
+        assert!(registry.list(user.clone()).unwrap().is_empty());
+        assert!(registry.conversation(1, user).unwrap().is_none());
+    }
+
+    #[test]
+    fn cancels_and_expires_conversations() {
+        let (registry, config, _dir) = temporary();
+        let user = registry
+            .find_or_create(TelegramId(1), &Actor::Telegram(1))
+            .unwrap()
+            .id;
+        let dialog = Dialog::AwaitCategory {
+            entry_id: ::accounting::EntryId::generate(),
+        };
+        start(&config, &registry, 1, user.clone(), dialog.clone()).unwrap();
+        let catalogue = Locale::Ru.catalogue();
+        assert_eq!(
+            cancel(&registry, 1, user.clone(), Locale::Ru).unwrap(),
+            catalogue.cancelled
+        );
+        assert_eq!(
+            cancel(&registry, 1, user.clone(), Locale::Ru).unwrap(),
+            catalogue.nothing_to_cancel
+        );
+
+        let config = Config {
+            bot_dialog_timeout_minutes: Some(0),
+            ..config
+        };
+        start(&config, &registry, 1, user.clone(), dialog).unwrap();
+        assert!(registry.conversation(1, user.clone()).unwrap().is_none());
+        assert_eq!(
+            cancel(&registry, 1, user, Locale::Ru).unwrap(),
+            catalogue.nothing_to_cancel
+        );
+    }
+}
@@ -6,6 +6,7 @@ use lettre_email::{EmailBuilder, MimeMessage, PartBuilder};
 
 use config::Config;
 use error::AppError;
+use i18n::Locale;
 
 pub struct EmailSender {
     from: String,
@@ -37,16 +38,17 @@ impl EmailSender {
         })
     }
 
-    pub fn send(&self, data: String, email: &str) -> Result<(), FailureError> {
+    pub fn send(&self, data: String, email: &str, locale: Locale) -> Result<(), FailureError> {
         debug!("sendign email to \"{}\"", email);
 
+        let text = locale.catalogue();
         let mut email = EmailBuilder::new()
             .to(email)
             .from(self.from.clone())
-            .subject("Отчет")
-            .text("Высылаем желаемый отчет");
+            .subject(text.report)
+            .text(text.report_email_text);
         email.set_message_type(::lettre_email::MimeMultipartType::Mixed);
-        email.add_child(child(data));
+        email.add_child(child(data, text.report));
         let email = email.build()?;
         trace!("creating mailer");
 
@@ -64,13 +66,13 @@ impl EmailSender {
     }
 }
 
-fn child(data: String) -> MimeMessage {
+fn child(data: String, name: &str) -> MimeMessage {
     let encoded: String = ::base64::encode(data.as_bytes());
     PartBuilder::new()
         .body(encoded)
         .header((
             "Content-Disposition",
-            format!("attachment; filename=\"Отчет.html\""),
+            format!("attachment; filename=\"{}.html\"", name),
         )).header(("Content-Type", ::mime::TEXT_HTML.to_string()))
         .header(("Content-Transfer-Encoding", "base64"))
         .build()
@@ -0,0 +1,74 @@
+use failure::Error as FailureError;
+use reqwest::multipart::{Form, Part};
+
+use std::env;
+
+use error::AppError;
+
+#[derive(Debug, Deserialize)]
+struct GetFileResponse {
+    result: Option<File>,
+}
+
+#[derive(Debug, Deserialize)]
+struct File {
+    file_path: Option<String>,
+}
+
+pub fn download(file_id: &str) -> Result<Vec<u8>, FailureError> {
+    let token = env::var("TELEGRAM_BOT_TOKEN")?;
+    let file: GetFileResponse = ::reqwest::get(&format!(
+        "https://api.telegram.org/bot{}/getFile?file_id={}",
+        token, file_id
+    )).and_then(|response| response.error_for_status())
+    .and_then(|mut response| response.json())
+    .map_err(telegram_error)?;
+    let file_path = file
+        .result
+        .and_then(|file| file.file_path)
+        .ok_or(AppError::TelegramFile {
+            reason: format!("no path for file {}", file_id),
+        })?;
+    let mut response = ::reqwest::get(&format!(
+        "https://api.telegram.org/file/bot{}/{}",
+        token, file_path
+    )).and_then(|response| response.error_for_status())
+    .map_err(telegram_error)?;
+    let mut content = Vec::new();
+    response.copy_to(&mut content).map_err(telegram_error)?;
+    Ok(content)
+}
+
+pub fn send_document(
+    chat_id: i64,
+    caption: &str,
+    file_name: String,
+    content: Vec<u8>,
+) -> Result<(), FailureError> {
+    let token = env::var("TELEGRAM_BOT_TOKEN")?;
+    let form = Form::new()
+        .text("chat_id", chat_id.to_string())
+        .text("caption", caption.to_owned())
+        .part(
+            "document",
+            Part::bytes(content).file_name(file_name),
+        );
+    ::reqwest::Client::new()
+        .post(&format!(
+            "https://api.telegram.org/bot{}/sendDocument",
+            token
+        )).multipart(form)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(telegram_error)?;
+    Ok(())
+}
+
+fn telegram_error(error: ::reqwest::Error) -> FailureError {
+    AppError::TelegramFile {
+        reason: match error.status() {
+            Some(status) => format!("telegram responded with {}", status),
+            None => "request to telegram failed".to_owned(),
+        },
+    }.into()
+}
@@ -2,53 +2,242 @@ use failure::Error as FailureError;
 
 use std::str::FromStr;
 
+use super::receipt::ScannedPhoto;
+use super::reply::Reply;
 use accounting::UserId;
-use accounting::{Entry, Product};
+use accounting::{Actor, Batch, Entry, FiscalCode, Product};
 use config::Config;
+use error::AppError;
+use i18n::Locale;
 use registry::Registry;
+use representation::BatchRepresentation;
 
 pub fn handle(
     data: &str,
     config: &Config,
     registry: &Registry,
+    chat_id: i64,
     user: UserId,
-) -> Result<String, FailureError> {
+    author_name: &str,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
     let mut words = data.split_whitespace();
     if let Some(command) = words.next() {
-        match command {
-            "help" | "Help" | "/help" => Ok(help()),
-            "отчет" | "/отчет" | "Отчет" => {
-                super::report::report(&mut words, config, registry, user)
+        if !command.starts_with('/') && !super::dialog::is_cancel(data) {
+            if let Some(conversation) = registry.conversation(chat_id, user.clone())? {
+                return super::dialog::answer(data, conversation, registry, actor, locale)
+                    .map(Reply::from);
             }
-            "Кат" | "кат" => super::category::category(&mut words, registry, user),
+        }
+        match command.to_lowercase().as_str() {
+            "help" | "/help" | "/помощь" => Ok(Reply::from(help(locale))),
+            "отчет" | "/отчет" | "/report" => {
+                super::report::report(&mut words, config, registry, user, locale)
+            }
+            "кат" | "/category" => {
+                super::category::category(&mut words, registry, user, actor, locale)
+                    .map(Reply::from)
+            }
+            "/token" | "/токен" => {
+                super::token::token(&mut words, registry, user, actor, locale).map(Reply::from)
+            }
+            "/login" | "/вход" => super::token::login(config, registry, user, actor, locale)
+                .map(|link| Reply::from(link).without_preview()),
+            "/access" | "/доступ" => {
+                super::access::access(&mut words, config, registry, actor, locale)
+            }
+            "/household" | "/семья" => {
+                super::household::household(&mut words, registry, user, author_name, actor, locale)
+                    .map(Reply::from)
+            }
+            "/split" | "/разделить" => {
+                super::split::split(&mut words, registry, user, actor, locale).map(Reply::from)
+            }
+            "/settle" | "/долги" => {
+                super::split::settle(&mut words, registry, user, actor, locale).map(Reply::from)
+            }
+            "/list" | "/список" => super::list::list(&mut words, registry, user, locale),
+            "/delete" | "/удалить" => {
+                super::dialog::delete_last(config, registry, chat_id, user, locale).map(Reply::from)
+            }
+            _ if super::dialog::is_cancel(data) => {
+                super::dialog::cancel(registry, chat_id, user, locale).map(Reply::from)
+            }
+            "/lang" | "/язык" => {
+                language(&mut words, registry, user, actor, locale).map(Reply::from)
+            }
+            _ if FiscalCode::find(data).is_some() => {
+                super::receipt::from_text(data, registry, user, actor, locale).map(Reply::from)
+            }
+            _ if Batch::is_batch(data) => added_batch(data, registry, user, actor, locale),
             _ => {
                 let parsed_new_product = Product::from_str(&data)?;
                 let new_entry = Entry::new(user, parsed_new_product);
-                let response = added_entry(registry, &new_entry)?;
-                registry.add_entry(new_entry)?;
+                let response = added_entry(registry, &new_entry, locale)?;
+                registry.add_entry(new_entry, actor)?;
                 Ok(response)
             }
         }
     } else {
-        Ok(help())
+        Ok(Reply::from(help(locale)))
+    }
+}
+
+pub fn handle_callback(
+    data: &str,
+    config: &Config,
+    registry: &Registry,
+    chat_id: i64,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    if data.starts_with(super::list::PAGE_PREFIX) {
+        super::list::callback(data, registry, user, locale)
+    } else {
+        super::category::callback(data, config, registry, chat_id, user, actor, locale)
+            .map(Reply::from)
+    }
+}
+
+pub fn handle_photo(
+    scanned: ScannedPhoto,
+    caption: Option<&str>,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    super::receipt::from_photo(scanned, caption, registry, user, actor, locale).map(Reply::from)
+}
+
+fn help(locale: Locale) -> String {
+    locale.catalogue().help.to_owned()
+}
+
+fn language<'a, I>(
+    commands: &mut I,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    match commands.next() {
+        Some(raw) => {
+            let locale = Locale::from_str(raw).map_err(|_| AppError::BotUsage {
+                reason: locale.catalogue().language_usage.to_owned(),
+            })?;
+            registry.set_locale(user, locale, actor)?;
+            Ok(locale.catalogue().language_set.to_owned())
+        }
+        None => Ok(locale.catalogue().language_set.to_owned()),
     }
 }
 
-fn help() -> String {
-    format!("/help\n/отчет")
+fn added_batch(
+    data: &str,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    let batch = Batch::parse(data, user, ::chrono::offset::Local::now().naive_local());
+    for entry in &batch.entries {
+        registry.add_entry(entry.clone(), actor)?;
+    }
+    Ok(Reply::from(format!(
+        "{}",
+        BatchRepresentation::from(&batch).with_locale(locale)
+    )))
 }
 
-fn added_entry(registry: &Registry, entry: &Entry) -> Result<String, FailureError> {
+fn added_entry(registry: &Registry, entry: &Entry, locale: Locale) -> Result<Reply, FailureError> {
     let categories = registry.categories(entry.user_id.clone())?;
     if let Some(category) = categories.iter().find(|c| c.product == entry.product.name) {
-        Ok(format!(
-            "{} ({}) - {} руб.",
-            entry.product.name, category.category, entry.product.price
-        ))
+        Ok(Reply::from(format!(
+            "{} ({}) - {}",
+            entry.product.name,
+            category.category,
+            locale.amount(entry.product.price)
+        )))
     } else {
-        Ok(format!(
-            "{} - {} руб.",
-            entry.product.name, entry.product.price
-        ))
+        super::category::suggest(registry, entry, locale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::temporary;
+    use super::*;
+    use accounting::TelegramId;
+
+    #[test]
+    fn cancels_conversation_with_a_bare_word() {
+        let (registry, config, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let send = |text: &str| {
+            handle(
+                text,
+                &config,
+                &registry,
+                1,
+                user.clone(),
+                "Test",
+                &actor,
+                Locale::Ru,
+            ).unwrap()
+            .text
+        };
+        send("хлеб 50");
+        send("/delete");
+        assert_eq!(send("Отмена"), Locale::Ru.catalogue().cancelled);
+        assert_eq!(registry.list(user.clone()).unwrap().len(), 1);
+        assert!(registry.conversation(1, user.clone()).unwrap().is_none());
+    }
+
+    #[test]
+    fn adds_entries_named_like_english_commands() {
+        let (registry, config, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        for text in &["cat 300", "list 50", "split 120", "delete 20", "lang 10"] {
+            let user = user.clone();
+            handle(text, &config, &registry, 1, user, "", &actor, Locale::En).unwrap();
+        }
+        let russian = [
+            "токен 10",
+            "вход 300",
+            "доступ 40",
+            "семья 500",
+            "разделить 60",
+            "долги 70",
+            "список 50",
+            "удалить 20",
+            "язык 30",
+            "помощь 80",
+        ];
+        for text in russian.iter() {
+            let user = user.clone();
+            handle(text, &config, &registry, 1, user, "", &actor, Locale::Ru).unwrap();
+        }
+        let mut names: Vec<String> = registry
+            .list(user.clone())
+            .unwrap()
+            .into_iter()
+            .map(|e| e.product.name)
+            .collect();
+        names.sort();
+        let mut expected: Vec<String> = ["cat", "delete", "lang", "list", "split"]
+            .iter()
+            .chain(russian.iter())
+            .map(|text| text.split_whitespace().next().unwrap().to_owned())
+            .collect();
+        expected.sort();
+        assert_eq!(names, expected);
     }
 }
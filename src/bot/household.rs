@@ -0,0 +1,156 @@
+use failure::Error as FailureError;
+
+use std::str::FromStr;
+
+use accounting::statistics::TimePeriod;
+use accounting::{Actor, UserId};
+use error::AppError;
+use i18n::{fill, Locale};
+use registry::Registry;
+use representation::{HouseholdReportRepresentation, HouseholdRepresentation};
+
+const INVITE_HOURS: i64 = 24;
+
+pub fn household<'a, I>(
+    commands: &mut I,
+    registry: &Registry,
+    user: UserId,
+    author_name: &str,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let text = locale.catalogue();
+    match commands.next() {
+        Some("info") | None => match registry.household_of(user)? {
+            Some((household, members)) => Ok(format!(
+                "{}",
+                HouseholdRepresentation::new(&household, &members).with_locale(locale)
+            )),
+            None => Ok(text.not_in_household.to_owned()),
+        },
+        Some("create") => {
+            let name: Vec<&str> = commands.collect();
+            if name.is_empty() {
+                return Err(wrong_bot_usage(locale));
+            }
+            let member_name = default_name(registry, &user, author_name)?;
+            let household = registry.create_household(user, name.join(" "), member_name, actor)?;
+            Ok(fill(text.household_created, &[&household.name]))
+        }
+        Some("invite") => {
+            let code = registry.invite_to_household(
+                user,
+                ::chrono::Duration::hours(INVITE_HOURS),
+                actor,
+            )?;
+            Ok(format!(
+                "{}\n/household join {}",
+                fill(text.household_invite, &[&INVITE_HOURS]),
+                code
+            ))
+        }
+        Some("join") => {
+            let code = commands.next().ok_or(wrong_bot_usage(locale))?;
+            let name: Vec<&str> = commands.collect();
+            let member_name = if name.is_empty() {
+                default_name(registry, &user, author_name)?
+            } else {
+                name.join(" ")
+            };
+            let household = registry.join_household(user, code, member_name, actor)?;
+            Ok(fill(text.household_joined, &[&household.name]))
+        }
+        Some("leave") => {
+            let household = registry.leave_household(user, actor)?;
+            Ok(fill(text.household_left, &[&household.name]))
+        }
+        Some("remove") => {
+            let number = commands
+                .next()
+                .and_then(|number| usize::from_str(number).ok())
+                .ok_or(wrong_bot_usage(locale))?;
+            let (_, members) = registry
+                .household_of(user.clone())?
+                .ok_or(wrong_bot_usage(locale))?;
+            let member = members
+                .get(number.wrapping_sub(1))
+                .ok_or(AppError::BotUsage {
+                    reason: fill(text.no_member_with_number, &[&number]),
+                })?;
+            match registry.remove_member(user, &member.id, actor)? {
+                Some(removed) => Ok(fill(text.member_removed, &[&removed.name])),
+                None => Ok(text.cannot_remove_self.to_owned()),
+            }
+        }
+        Some("report") => {
+            let time_period = match commands.next() {
+                Some(time_period) => super::report::parse_time_period(time_period)?,
+                None => TimePeriod::ThisWeek,
+            };
+            let (statistics, members) = registry.household_statistics(user, &time_period)?;
+            match statistics.report(time_period)? {
+                Some(report) => Ok(format!(
+                    "{}",
+                    HouseholdReportRepresentation::new(report, &members).with_locale(locale)
+                )),
+                None => Ok(text.no_data.to_owned()),
+            }
+        }
+        Some(_) => Err(wrong_bot_usage(locale)),
+    }
+}
+
+fn default_name(
+    registry: &Registry,
+    user: &UserId,
+    author_name: &str,
+) -> Result<String, FailureError> {
+    if !author_name.trim().is_empty() {
+        return Ok(author_name.trim().to_owned());
+    }
+    let telegram_id = registry
+        .find_user(|u| u.id == *user)?
+        .and_then(|u| u.telegram_id);
+    Ok(match telegram_id {
+        Some(telegram_id) => format!("{}", telegram_id.0),
+        None => user.0.clone(),
+    })
+}
+
+fn wrong_bot_usage(locale: Locale) -> FailureError {
+    AppError::BotUsage {
+        reason: locale.catalogue().household_usage.to_owned(),
+    }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::temporary;
+    use super::*;
+    use accounting::TelegramId;
+
+    #[test]
+    fn names_members_after_the_sender() {
+        let (registry, _, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let owner = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let partner = registry.find_or_create(TelegramId(2), &actor).unwrap().id;
+        let run = |command: &str, user: &UserId, author_name: &str| {
+            let mut words = command.split_whitespace();
+            let user = user.clone();
+            household(&mut words, &registry, user, author_name, &actor, Locale::Ru).unwrap()
+        };
+        run("create дом", &owner, "Аня");
+        let code = registry
+            .invite_to_household(owner.clone(), ::chrono::Duration::hours(1), &actor)
+            .unwrap();
+        run(&format!("join {}", code), &partner, "");
+
+        let (_, members) = registry.household_of(owner).unwrap().unwrap();
+        let names: Vec<&str> = members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["Аня", "2"]);
+    }
+}
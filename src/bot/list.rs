@@ -0,0 +1,285 @@
+use chrono::prelude::*;
+use failure::Error as FailureError;
+
+use std::str::FromStr;
+
+use super::reply::{callback_key, Button, Reply};
+use accounting::statistics::TimePeriod;
+use accounting::{Entry, UserId};
+use error::AppError;
+use i18n::{fill, Locale};
+use registry::Registry;
+use representation::EntryRepresentation;
+
+pub const PAGE_PREFIX: &'static str = "list:";
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+#[derive(Debug, PartialEq)]
+struct Query {
+    limit: usize,
+    period_word: Option<String>,
+    period: Option<(NaiveDateTime, NaiveDateTime)>,
+    product: Option<String>,
+}
+
+pub fn list<'a, I>(
+    commands: &mut I,
+    registry: &Registry,
+    user: UserId,
+    locale: Locale,
+) -> Result<Reply, FailureError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let args: Vec<&str> = commands.collect();
+    let query = parse(
+        &args.join(" "),
+        ::chrono::offset::Local::now().naive_local(),
+    );
+    page(registry, user, query, 0, locale)
+}
+
+pub fn callback(
+    data: &str,
+    registry: &Registry,
+    user: UserId,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    let mut parts = data[PAGE_PREFIX.len()..].splitn(4, ':');
+    let mut number = || {
+        parts
+            .next()
+            .and_then(|number| usize::from_str(number).ok())
+            .ok_or(wrong_bot_usage(locale))
+    };
+    let offset = number()?;
+    let limit = number()?;
+    let period_word = parts.next().unwrap_or("");
+    let product_key = parts.next().unwrap_or("");
+    let mut query = parse(
+        &format!("{} {}", limit, period_word),
+        ::chrono::offset::Local::now().naive_local(),
+    );
+    if !product_key.is_empty() {
+        query.product = Some(
+            registry
+                .visible_entries(user.clone())?
+                .into_iter()
+                .map(|e| e.product.name.to_lowercase())
+                .find(|name| callback_key(name) == product_key)
+                .unwrap_or(product_key.to_owned()),
+        );
+    }
+    page(registry, user, query, offset, locale)
+}
+
+fn page(
+    registry: &Registry,
+    user: UserId,
+    query: Query,
+    offset: usize,
+    locale: Locale,
+) -> Result<Reply, FailureError> {
+    let text = locale.catalogue();
+    let members = registry.member_names(user.clone())?;
+    let mut visible = registry.visible_entries(user)?;
+    visible.sort_by(|a, b| b.time.cmp(&a.time));
+    let entries: Vec<(usize, Entry)> = visible
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| (index + 1, entry))
+        .filter(|&(_, ref entry)| query.matches(entry))
+        .collect();
+    if entries.is_empty() {
+        return Ok(Reply::from(text.no_entries.to_owned()));
+    }
+
+    let total = entries.len();
+    let offset = offset.min((total - 1) / query.limit * query.limit);
+    let spent: i32 = entries.iter().map(|&(_, ref e)| e.product.price).sum();
+    let shown: Vec<String> = entries
+        .into_iter()
+        .skip(offset)
+        .take(query.limit)
+        .map(|(number, entry)| {
+            let member = members
+                .get(&entry.user_id)
+                .map_or(String::new(), |name| format!(" ({})", name));
+            format!(
+                "{}. {}{}",
+                number,
+                EntryRepresentation::from(entry).with_locale(locale),
+                member
+            )
+        }).collect();
+    let page_text = format!(
+        "{}\n{}\n{}: {}",
+        fill(text.list_page, &[&(offset + 1), &(offset + shown.len()), &total]),
+        shown.join("\n"),
+        text.total,
+        locale.amount(spent)
+    );
+
+    let mut buttons = Vec::new();
+    if offset > 0 {
+        buttons.push(page_button(
+            text.page_back,
+            offset.saturating_sub(query.limit),
+            &query,
+        ));
+    }
+    if offset + query.limit < total {
+        buttons.push(page_button(text.page_next, offset + query.limit, &query));
+    }
+    if buttons.is_empty() {
+        Ok(Reply::from(page_text))
+    } else {
+        Ok(Reply::from(page_text).with_buttons(vec![buttons]))
+    }
+}
+
+fn page_button(text: &str, offset: usize, query: &Query) -> Button {
+    let product_key = match query.product {
+        Some(ref product) => callback_key(&product.to_lowercase()),
+        None => String::new(),
+    };
+    let data = format!(
+        "{}{}:{}:{}:{}",
+        PAGE_PREFIX,
+        offset,
+        query.limit,
+        query.period_word.as_ref().map_or("", |word| word.as_str()),
+        product_key
+    );
+    Button::callback(text, data)
+}
+
+fn parse(args: &str, now: NaiveDateTime) -> Query {
+    let mut words = args.split_whitespace().peekable();
+    let limit = match words.peek().and_then(|word| usize::from_str(word).ok()) {
+        Some(limit) if limit > 0 && limit <= MAX_LIMIT => {
+            words.next();
+            limit
+        }
+        _ => DEFAULT_LIMIT,
+    };
+    let (period_word, period) = match words.peek().and_then(|word| time_period(word)) {
+        Some(period) => (words.next().map(str::to_owned), Some(period.bounds(now))),
+        None => (None, None),
+    };
+    let product: Vec<&str> = words.collect();
+    Query {
+        limit,
+        period_word,
+        period,
+        product: if product.is_empty() {
+            None
+        } else {
+            Some(product.join(" "))
+        },
+    }
+}
+
+fn time_period(word: &str) -> Option<TimePeriod> {
+    TimePeriod::from_str(word)
+        .or_else(|_| super::report::parse_time_period(word))
+        .ok()
+}
+
+impl Query {
+    fn matches(&self, entry: &Entry) -> bool {
+        self.period
+            .map_or(true, |(from, till)| entry.time >= from && entry.time <= till)
+            && self.product.as_ref().map_or(true, |product| {
+                entry.product.name.to_lowercase() == product.to_lowercase()
+            })
+    }
+}
+
+fn wrong_bot_usage(locale: Locale) -> FailureError {
+    AppError::BotUsage {
+        reason: locale.catalogue().list_usage.to_owned(),
+    }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::temporary;
+    use super::*;
+    use accounting::{Actor, TelegramId};
+
+    #[test]
+    fn parses_limit_period_and_product() {
+        let now = NaiveDate::from_ymd(2018, 10, 20).and_hms(12, 0, 0);
+        let query = parse("5 month черный хлеб", now);
+        assert_eq!(query.limit, 5);
+        assert_eq!(
+            query.period,
+            Some((
+                NaiveDate::from_ymd(2018, 10, 1).and_hms(0, 0, 0),
+                NaiveDate::from_ymd(2018, 10, 31).and_hms(23, 59, 59)
+            ))
+        );
+        assert_eq!(query.product, Some("черный хлеб".to_owned()));
+
+        let query = parse("2017", now);
+        assert_eq!(query.limit, DEFAULT_LIMIT);
+        assert!(query.period.is_some());
+        assert_eq!(query.product, None);
+
+        let query = parse("хлеб", now);
+        assert_eq!(query.period, None);
+        assert_eq!(query.product, Some("хлеб".to_owned()));
+    }
+
+    #[test]
+    fn pages_through_entries_of_a_long_product_name() {
+        let (registry, _, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let product = "свежевыжатый апельсиновый сок в стеклянной бутылке";
+        for price in 1..13 {
+            let raw = format!("{} {}", price, product);
+            let entry = Entry::new(user.clone(), raw.parse().unwrap());
+            registry.add_entry(entry, &actor).unwrap();
+        }
+        registry
+            .add_entry(Entry::new(user.clone(), "хлеб 50".parse().unwrap()), &actor)
+            .unwrap();
+
+        let list_page = Locale::Ru.catalogue().list_page;
+        let args = format!("5 {}", product.to_uppercase());
+        let mut words = args.split_whitespace();
+        let first = list(&mut words, &registry, user.clone(), Locale::Ru).unwrap();
+        assert!(first.text.contains(&fill(list_page, &[&1, &5, &12])));
+        let next = &first.buttons[0][0];
+        assert!(next.callback_data.len() <= 64);
+
+        let second = callback(&next.callback_data, &registry, user.clone(), Locale::Ru).unwrap();
+        assert!(second.text.contains(&fill(list_page, &[&6, &10, &12])));
+        assert!(!second.text.contains("хлеб"));
+        assert_eq!(second.buttons[0].len(), 2);
+    }
+
+    #[test]
+    fn numbers_filtered_entries_like_split() {
+        let (registry, _, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let start = NaiveDate::from_ymd(2018, 10, 20).and_hms(12, 0, 0);
+        for (hours, raw) in vec![(0, "хлеб 50"), (1, "молоко 70"), (2, "хлеб 45")] {
+            let mut entry = Entry::new(user.clone(), raw.parse().unwrap());
+            entry.time = start + ::chrono::Duration::hours(hours);
+            registry.add_entry(entry, &actor).unwrap();
+        }
+
+        let mut words = "хлеб".split_whitespace();
+        let reply = list(&mut words, &registry, user.clone(), Locale::Ru).unwrap();
+        assert!(reply.text.contains("1. "));
+        assert!(reply.text.contains("3. "));
+        assert!(!reply.text.contains("2. "));
+        let third = registry.numbered_entry(user, 3).unwrap().unwrap();
+        assert_eq!(third.product.price, 50);
+    }
+}
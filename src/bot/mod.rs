@@ -7,16 +7,32 @@ use tokio_core::reactor::Core;
 use std::env;
 
 use self::app::App;
-use accounting::TelegramId;
-use config::Config;
+pub use self::access::is_allowed;
+pub use self::receipt::{scan as scan_photo, ScannedPhoto};
+use self::reply::{Document, Reply};
+use accounting::{Actor, TelegramId};
+use backup::DailyBackup;
+use config::{Config, TelegramMode};
 use error::AppError;
+use i18n::Locale;
 use registry::Registry;
 
+mod access;
 mod app;
 mod category;
+mod dialog;
 mod email;
+mod files;
 mod handler;
+mod household;
+mod list;
+mod notify;
+mod receipt;
+pub mod reply;
 mod report;
+mod split;
+mod token;
+pub mod webhook;
 
 pub fn start() {
     let app = match App::from_args() {
@@ -43,21 +59,165 @@ fn start_bot(app: App) -> Result<(), FailureError> {
     let config = crate::config::config(&app.config_path)?;
     let config_without_passwords = crate::config::Config {
         email_smtp_credential_password: None,
+        session_secret: None,
+        telegram_webhook_secret: None,
         ..config.clone()
     };
     info!("config: {:?}", &config_without_passwords);
-    let registry = Registry::new(config.data_path.clone().into())?;
+    let registry = Registry::new(
+        config.data_path.clone().into(),
+        config.storage_format.unwrap_or_default(),
+    )?;
     info!("registry created");
+    if config.telegram_mode.unwrap_or_default() == TelegramMode::Webhook {
+        return Err(AppError::BotUsage {
+            reason: "telegram_mode is \"webhook\", updates are received by the web server"
+                .to_owned(),
+        }.into());
+    }
     BotLauncher::new(registry, config).start()
 }
 
+pub fn respond(
+    text: &str,
+    author_id: i64,
+    author_name: &str,
+    chat_id: i64,
+    config: &Config,
+    registry: &Registry,
+) -> Result<Option<Reply>, FailureError> {
+    if !self::access::is_allowed(config, registry, author_id)? {
+        return unauthorized(registry, author_id, chat_id);
+    }
+    let actor = Actor::Telegram(author_id);
+    let user = registry.find_or_create(TelegramId(author_id), &actor)?;
+    let locale = registry.locale(&user.id)?;
+    match self::handler::handle(
+        text,
+        config,
+        registry,
+        chat_id,
+        user.id,
+        author_name,
+        &actor,
+        locale,
+    ) {
+        Ok(reply) => Ok(Some(reply)),
+        Err(msg) => {
+            warn!("{}", msg);
+            Ok(Some(Reply::from(locale.error(&msg))))
+        }
+    }
+}
+
+pub fn respond_photo(
+    scanned: ScannedPhoto,
+    caption: Option<&str>,
+    author_id: i64,
+    chat_id: i64,
+    config: &Config,
+    registry: &Registry,
+) -> Result<Option<Reply>, FailureError> {
+    if !self::access::is_allowed(config, registry, author_id)? {
+        return unauthorized(registry, author_id, chat_id);
+    }
+    let actor = Actor::Telegram(author_id);
+    let user = registry.find_or_create(TelegramId(author_id), &actor)?;
+    let locale = registry.locale(&user.id)?;
+    match self::handler::handle_photo(scanned, caption, registry, user.id, &actor, locale) {
+        Ok(reply) => Ok(Some(reply)),
+        Err(msg) => {
+            warn!("{}", msg);
+            Ok(Some(Reply::from(locale.error(&msg))))
+        }
+    }
+}
+
+pub fn respond_callback(
+    data: &str,
+    author_id: i64,
+    author_name: &str,
+    chat_id: i64,
+    config: &Config,
+    registry: &Registry,
+) -> Result<Option<Reply>, FailureError> {
+    let actor = Actor::Telegram(author_id);
+    let locale = locale_of(registry, author_id)?;
+    let reply = if data.starts_with(self::access::PREFIX) {
+        self::access::callback(data, author_id, author_name, config, registry, &actor, locale)
+    } else if self::access::is_allowed(config, registry, author_id)? {
+        let user = registry.find_or_create(TelegramId(author_id), &actor)?;
+        self::handler::handle_callback(data, config, registry, chat_id, user.id, &actor, locale)
+    } else {
+        return Ok(None);
+    };
+    match reply {
+        Ok(reply) => Ok(Some(reply)),
+        Err(msg) => {
+            warn!("{}", msg);
+            Ok(Some(Reply::from(locale.error(&msg))))
+        }
+    }
+}
+
+fn locale_of(registry: &Registry, telegram_id: i64) -> Result<Locale, FailureError> {
+    match registry.find_user(|u| u.telegram_id == Some(TelegramId(telegram_id)))? {
+        Some(user) => registry.locale(&user.id),
+        None => Ok(Locale::default()),
+    }
+}
+
+fn unauthorized(
+    registry: &Registry,
+    author_id: i64,
+    chat_id: i64,
+) -> Result<Option<Reply>, FailureError> {
+    if author_id != chat_id {
+        return Ok(None);
+    }
+    let locale = locale_of(registry, author_id)?;
+    self::access::unauthorized(registry, author_id, locale).map(Some)
+}
+
+pub fn deliver(chat_id: i64, mut reply: Reply, config: &Config) -> Option<Reply> {
+    for notification in reply.notifications.drain(..) {
+        self::notify::send_or_warn(notification.chat_id, notification.reply);
+    }
+    if let Some(email) = reply.email.take() {
+        let locale = email.locale;
+        if let Err(e) = self::report::send_email(config, email) {
+            warn!("{}", e);
+            return Some(Reply::from(locale.error(&e)));
+        }
+    }
+    match reply.document.take() {
+        Some(Document {
+            file_name,
+            content,
+            locale,
+        }) => {
+            let sent = self::report::render(config, content).and_then(|content| {
+                self::files::send_document(chat_id, &reply.text, file_name, content)
+            });
+            match sent {
+                Ok(()) => None,
+                Err(e) => {
+                    warn!("{}", e);
+                    Some(Reply::from(locale.error(&e)))
+                }
+            }
+        }
+        None => Some(reply),
+    }
+}
+
 pub struct BotLauncher {
     registry: Registry,
     config: Config,
     latest_start: NaiveDateTime,
     number_of_tries: i32,
     max_number_of_tries: i32,
-    allowed_telegram_users: Vec<i64>,
+    daily_backup: Option<DailyBackup>,
 }
 
 impl BotLauncher {
@@ -66,8 +226,8 @@ impl BotLauncher {
             registry,
             max_number_of_tries: config.max_number_of_tries,
             number_of_tries: 0,
-            allowed_telegram_users: config.allowed_telegram_users.clone(),
             latest_start: ::chrono::offset::Local::now().naive_local(),
+            daily_backup: DailyBackup::from_config(&config),
             config,
         }
     }
@@ -83,6 +243,8 @@ impl BotLauncher {
 
             self.number_of_tries += 1;
 
+            self.backup_if_due();
+
             if self.number_of_tries == self.max_number_of_tries {
                 return Err(AppError::NumberOfLauchesExeeded.into());
             }
@@ -105,29 +267,95 @@ impl BotLauncher {
             .map_err(|e| format_err!("{}", e))?;
 
         let future = api.stream().for_each(|update| {
-            if let UpdateKind::Message(message) = update.kind {
-                let author_id: i64 = message.from.id.into();
+            match update.kind {
+                UpdateKind::Message(message) => {
+                    if let MessageKind::Text { ref data, .. } = message.kind {
+                        self.backup_if_due();
+                        trace!("{:?}", &message);
+                        trace!("<{}>: {}", &message.from.first_name, data);
 
-                if !self.allowed_telegram_users.contains(&author_id) {
-                    return Ok(());
+                        let reply = respond(
+                            data,
+                            message.from.id.into(),
+                            &message.from.first_name,
+                            message.chat.id().into(),
+                            &self.config,
+                            &self.registry,
+                        ).map_err(|e| format!("{:?}", e))?;
+                        let chat_id: i64 = message.chat.id().into();
+                        let reply = reply.and_then(|reply| deliver(chat_id, reply, &self.config));
+                        if let Some(reply) = reply {
+                            let mut request = message.text_reply(reply.text);
+                            if reply.disable_preview {
+                                request.disable_preview();
+                            }
+                            if !reply.buttons.is_empty() {
+                                request.reply_markup(keyboard(reply.buttons));
+                            }
+                            api.spawn(request);
+                        }
+                    }
+                    if let MessageKind::Photo {
+                        ref data,
+                        ref caption,
+                        ..
+                    } = message.kind
+                    {
+                        self.backup_if_due();
+                        trace!("{:?}", &message);
+                        if let Some(photo) = data.last() {
+                            let author_id: i64 = message.from.id.into();
+                            let scanned = if is_allowed(&self.config, &self.registry, author_id)
+                                .map_err(|e| format!("{:?}", e))?
+                            {
+                                scan_photo(&photo.file_id)
+                            } else {
+                                Ok(None)
+                            };
+                            let reply = respond_photo(
+                                scanned,
+                                caption.as_ref().map(|caption| caption.as_str()),
+                                author_id,
+                                message.chat.id().into(),
+                                &self.config,
+                                &self.registry,
+                            ).map_err(|e| format!("{:?}", e))?;
+                            let chat_id: i64 = message.chat.id().into();
+                            let reply =
+                                reply.and_then(|reply| deliver(chat_id, reply, &self.config));
+                            if let Some(reply) = reply {
+                                let mut request = message.text_reply(reply.text);
+                                if !reply.buttons.is_empty() {
+                                    request.reply_markup(keyboard(reply.buttons));
+                                }
+                                api.spawn(request);
+                            }
+                        }
+                    }
                 }
-                if let MessageKind::Text { ref data, .. } = message.kind {
-                    trace!("{:?}", &message);
-                    trace!("<{}>: {}", &message.from.first_name, data);
-
-                    let user = self
-                        .registry
-                        .find_or_create(TelegramId(i64::from(message.from.id)))
-                        .map_err(|e| format!("{:?}", e))?;
-
-                    match self::handler::handle(data, &self.config, &self.registry, user.id) {
-                        Ok(msg) => api.spawn(message.text_reply(msg)),
-                        Err(msg) => {
-                            warn!("{}", msg);
-                            api.spawn(message.text_reply(format!("Error: {}", msg)))
+                UpdateKind::CallbackQuery(query) => {
+                    trace!("{:?}", &query);
+                    let reply = respond_callback(
+                        &query.data,
+                        query.from.id.into(),
+                        &query.from.first_name,
+                        query.message.chat.id().into(),
+                        &self.config,
+                        &self.registry,
+                    ).map_err(|e| format!("{:?}", e))?;
+                    let chat_id: i64 = query.message.chat.id().into();
+                    let reply = reply.and_then(|reply| deliver(chat_id, reply, &self.config));
+                    if let Some(reply) = reply {
+                        let notice = reply.text.lines().next().unwrap_or("").to_owned();
+                        let mut request = query.message.edit_text(reply.text);
+                        if !reply.buttons.is_empty() {
+                            request.reply_markup(keyboard(reply.buttons));
                         }
+                        api.spawn(request);
+                        api.spawn(query.answer(notice));
                     }
                 }
+                _ => {}
             }
 
             Ok(())
@@ -137,4 +365,40 @@ impl BotLauncher {
 
         Ok(())
     }
+
+    fn backup_if_due(&self) {
+        if let Some(ref daily_backup) = self.daily_backup {
+            if let Err(e) = daily_backup.run_if_due() {
+                error!("daily backup failed: {}", e);
+            }
+        }
+    }
+}
+
+fn keyboard(buttons: Vec<Vec<self::reply::Button>>) -> InlineKeyboardMarkup {
+    let mut markup = InlineKeyboardMarkup::new();
+    for row in buttons {
+        markup.add_row(
+            row.into_iter()
+                .map(|button| InlineKeyboardButton::callback(button.text, button.callback_data))
+                .collect(),
+        );
+    }
+    markup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use persistence::Format;
+    use testing::{temporary_registry, TemporaryDir};
+
+    pub fn temporary() -> (Registry, Config, TemporaryDir) {
+        let (registry, path) = temporary_registry(Format::Json);
+        let config: Config = ::serde_yaml::from_str(&format!(
+            "data_path: {:?}\ntelegram_user_id: 1\nallowed_telegram_users: [1]\nmax_number_of_tries: 1\n",
+            *path
+        )).unwrap();
+        (registry, config, path)
+    }
 }
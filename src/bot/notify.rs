@@ -0,0 +1,66 @@
+use failure::Error as FailureError;
+
+use std::env;
+
+use super::reply::{Button, Reply};
+
+#[derive(Debug, Serialize)]
+struct SendMessage {
+    chat_id: i64,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_markup: Option<InlineKeyboard>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnswerCallbackQuery<'a> {
+    callback_query_id: &'a str,
+    text: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct InlineKeyboard {
+    inline_keyboard: Vec<Vec<Button>>,
+}
+
+pub fn send_message(chat_id: i64, reply: Reply) -> Result<(), FailureError> {
+    let token = env::var("TELEGRAM_BOT_TOKEN")?;
+    let reply_markup = if reply.buttons.is_empty() {
+        None
+    } else {
+        Some(InlineKeyboard {
+            inline_keyboard: reply.buttons,
+        })
+    };
+    ::reqwest::Client::new()
+        .post(&format!(
+            "https://api.telegram.org/bot{}/sendMessage",
+            token
+        )).json(&SendMessage {
+            chat_id,
+            text: reply.text,
+            reply_markup,
+        }).send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+pub fn answer_callback_query(callback_query_id: &str, text: &str) -> Result<(), FailureError> {
+    let token = env::var("TELEGRAM_BOT_TOKEN")?;
+    ::reqwest::Client::new()
+        .post(&format!(
+            "https://api.telegram.org/bot{}/answerCallbackQuery",
+            token
+        )).json(&AnswerCallbackQuery {
+            callback_query_id,
+            text,
+        }).send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+pub fn send_or_warn(chat_id: i64, reply: Reply) {
+    if let Err(e) = send_message(chat_id, reply) {
+        warn!("could not notify {}: {}", chat_id, e);
+    }
+}
@@ -0,0 +1,81 @@
+use failure::Error as FailureError;
+
+use accounting::{Actor, FiscalCode, ReceiptStatus, UserId};
+use error::AppError;
+use i18n::{fill, Locale};
+use registry::Registry;
+
+pub fn from_text(
+    text: &str,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    let payload = FiscalCode::find(text).ok_or(not_found(locale.catalogue().no_qr_in_message))?;
+    let code = FiscalCode::parse(payload)?;
+    add(code, &text.replace(payload, ""), registry, user, actor, locale)
+}
+
+pub type ScannedPhoto = Result<Option<String>, FailureError>;
+
+pub fn scan(file_id: &str) -> ScannedPhoto {
+    let photo = super::files::download(file_id)?;
+    decode(&photo)
+}
+
+pub fn from_photo(
+    scanned: ScannedPhoto,
+    caption: Option<&str>,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    let payload = scanned?.ok_or(not_found(locale.catalogue().no_qr_in_photo))?;
+    let code = FiscalCode::parse(&payload)?;
+    add(code, caption.unwrap_or(""), registry, user, actor, locale)
+}
+
+fn add(
+    code: FiscalCode,
+    name: &str,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    let text = locale.catalogue();
+    let name = match name.trim() {
+        "" => text.receipt.to_owned(),
+        name => name.to_owned(),
+    };
+    match registry.add_receipt(user, code, name, actor)? {
+        ReceiptStatus::Stored(entry) => Ok(format!(
+            "{} - {}, {} {}",
+            entry.product.name,
+            locale.amount(entry.product.price),
+            text.receipt_of,
+            locale.date_time(entry.time)
+        )),
+        ReceiptStatus::Duplicate(receipt) => Ok(fill(
+            text.receipt_duplicate,
+            &[&locale.date_time(receipt.added)],
+        )),
+    }
+}
+
+fn decode(photo: &[u8]) -> ScannedPhoto {
+    let image = ::image::load_from_memory(photo)?;
+    Ok(::bardecoder::default_decoder()
+        .decode(image)
+        .into_iter()
+        .filter_map(|result| result.ok())
+        .find(|text| FiscalCode::find(text).is_some()))
+}
+
+fn not_found(reason: &str) -> FailureError {
+    AppError::QrCodeNotFound {
+        reason: reason.to_owned(),
+    }.into()
+}
@@ -0,0 +1,105 @@
+use sha2::{Digest, Sha256};
+
+use i18n::Locale;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub text: String,
+    pub buttons: Vec<Vec<Button>>,
+    pub document: Option<Document>,
+    pub email: Option<Email>,
+    pub notifications: Vec<Notification>,
+    pub disable_preview: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Button {
+    pub text: String,
+    pub callback_data: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub file_name: String,
+    pub content: Content,
+    pub locale: Locale,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Rendered(Vec<u8>),
+    PdfFromHtml(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub address: String,
+    pub report: String,
+    pub locale: Locale,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub chat_id: i64,
+    pub reply: Reply,
+}
+
+impl Reply {
+    pub fn with_buttons(self, buttons: Vec<Vec<Button>>) -> Reply {
+        Reply { buttons, ..self }
+    }
+
+    pub fn with_document(self, document: Document) -> Reply {
+        Reply {
+            document: Some(document),
+            ..self
+        }
+    }
+
+    pub fn with_email(self, email: Email) -> Reply {
+        Reply {
+            email: Some(email),
+            ..self
+        }
+    }
+
+    pub fn with_notification(mut self, chat_id: i64, reply: Reply) -> Reply {
+        self.notifications.push(Notification { chat_id, reply });
+        self
+    }
+
+    pub fn without_preview(self) -> Reply {
+        Reply {
+            disable_preview: true,
+            ..self
+        }
+    }
+}
+
+impl From<String> for Reply {
+    fn from(text: String) -> Reply {
+        Reply {
+            text,
+            buttons: Vec::new(),
+            document: None,
+            email: None,
+            notifications: Vec::new(),
+            disable_preview: false,
+        }
+    }
+}
+
+impl Button {
+    pub fn callback<S: Into<String>>(text: S, callback_data: String) -> Button {
+        Button {
+            text: text.into(),
+            callback_data,
+        }
+    }
+}
+
+pub fn callback_key(value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(value.as_bytes());
+    format!("{:x}", hasher.result())[..8].to_owned()
+}
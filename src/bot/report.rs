@@ -1,35 +1,53 @@
 use chrono::prelude::*;
 use failure::Error as FailureError;
 
+use std::io::Write;
+use std::process::{Command, Stdio};
 use std::str::FromStr;
 
-use accounting::statistics::{Statistics, TimePeriod};
+use super::reply::{Content, Document, Email, Reply};
+use accounting::statistics::{Report, Statistics, TimePeriod};
 use accounting::UserId;
 use bot::email::EmailSender;
 use config::Config;
 use error::AppError;
+use i18n::{fill, parse_month, Locale};
 use registry::Registry;
+use representation::{CsvReportRepresentation, ReactReportRepresentation};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileFormat {
+    Html,
+    Csv,
+    Pdf,
+}
 
 pub fn report<'a, I>(
     commands: &mut I,
     config: &Config,
     registry: &Registry,
     user: UserId,
-) -> Result<String, FailureError>
+    locale: Locale,
+) -> Result<Reply, FailureError>
 where
     I: Iterator<Item = &'a str>,
 {
-    let reports = ReportFactory { registry, user };
+    let reports = ReportFactory {
+        registry,
+        user,
+        locale,
+    };
     let time_period = commands.next();
     let email = commands.next();
     let last = commands.next();
     match (time_period, email, last) {
-        (None, None, None) => reports.print_week_report(),
-        (Some(time_period), None, _) => reports.try_print_report(time_period),
-        (Some(time_period), Some(email), None) => {
-            reports.try_send_report(config, time_period, email)
-        }
-        (Some(_), Some(_), Some(_)) => Err(wrong_bot_usage()),
+        (None, None, None) => reports.print_week_report().map(Reply::from),
+        (Some(time_period), None, _) => reports.try_print_report(time_period).map(Reply::from),
+        (Some(time_period), Some(target), None) => match FileFormat::parse(target) {
+            Some(format) => reports.try_send_file(config, time_period, format),
+            None => reports.try_send_report(config, time_period, target),
+        },
+        (Some(_), Some(_), Some(_)) => Err(wrong_bot_usage(locale)),
         (None, _, _) => unreachable!(),
     }
 }
@@ -37,6 +55,7 @@ where
 struct ReportFactory<'r> {
     registry: &'r Registry,
     user: UserId,
+    locale: Locale,
 }
 
 impl<'r> ReportFactory<'r> {
@@ -54,64 +73,180 @@ impl<'r> ReportFactory<'r> {
         config: &Config,
         time_period: &str,
         email: &str,
-    ) -> Result<String, FailureError> {
-        let sender = EmailSender::from_config(config)?;
+    ) -> Result<Reply, FailureError> {
+        EmailSender::from_config(config)?;
         let time_period = parse_time_period(time_period)?;
-        let statistics = self.statistics()?;
+        let statistics = self.statistics(&time_period)?;
         let report = statistics.report(time_period)?;
+        let text = self.locale.catalogue();
         match report {
             Some(actual_report) => {
                 let react_report = format!(
                     "{}",
-                    ::representation::ReactReportRepresentation::from(actual_report)
+                    ReactReportRepresentation::from(actual_report).with_locale(self.locale)
                 );
-                sender.send(react_report, email)?;
-                Ok(format!("Отчет выслан на {}", email))
+                Ok(
+                    Reply::from(fill(text.report_sent, &[&email])).with_email(Email {
+                        address: email.to_owned(),
+                        report: react_report,
+                        locale: self.locale,
+                    }),
+                )
             }
-            None => Ok(format!("нет данных за этот период")),
+            None => Ok(Reply::from(text.no_data.to_owned())),
+        }
+    }
+
+    fn try_send_file(
+        &self,
+        config: &Config,
+        time_period: &str,
+        format: FileFormat,
+    ) -> Result<Reply, FailureError> {
+        if format == FileFormat::Pdf {
+            pdf_command(config)?;
+        }
+        let time_period = parse_time_period(time_period)?;
+        let statistics = self.statistics(&time_period)?;
+        let report = statistics.report(time_period)?;
+        let text = self.locale.catalogue();
+        match report {
+            Some(actual_report) => {
+                let file_name = format!(
+                    "report-{}-{}.{}",
+                    actual_report.period.0.format("%Y-%m-%d"),
+                    actual_report.period.1.format("%Y-%m-%d"),
+                    format.extension()
+                );
+                let caption = fill(
+                    text.report_caption,
+                    &[
+                        &self.locale.date(actual_report.period.0),
+                        &self.locale.date(actual_report.period.1),
+                    ],
+                );
+                let content = format.render(actual_report, self.locale);
+                Ok(Reply::from(caption).with_document(Document {
+                    file_name,
+                    content,
+                    locale: self.locale,
+                }))
+            }
+            None => Ok(Reply::from(text.no_data.to_owned())),
         }
     }
 
     fn print_report(&self, time_period: TimePeriod) -> Result<String, FailureError> {
-        let statistics = self.statistics()?;
+        let statistics = self.statistics(&time_period)?;
         let report = statistics.report(time_period)?;
         match report {
             Some(actual_report) => Ok(format!(
                 "{}",
                 ::representation::BotReportRepresentation::from(actual_report)
+                    .with_locale(self.locale)
             )),
-            None => Ok(format!("нет данных за этот период")),
+            None => Ok(self.locale.catalogue().no_data.to_owned()),
         }
     }
 
-    fn statistics(&self) -> Result<Statistics, FailureError> {
-        let entries = self.registry.list(self.user.clone())?;
-        let categories = self.registry.categories(self.user.clone())?;
-        Ok(Statistics::new(entries, categories))
+    fn statistics(&self, time_period: &TimePeriod) -> Result<Statistics, FailureError> {
+        self.registry.statistics_for(self.user.clone(), time_period)
     }
 }
 
-fn parse_time_period(time_period: &str) -> Result<TimePeriod, FailureError> {
+impl FileFormat {
+    fn parse(raw: &str) -> Option<FileFormat> {
+        match raw {
+            "файл" | "file" | "html" => Some(FileFormat::Html),
+            "csv" => Some(FileFormat::Csv),
+            "pdf" => Some(FileFormat::Pdf),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match *self {
+            FileFormat::Html => "html",
+            FileFormat::Csv => "csv",
+            FileFormat::Pdf => "pdf",
+        }
+    }
+
+    fn render(&self, report: Report, locale: Locale) -> Content {
+        match *self {
+            FileFormat::Html => Content::Rendered(html(report, locale).into_bytes()),
+            FileFormat::Csv => {
+                Content::Rendered(format!("{}", CsvReportRepresentation::from(report)).into_bytes())
+            }
+            FileFormat::Pdf => Content::PdfFromHtml(html(report, locale)),
+        }
+    }
+}
+
+pub fn render(config: &Config, content: Content) -> Result<Vec<u8>, FailureError> {
+    match content {
+        Content::Rendered(content) => Ok(content),
+        Content::PdfFromHtml(html) => pdf(config, html.into_bytes()),
+    }
+}
+
+pub fn send_email(config: &Config, email: Email) -> Result<(), FailureError> {
+    let sender = EmailSender::from_config(config)?;
+    sender.send(email.report, &email.address, email.locale)
+}
+
+fn html(report: Report, locale: Locale) -> String {
+    format!(
+        "{}",
+        ReactReportRepresentation::from(report).with_locale(locale)
+    )
+}
+
+fn pdf_command(config: &Config) -> Result<String, FailureError> {
+    let command = config
+        .report_pdf_command
+        .clone()
+        .ok_or(AppError::PdfNotSetUp {
+            property: "report_pdf_command".to_owned(),
+        })?;
+    Ok(command)
+}
+
+fn pdf(config: &Config, html: Vec<u8>) -> Result<Vec<u8>, FailureError> {
+    let command = pdf_command(config)?;
+    let mut child = Command::new(command)
+        .args(&["--quiet", "--javascript-delay", "1000", "-", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdin = child.stdin.take();
+    let writer = ::std::thread::spawn(move || match stdin {
+        Some(mut stdin) => stdin.write_all(&html),
+        None => Ok(()),
+    });
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(AppError::PdfConversion {
+            reason: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }.into());
+    }
+    writer
+        .join()
+        .map_err(|_| format_err!("writing the report to the pdf converter panicked"))??;
+    Ok(output.stdout)
+}
+
+pub fn parse_time_period(time_period: &str) -> Result<TimePeriod, FailureError> {
     let now = ::chrono::offset::Local::now().naive_local().date();
-    match time_period {
-        "январь" => Ok(month(now, 1)),
-        "февраль" => Ok(month(now, 2)),
-        "март" => Ok(month(now, 3)),
-        "апрель" => Ok(month(now, 4)),
-        "май" => Ok(month(now, 5)),
-        "июнь" => Ok(month(now, 6)),
-        "июль" => Ok(month(now, 7)),
-        "август" => Ok(month(now, 8)),
-        "сентябрь" => Ok(month(now, 9)),
-        "октябрь" => Ok(month(now, 10)),
-        "ноябрь" => Ok(month(now, 11)),
-        "декабрь" => Ok(month(now, 12)),
-        year @ _ => parse_year(year),
+    match parse_month(time_period) {
+        Some(number) => Ok(month(now, number)),
+        None => parse_year(time_period),
     }
 }
 
 fn parse_year(time_period: &str) -> Result<TimePeriod, FailureError> {
-    let year = i32::from_str(time_period)?;
+    let year = i32::from_str(time_period).map_err(|_| AppError::InvalidDate)?;
     let invalid_date: FailureError = AppError::InvalidDate.into();
     let start_of_year = NaiveDate::from_ymd_opt(year, 1, 1).ok_or(invalid_date)?;
     let invalid_date: FailureError = AppError::InvalidDate.into();
@@ -132,8 +267,31 @@ fn end_of(now: NaiveDate, month: u32) -> NaiveDate {
     ::dates::last_day_of_month(start_of(now, month))
 }
 
-fn wrong_bot_usage() -> FailureError {
+fn wrong_bot_usage(locale: Locale) -> FailureError {
     AppError::BotUsage {
-        reason: "ожидается два аргумента".to_owned(),
+        reason: locale.catalogue().report_usage.to_owned(),
     }.into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::temporary;
+    use super::*;
+
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn streams_large_reports_through_the_pdf_command() {
+        let (_, config, path) = temporary();
+        let command = path.join("fake-pdf");
+        fs::write(&command, "#!/bin/sh\ncat\n").unwrap();
+        fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+        let config = Config {
+            report_pdf_command: Some(command.to_string_lossy().into_owned()),
+            ..config
+        };
+        let html = vec![b'x'; 1024 * 1024];
+        assert_eq!(pdf(&config, html.clone()).unwrap(), html);
+    }
+}
@@ -0,0 +1,194 @@
+use failure::Error as FailureError;
+
+use std::str::FromStr;
+
+use accounting::{user_key, Actor, Settlement, Share, UserId};
+use error::AppError;
+use i18n::Locale;
+use registry::Registry;
+use representation::{Participants, SettleUpRepresentation, SplitRepresentation};
+
+pub fn split<'a, I>(
+    commands: &mut I,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let text = locale.catalogue();
+    let mut args: Vec<&str> = commands.collect();
+    let number = match args.first().and_then(|first| usize::from_str(first).ok()) {
+        Some(number) => {
+            args.remove(0);
+            number
+        }
+        None => 1,
+    };
+    let payer = match args.iter().position(|arg| payer_of(arg).is_some()) {
+        Some(index) => payer_of(args.remove(index)).unwrap().to_owned(),
+        None => user_key(&user),
+    };
+    if args.is_empty() || payer.is_empty() {
+        return Err(wrong_split_usage(locale));
+    }
+    let shares = Share::parse_list(&args)?;
+    let entry = registry
+        .numbered_entry(user.clone(), number)?
+        .ok_or(AppError::BotUsage {
+            reason: text.no_entries.to_owned(),
+        })?;
+    let split = registry.split_entry(user.clone(), &entry, &payer, shares, actor)?;
+    let participants = Participants::new(user.clone(), registry.member_names(user)?);
+    Ok(format!(
+        "{}",
+        SplitRepresentation::new(&entry, &split)
+            .with_locale(locale)
+            .with_participants(participants)
+    ))
+}
+
+pub fn settle<'a, I>(
+    commands: &mut I,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    match commands.next() {
+        None => {
+            let settle_up = registry.settle_up(user.clone())?;
+            let participants = Participants::new(user.clone(), registry.member_names(user)?);
+            Ok(format!(
+                "{}",
+                SettleUpRepresentation::from(&settle_up)
+                    .with_locale(locale)
+                    .with_participants(participants)
+            ))
+        }
+        Some("paid") | Some("оплатил") => {
+            let from = commands.next().ok_or(wrong_settle_usage(locale))?;
+            let to = commands.next().ok_or(wrong_settle_usage(locale))?;
+            let amount = commands
+                .next()
+                .and_then(|amount| i32::from_str(amount).ok())
+                .filter(|amount| *amount > 0)
+                .ok_or(wrong_settle_usage(locale))?;
+            let settlement = Settlement::new(user, from, to, amount);
+            registry.add_settlement(settlement, actor)?;
+            Ok(format!(
+                "{} → {}: {} {}",
+                from,
+                to,
+                locale.amount(amount),
+                locale.catalogue().settlement_recorded
+            ))
+        }
+        Some(_) => Err(wrong_settle_usage(locale)),
+    }
+}
+
+fn payer_of(arg: &str) -> Option<&str> {
+    let mut parts = arg.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some("payer"), Some(payer)) | (Some("платил"), Some(payer)) => Some(payer.trim()),
+        _ => None,
+    }
+}
+
+fn wrong_split_usage(locale: Locale) -> FailureError {
+    AppError::BotUsage {
+        reason: locale.catalogue().split_usage.to_owned(),
+    }.into()
+}
+
+fn wrong_settle_usage(locale: Locale) -> FailureError {
+    AppError::BotUsage {
+        reason: locale.catalogue().settle_usage.to_owned(),
+    }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::temporary;
+    use super::*;
+    use accounting::{Entry, TelegramId};
+
+    #[test]
+    fn splits_numbered_entry_with_another_payer() {
+        let (registry, _, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let now = ::chrono::offset::Local::now().naive_local();
+        for (minutes, raw) in [(2, "такси 600"), (1, "кофе 200")].iter() {
+            let mut entry = Entry::new(user.clone(), raw.parse().unwrap());
+            entry.time = now - ::chrono::Duration::minutes(*minutes);
+            registry.add_entry(entry, &actor).unwrap();
+        }
+
+        let mut words = "2 платил=Петя я Петя Вася".split_whitespace();
+        split(&mut words, &registry, user.clone(), &actor, Locale::Ru).unwrap();
+        let balances = registry.settle_up(user.clone()).unwrap().balances;
+        let petya = balances.iter().find(|b| b.participant == "Петя").unwrap();
+        assert_eq!(petya.amount, 400);
+
+        let mut words = "3 я Петя".split_whitespace();
+        assert!(split(&mut words, &registry, user.clone(), &actor, Locale::Ru).is_err());
+        let mut words = "платил= я".split_whitespace();
+        assert!(split(&mut words, &registry, user, &actor, Locale::Ru).is_err());
+    }
+
+    #[test]
+    fn shows_default_payer_in_the_current_locale() {
+        let (registry, _, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let entry = Entry::new(user.clone(), "ужин 300".parse().unwrap());
+        registry.add_entry(entry, &actor).unwrap();
+
+        let mut words = "я Петя".split_whitespace();
+        split(&mut words, &registry, user.clone(), &actor, Locale::Ru).unwrap();
+        let mut words = None::<&str>.into_iter();
+        let settle_up = settle(&mut words, &registry, user, &actor, Locale::En).unwrap();
+        assert!(settle_up.contains("me +"));
+        assert!(settle_up.contains("Петя → me"));
+        assert!(!settle_up.contains("я +"));
+    }
+
+    #[test]
+    fn settles_splits_of_the_whole_household() {
+        let (registry, _, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let owner = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let partner = registry.find_or_create(TelegramId(2), &actor).unwrap().id;
+        registry
+            .create_household(owner.clone(), "дом".to_owned(), "Аня".to_owned(), &actor)
+            .unwrap();
+        let code = registry
+            .invite_to_household(owner.clone(), ::chrono::Duration::hours(1), &actor)
+            .unwrap();
+        registry
+            .join_household(partner.clone(), &code, "Боря".to_owned(), &actor)
+            .unwrap();
+        let entry = Entry::new(owner.clone(), "ужин 300".parse().unwrap());
+        registry.add_entry(entry, &actor).unwrap();
+
+        let mut words = "я Петя".split_whitespace();
+        split(&mut words, &registry, partner.clone(), &actor, Locale::Ru).unwrap();
+        for user in &[owner.clone(), partner.clone()] {
+            let balances = registry.settle_up(user.clone()).unwrap().balances;
+            assert_eq!(balances.len(), 2);
+        }
+        let mut words = None::<&str>.into_iter();
+        let settle_up = settle(&mut words, &registry, owner, &actor, Locale::Ru).unwrap();
+        assert!(settle_up.contains("Петя → Боря: 150"));
+        let mut words = None::<&str>.into_iter();
+        let settle_up = settle(&mut words, &registry, partner, &actor, Locale::Ru).unwrap();
+        assert!(settle_up.contains("Петя → я: 150"));
+    }
+}
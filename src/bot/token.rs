@@ -0,0 +1,87 @@
+use failure::Error as FailureError;
+
+use accounting::{Actor, Scope, TokenId, UserId};
+use config::Config;
+use error::AppError;
+use i18n::{fill, Locale};
+use registry::Registry;
+use representation::TokenRepresentation;
+
+const LOGIN_LINK_MINUTES: i64 = 10;
+
+pub fn token<'a, I>(
+    commands: &mut I,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let text = locale.catalogue();
+    match commands.next() {
+        Some("new") => {
+            let name = commands.next().ok_or(wrong_bot_usage(locale))?;
+            let scopes = match commands.next() {
+                Some(scopes) => Scope::parse_list(scopes)?,
+                None => Scope::all(),
+            };
+            let (token, secret) = registry.issue_token(user, name.to_owned(), scopes, actor)?;
+            Ok(format!(
+                "{}\n{}\n{}",
+                TokenRepresentation::from(token),
+                text.token_shown_once,
+                secret
+            ))
+        }
+        Some("list") | None => {
+            let tokens: Vec<String> = registry
+                .tokens(user)?
+                .into_iter()
+                .map(|token| format!("{}", TokenRepresentation::from(token)))
+                .collect();
+            if tokens.is_empty() {
+                Ok(text.no_tokens.to_owned())
+            } else {
+                Ok(tokens.join("\n"))
+            }
+        }
+        Some("revoke") => {
+            let token_id = commands.next().ok_or(wrong_bot_usage(locale))?;
+            match registry.revoke_token(user, TokenId::new(token_id.to_owned()), actor)? {
+                Some(token) => Ok(format!("{}", TokenRepresentation::from(token))),
+                None => Ok(text.token_not_found.to_owned()),
+            }
+        }
+        Some(_) => Err(wrong_bot_usage(locale)),
+    }
+}
+
+pub fn login(
+    config: &Config,
+    registry: &Registry,
+    user: UserId,
+    actor: &Actor,
+    locale: Locale,
+) -> Result<String, FailureError> {
+    let web_public_url = config
+        .web_public_url
+        .as_ref()
+        .ok_or(AppError::WebLoginNotSetUp {
+            property: "web_public_url".to_owned(),
+        })?;
+    let code = registry.issue_login(user, ::chrono::Duration::minutes(LOGIN_LINK_MINUTES), actor)?;
+    Ok(format!(
+        "{}\n{}/login/{}",
+        fill(locale.catalogue().login_link, &[&LOGIN_LINK_MINUTES]),
+        web_public_url.trim_right_matches('/'),
+        code
+    ))
+}
+
+fn wrong_bot_usage(locale: Locale) -> FailureError {
+    AppError::BotUsage {
+        reason: locale.catalogue().token_usage.to_owned(),
+    }.into()
+}
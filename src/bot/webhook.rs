@@ -0,0 +1,276 @@
+use failure::Error as FailureError;
+
+use super::reply::{Button, Reply};
+use super::ScannedPhoto;
+use config::Config;
+use registry::Registry;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<Message>,
+    pub callback_query: Option<CallbackQuery>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CallbackQuery {
+    pub id: String,
+    pub from: Sender,
+    pub message: Option<Message>,
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Message {
+    pub message_id: i64,
+    pub from: Option<Sender>,
+    pub chat: Chat,
+    pub text: Option<String>,
+    pub photo: Option<Vec<PhotoSize>>,
+    pub caption: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhotoSize {
+    pub file_id: String,
+    pub width: i64,
+    pub height: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Sender {
+    pub id: i64,
+    pub first_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Chat {
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum WebhookReply {
+    Send(SendMessage),
+    Edit(EditMessageText),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SendMessage {
+    pub method: &'static str,
+    pub chat_id: i64,
+    pub text: String,
+    pub reply_to_message_id: i64,
+    pub disable_web_page_preview: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<InlineKeyboard>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EditMessageText {
+    pub method: &'static str,
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<InlineKeyboard>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InlineKeyboard {
+    pub inline_keyboard: Vec<Vec<Button>>,
+}
+
+pub fn photo(update: &Update) -> Option<(i64, &str)> {
+    let message = update.message.as_ref()?;
+    if message.text.is_some() {
+        return None;
+    }
+    let author_id = message.from.as_ref()?.id;
+    let photo = message.photo.as_ref()?.last()?;
+    Some((author_id, &photo.file_id))
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Message {
+        chat_id: i64,
+        message_id: i64,
+        reply: Reply,
+    },
+    Callback {
+        query_id: String,
+        chat_id: i64,
+        message_id: i64,
+        reply: Option<Reply>,
+    },
+}
+
+pub fn dispatch(
+    update: Update,
+    scanned: Option<ScannedPhoto>,
+    config: &Config,
+    registry: &Registry,
+) -> Result<Option<Outcome>, FailureError> {
+    trace!("webhook update {}", update.update_id);
+    if let Some(query) = update.callback_query {
+        return dispatch_callback(query, config, registry);
+    }
+    let message = match update.message {
+        Some(message) => message,
+        None => return Ok(None),
+    };
+    let (author_id, author_name) = match message.from {
+        Some(ref from) => (from.id, from.first_name.clone().unwrap_or_default()),
+        None => return Ok(None),
+    };
+    let (chat_id, message_id) = (message.chat.id, message.message_id);
+    let reply = match (message.text, message.photo) {
+        (Some(text), _) => {
+            super::respond(&text, author_id, &author_name, chat_id, config, registry)?
+        }
+        (None, Some(_)) => super::respond_photo(
+            scanned.unwrap_or(Ok(None)),
+            message.caption.as_ref().map(|caption| caption.as_str()),
+            author_id,
+            chat_id,
+            config,
+            registry,
+        )?,
+        (None, None) => None,
+    };
+    Ok(reply.map(|reply| Outcome::Message {
+        chat_id,
+        message_id,
+        reply,
+    }))
+}
+
+fn dispatch_callback(
+    query: CallbackQuery,
+    config: &Config,
+    registry: &Registry,
+) -> Result<Option<Outcome>, FailureError> {
+    let (message, data) = match (query.message, query.data) {
+        (Some(message), Some(data)) => (message, data),
+        _ => return Ok(None),
+    };
+    let author_name = query.from.first_name.unwrap_or_default();
+    let reply = super::respond_callback(
+        &data,
+        query.from.id,
+        &author_name,
+        message.chat.id,
+        config,
+        registry,
+    )?;
+    Ok(Some(Outcome::Callback {
+        query_id: query.id,
+        chat_id: message.chat.id,
+        message_id: message.message_id,
+        reply,
+    }))
+}
+
+pub fn deliver(outcome: Outcome, config: &Config) -> Option<WebhookReply> {
+    match outcome {
+        Outcome::Message {
+            chat_id,
+            message_id,
+            reply,
+        } => super::deliver(chat_id, reply, config).map(|reply| {
+            WebhookReply::Send(SendMessage {
+                method: "sendMessage",
+                chat_id,
+                text: reply.text,
+                reply_to_message_id: message_id,
+                disable_web_page_preview: reply.disable_preview,
+                reply_markup: keyboard(reply.buttons),
+            })
+        }),
+        Outcome::Callback {
+            query_id,
+            chat_id,
+            message_id,
+            reply,
+        } => {
+            let reply = reply.and_then(|reply| super::deliver(chat_id, reply, config));
+            let notice = reply
+                .as_ref()
+                .and_then(|reply| reply.text.lines().next())
+                .unwrap_or("");
+            if let Err(e) = super::notify::answer_callback_query(&query_id, notice) {
+                warn!("could not answer callback query {}: {}", &query_id, e);
+            }
+            reply.map(|reply| {
+                WebhookReply::Edit(EditMessageText {
+                    method: "editMessageText",
+                    chat_id,
+                    message_id,
+                    text: reply.text,
+                    reply_markup: keyboard(reply.buttons),
+                })
+            })
+        }
+    }
+}
+
+fn keyboard(buttons: Vec<Vec<Button>>) -> Option<InlineKeyboard> {
+    if buttons.is_empty() {
+        None
+    } else {
+        Some(InlineKeyboard {
+            inline_keyboard: buttons,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDED_UPDATE: &'static str = r#"{
+        "update_id": 10000,
+        "message": {
+            "message_id": 1365,
+            "date": 1441645532,
+            "chat": {"id": 1111111, "type": "private", "first_name": "Test"},
+            "from": {"id": 1111111, "is_bot": false, "first_name": "Test"},
+            "text": "/help"
+        }
+    }"#;
+
+    #[test]
+    fn reads_recorded_update() {
+        let update: Update = ::serde_json::from_str(RECORDED_UPDATE).unwrap();
+        let message = update.message.unwrap();
+        assert_eq!(message.from.unwrap().id, 1111111);
+        assert_eq!(message.chat.id, 1111111);
+        assert_eq!(message.text.unwrap(), "/help");
+    }
+
+    #[test]
+    fn reads_recorded_callback_query() {
+        let update: Update = ::serde_json::from_str(
+            r#"{
+                "update_id": 10001,
+                "callback_query": {
+                    "id": "4382bfdwdsb323b2d9",
+                    "from": {"id": 1111111, "is_bot": false, "first_name": "Test"},
+                    "chat_instance": "42",
+                    "data": "cat:9e1b2c6e-0000-0000-0000-000000000000:еда",
+                    "message": {
+                        "message_id": 1366,
+                        "date": 1441645532,
+                        "chat": {"id": 1111111, "type": "private"},
+                        "text": "хлеб - 50 руб."
+                    }
+                }
+            }"#,
+        ).unwrap();
+        let query = update.callback_query.unwrap();
+        assert_eq!(query.from.id, 1111111);
+        assert_eq!(query.message.unwrap().message_id, 1366);
+        assert!(query.data.unwrap().starts_with("cat:"));
+    }
+}
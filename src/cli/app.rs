@@ -5,7 +5,13 @@ use failure::Error as FailureError;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use chrono::{NaiveDate, NaiveDateTime};
+
 use accounting::statistics::TimePeriod;
+use accounting::{AuditFilter, Scope, SmsFilter};
+use dates::{end_of_day, start_of_day};
+use i18n::Locale;
+use persistence::Format;
 
 #[derive(Debug)]
 pub struct App {
@@ -20,12 +26,20 @@ pub enum Command {
     User(UserCmd),
     Category(CategoryCmd),
     Report(TimePeriod, bool),
+    Backup(BackupCmd),
+    Fsck(bool),
+    Convert(Format),
+    Audit(AuditFilter),
+    Token(TokenCmd),
+    Sms(SmsCmd),
+    Split(SplitCmd),
 }
 
 #[derive(Debug)]
 pub enum EntryCmd {
     List,
     Add(String),
+    AddFromStdin,
 }
 
 #[derive(Debug)]
@@ -40,6 +54,33 @@ pub enum UserCmd {
     List,
     Add(i64),
     SetTimezone(i32),
+    SetLocale(Locale),
+}
+
+#[derive(Debug)]
+pub enum BackupCmd {
+    Create,
+    List,
+    Restore(PathBuf),
+}
+
+#[derive(Debug)]
+pub enum TokenCmd {
+    New(String, Vec<Scope>),
+    List,
+    Revoke(String),
+}
+
+#[derive(Debug)]
+pub enum SmsCmd {
+    List(SmsFilter),
+}
+
+#[derive(Debug)]
+pub enum SplitCmd {
+    Last(usize, Option<String>, Vec<String>),
+    Paid(String, String, i32),
+    Settle,
 }
 
 #[derive(Debug)]
@@ -66,9 +107,10 @@ impl App {
                         Arg::with_name("add")
                             .short("a")
                             .long("add")
-                            .help("adds new entry")
+                            .help("adds new entry, \"-\" or no INPUT reads entries from stdin")
                             .value_name("INPUT")
                             .takes_value(true)
+                            .min_values(0)
                             .conflicts_with("list")
                             .required_unless_one(&["list"]),
                     ).arg(
@@ -119,8 +161,8 @@ impl App {
                             .short("l")
                             .long("list")
                             .help("lists all users")
-                            .conflicts_with_all(&["add", "timezone"])
-                            .required_unless_one(&["add", "timezone"]),
+                            .conflicts_with_all(&["add", "timezone", "locale"])
+                            .required_unless_one(&["add", "timezone", "locale"]),
                     ).arg(
                         Arg::with_name("add")
                             .short("a")
@@ -128,8 +170,8 @@ impl App {
                             .help("adds new user")
                             .value_name("TELEGRAM_ID")
                             .takes_value(true)
-                            .conflicts_with_all(&["list", "timezone"])
-                            .required_unless_one(&["list", "timezone"]),
+                            .conflicts_with_all(&["list", "timezone", "locale"])
+                            .required_unless_one(&["list", "timezone", "locale"]),
                     ).arg(
                         Arg::with_name("timezone")
                             .short("t")
@@ -137,8 +179,17 @@ impl App {
                             .help("sets current user timezone")
                             .value_name("TIMEZONE_OFFSET_MINUTES")
                             .takes_value(true)
-                            .conflicts_with_all(&["list", "add"])
-                            .required_unless_one(&["list", "add"]),
+                            .conflicts_with_all(&["list", "add", "locale"])
+                            .required_unless_one(&["list", "add", "locale"]),
+                    ).arg(
+                        Arg::with_name("locale")
+                            .long("locale")
+                            .help("sets current user language of reports and bot replies")
+                            .value_name("LOCALE")
+                            .takes_value(true)
+                            .possible_values(&["ru", "en"])
+                            .conflicts_with_all(&["list", "add", "timezone"])
+                            .required_unless_one(&["list", "add", "timezone"]),
                     ),
             ).subcommand(
                 SubCommand::with_name("category")
@@ -177,6 +228,217 @@ impl App {
                             .long("html")
                             .help("prints html representation"),
                     ),
+            ).subcommand(
+                SubCommand::with_name("backup")
+                    .about("controls backups of the data directory")
+                    .arg(
+                        Arg::with_name("create")
+                            .short("c")
+                            .long("create")
+                            .help("creates a new backup")
+                            .conflicts_with_all(&["list", "restore"])
+                            .required_unless_one(&["list", "restore"]),
+                    ).arg(
+                        Arg::with_name("list")
+                            .short("l")
+                            .long("list")
+                            .help("lists existing backups")
+                            .conflicts_with_all(&["create", "restore"])
+                            .required_unless_one(&["create", "restore"]),
+                    ).arg(
+                        Arg::with_name("restore")
+                            .short("r")
+                            .long("restore")
+                            .help("verifies and restores a backup")
+                            .value_name("ARCHIVE")
+                            .takes_value(true)
+                            .conflicts_with_all(&["create", "list"])
+                            .required_unless_one(&["create", "list"]),
+                    ),
+            ).subcommand(
+                SubCommand::with_name("fsck")
+                    .about("checks integrity of all tables")
+                    .arg(
+                        Arg::with_name("repair")
+                            .short("r")
+                            .long("repair")
+                            .help("moves problematic lines to quarantine files"),
+                    ),
+            ).subcommand(
+                SubCommand::with_name("convert")
+                    .about("converts all tables to another storage format")
+                    .arg(
+                        Arg::with_name("to")
+                            .short("t")
+                            .long("to")
+                            .help("target storage format")
+                            .value_name("FORMAT")
+                            .possible_values(&["json", "bincode"])
+                            .takes_value(true)
+                            .required(true),
+                    ),
+            ).subcommand(
+                SubCommand::with_name("audit")
+                    .about("queries the audit log of changes")
+                    .arg(
+                        Arg::with_name("table")
+                            .short("t")
+                            .long("table")
+                            .help("shows changes of one table only")
+                            .value_name("TABLE")
+                            .possible_values(&[
+                                "users",
+                                "entries",
+                                "categories",
+                                "sms",
+                                "tokens",
+                                "logins",
+                                "receipts",
+                                "households",
+                                "members",
+                                "invites",
+                                "splits",
+                                "settlements",
+                                "access",
+                            ])
+                            .takes_value(true),
+                    ).arg(
+                        Arg::with_name("entity")
+                            .short("e")
+                            .long("entity")
+                            .help("shows changes of one entity only")
+                            .value_name("ID")
+                            .takes_value(true),
+                    ).arg(
+                        Arg::with_name("from")
+                            .short("f")
+                            .long("from")
+                            .help("shows changes made since this date")
+                            .value_name("YYYY-MM-DD")
+                            .takes_value(true),
+                    ).arg(
+                        Arg::with_name("till")
+                            .long("till")
+                            .help("shows changes made until this date")
+                            .value_name("YYYY-MM-DD")
+                            .takes_value(true),
+                    ),
+            ).subcommand(
+                SubCommand::with_name("token")
+                    .about("controls api tokens")
+                    .arg(
+                        Arg::with_name("new")
+                            .short("n")
+                            .long("new")
+                            .help("issues a new token, it is shown only once")
+                            .value_name("NAME")
+                            .takes_value(true)
+                            .conflicts_with_all(&["list", "revoke"])
+                            .required_unless_one(&["list", "revoke"]),
+                    ).arg(
+                        Arg::with_name("scopes")
+                            .short("s")
+                            .long("scopes")
+                            .help("comma separated scopes of a new token")
+                            .value_name("SCOPES")
+                            .takes_value(true)
+                            .default_value("sms-upload,read,write"),
+                    ).arg(
+                        Arg::with_name("list")
+                            .short("l")
+                            .long("list")
+                            .help("lists all tokens")
+                            .conflicts_with_all(&["new", "revoke"])
+                            .required_unless_one(&["new", "revoke"]),
+                    ).arg(
+                        Arg::with_name("revoke")
+                            .short("r")
+                            .long("revoke")
+                            .help("revokes a token")
+                            .value_name("TOKEN_ID")
+                            .takes_value(true)
+                            .conflicts_with_all(&["new", "list"])
+                            .required_unless_one(&["new", "list"]),
+                    ),
+            ).subcommand(
+                SubCommand::with_name("sms")
+                    .about("browses stored sms")
+                    .arg(
+                        Arg::with_name("list")
+                            .short("l")
+                            .long("list")
+                            .help("lists sms, newest first")
+                            .required(true),
+                    ).arg(
+                        Arg::with_name("sender")
+                            .short("s")
+                            .long("sender")
+                            .help("shows sms of one sender only")
+                            .value_name("SENDER")
+                            .takes_value(true),
+                    ).arg(
+                        Arg::with_name("search")
+                            .short("q")
+                            .long("search")
+                            .help("shows sms containing this text")
+                            .value_name("TEXT")
+                            .takes_value(true),
+                    ).arg(
+                        Arg::with_name("from")
+                            .short("f")
+                            .long("from")
+                            .help("shows sms received since this date")
+                            .value_name("YYYY-MM-DD")
+                            .takes_value(true),
+                    ).arg(
+                        Arg::with_name("till")
+                            .long("till")
+                            .help("shows sms received until this date")
+                            .value_name("YYYY-MM-DD")
+                            .takes_value(true),
+                    ),
+            ).subcommand(
+                SubCommand::with_name("split")
+                    .about("splits expenses between people")
+                    .arg(
+                        Arg::with_name("last")
+                            .long("last")
+                            .help("splits an entry among participants, e.g. --last я Петя:2")
+                            .value_name("PARTICIPANT[:SHARE]")
+                            .takes_value(true)
+                            .multiple(true)
+                            .conflicts_with_all(&["paid", "settle"])
+                            .required_unless_one(&["paid", "settle"]),
+                    ).arg(
+                        Arg::with_name("entry")
+                            .long("entry")
+                            .help("splits the entry with this number in the list, newest first")
+                            .value_name("NUMBER")
+                            .takes_value(true)
+                            .requires("last"),
+                    ).arg(
+                        Arg::with_name("payer")
+                            .long("payer")
+                            .help("who paid for the entry, you by default")
+                            .value_name("NAME")
+                            .takes_value(true)
+                            .requires("last"),
+                    ).arg(
+                        Arg::with_name("paid")
+                            .long("paid")
+                            .help("records a payment between participants")
+                            .value_names(&["FROM", "TO", "AMOUNT"])
+                            .takes_value(true)
+                            .number_of_values(3)
+                            .conflicts_with_all(&["last", "settle"])
+                            .required_unless_one(&["last", "settle"]),
+                    ).arg(
+                        Arg::with_name("settle")
+                            .long("settle")
+                            .help("shows balances and transfers that settle all debts")
+                            .conflicts_with_all(&["last", "paid"])
+                            .required_unless_one(&["last", "paid"]),
+                    ),
             ).get_matches();
 
         let config_path = matches.value_of("config").map(PathBuf::from);
@@ -192,6 +454,20 @@ impl App {
                 Command::Category(category(category_matches))
             } else if let Some(report_matches) = matches.subcommand_matches("report") {
                 Command::Report(report(report_matches)?, report_matches.is_present("html"))
+            } else if let Some(backup_matches) = matches.subcommand_matches("backup") {
+                Command::Backup(backup(backup_matches))
+            } else if let Some(fsck_matches) = matches.subcommand_matches("fsck") {
+                Command::Fsck(fsck_matches.is_present("repair"))
+            } else if let Some(convert_matches) = matches.subcommand_matches("convert") {
+                Command::Convert(Format::from_str(convert_matches.value_of("to").unwrap())?)
+            } else if let Some(audit_matches) = matches.subcommand_matches("audit") {
+                Command::Audit(audit(audit_matches)?)
+            } else if let Some(token_matches) = matches.subcommand_matches("token") {
+                Command::Token(token(token_matches)?)
+            } else if let Some(sms_matches) = matches.subcommand_matches("sms") {
+                Command::Sms(SmsCmd::List(sms(sms_matches)?))
+            } else if let Some(split_matches) = matches.subcommand_matches("split") {
+                Command::Split(split(split_matches)?)
             } else {
                 unreachable!()
             }
@@ -209,8 +485,10 @@ fn report(matches: &ArgMatches) -> Result<TimePeriod, FailureError> {
 }
 
 fn entry(matches: &ArgMatches) -> EntryCmd {
-    if let Some(new_entry) = matches.value_of("add") {
+    if let Some(new_entry) = matches.value_of("add").filter(|input| *input != "-") {
         EntryCmd::Add(new_entry.to_owned())
+    } else if matches.is_present("add") {
+        EntryCmd::AddFromStdin
     } else if matches.is_present("list") {
         EntryCmd::List
     } else {
@@ -240,6 +518,8 @@ fn user(matches: &ArgMatches) -> Result<UserCmd, FailureError> {
         Ok(UserCmd::List)
     } else if let Some(timezone) = matches.value_of("timezone") {
         Ok(UserCmd::SetTimezone(i32::from_str(timezone)?))
+    } else if let Some(locale) = matches.value_of("locale") {
+        Ok(UserCmd::SetLocale(Locale::from_str(locale)?))
     } else {
         unreachable!()
     }
@@ -257,3 +537,83 @@ fn category(matches: &ArgMatches) -> CategoryCmd {
         unreachable!()
     }
 }
+
+fn backup(matches: &ArgMatches) -> BackupCmd {
+    if matches.is_present("create") {
+        BackupCmd::Create
+    } else if matches.is_present("list") {
+        BackupCmd::List
+    } else if let Some(archive) = matches.value_of("restore") {
+        BackupCmd::Restore(PathBuf::from(archive))
+    } else {
+        unreachable!()
+    }
+}
+
+fn token(matches: &ArgMatches) -> Result<TokenCmd, FailureError> {
+    if let Some(name) = matches.value_of("new") {
+        let scopes = Scope::parse_list(matches.value_of("scopes").unwrap_or(""))?;
+        Ok(TokenCmd::New(name.to_owned(), scopes))
+    } else if matches.is_present("list") {
+        Ok(TokenCmd::List)
+    } else if let Some(token_id) = matches.value_of("revoke") {
+        Ok(TokenCmd::Revoke(token_id.to_owned()))
+    } else {
+        unreachable!()
+    }
+}
+
+fn split(matches: &ArgMatches) -> Result<SplitCmd, FailureError> {
+    if let Some(participants) = matches.values_of("last") {
+        let number = match matches.value_of("entry") {
+            Some(number) => usize::from_str(number)?,
+            None => 1,
+        };
+        let payer = matches.value_of("payer").map(str::to_owned);
+        let participants = participants.map(str::to_owned).collect();
+        Ok(SplitCmd::Last(number, payer, participants))
+    } else if let Some(mut paid) = matches.values_of("paid") {
+        let from = paid.next().unwrap().to_owned();
+        let to = paid.next().unwrap().to_owned();
+        let amount = i32::from_str(paid.next().unwrap())?;
+        Ok(SplitCmd::Paid(from, to, amount))
+    } else if matches.is_present("settle") {
+        Ok(SplitCmd::Settle)
+    } else {
+        unreachable!()
+    }
+}
+
+fn sms(matches: &ArgMatches) -> Result<SmsFilter, FailureError> {
+    let (from, till) = date_range(matches)?;
+    Ok(SmsFilter {
+        sender: matches.value_of("sender").map(str::to_owned),
+        text: matches.value_of("search").map(str::to_owned),
+        from,
+        till,
+    })
+}
+
+fn date_range(
+    matches: &ArgMatches,
+) -> Result<(Option<NaiveDateTime>, Option<NaiveDateTime>), FailureError> {
+    let from = match matches.value_of("from") {
+        Some(date) => Some(NaiveDate::parse_from_str(date, "%Y-%m-%d")?.and_time(start_of_day())),
+        None => None,
+    };
+    let till = match matches.value_of("till") {
+        Some(date) => Some(NaiveDate::parse_from_str(date, "%Y-%m-%d")?.and_time(end_of_day())),
+        None => None,
+    };
+    Ok((from, till))
+}
+
+fn audit(matches: &ArgMatches) -> Result<AuditFilter, FailureError> {
+    let (from, till) = date_range(matches)?;
+    Ok(AuditFilter {
+        table: matches.value_of("table").map(str::to_owned),
+        entity_id: matches.value_of("entity").map(str::to_owned),
+        from,
+        till,
+    })
+}
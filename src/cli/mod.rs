@@ -1,11 +1,13 @@
 use failure::Error as FailureError;
 
+use std::io::Read;
+use std::path::Path;
 use std::str::FromStr;
 
 mod app;
 
 use self::app::*;
-use accounting::{Entry, Product, TelegramId};
+use accounting::{Actor, Batch, Entry, Product, Settlement, Share, TelegramId, TokenId};
 use error::AppError;
 use registry::Registry;
 
@@ -34,77 +36,248 @@ fn start_cli(app: App) -> Result<(), FailureError> {
     let config = crate::config::config(&app.config_path)?;
     let config_without_passwords = crate::config::Config {
         email_smtp_credential_password: None,
+        session_secret: None,
+        telegram_webhook_secret: None,
         ..config.clone()
     };
     info!("config: {:?}", &config_without_passwords);
-    let registry = Registry::new(config.data_path.clone().into())?;
+    let registry = Registry::new(
+        config.data_path.clone().into(),
+        config.storage_format.unwrap_or_default(),
+    )?;
     info!("registry created");
+    let actor = Actor::Cli;
 
     match app.command {
         Command::Entry(EntryCmd::List) => {
-            let user = registry.find_or_create(TelegramId(config.telegram_user_id))?;
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            let locale = registry.locale(&user.id)?;
             for entry in registry.list(user.id)? {
                 println!(
                     "{}",
-                    crate::representation::EntryRepresentation::from(entry)
+                    crate::representation::EntryRepresentation::from(entry).with_locale(locale)
                 );
             }
         }
         Command::Entry(EntryCmd::Add(new_entry)) => {
-            let user = registry.find_or_create(TelegramId(config.telegram_user_id))?;
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
             let parsed_new_product = Product::from_str(&new_entry)?;
             let new_entry = Entry::new(user.id, parsed_new_product);
-            registry.add_entry(new_entry)?;
+            registry.add_entry(new_entry, &actor)?;
+        }
+        Command::Entry(EntryCmd::AddFromStdin) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            let locale = registry.locale(&user.id)?;
+            let mut input = String::new();
+            ::std::io::stdin().read_to_string(&mut input)?;
+            let batch = Batch::parse(&input, user.id, ::chrono::offset::Local::now().naive_local());
+            for entry in &batch.entries {
+                registry.add_entry(entry.clone(), &actor)?;
+            }
+            println!(
+                "{}",
+                crate::representation::BatchRepresentation::from(&batch).with_locale(locale)
+            );
         }
         Command::Migrate(MigrateCmd::Add(field_name, value)) => {
-            registry
-                .migrate_entries(::persistence::Migration::add_from_str(field_name, &value)?)?;
+            registry.migrate_entries(
+                ::persistence::Migration::add_from_str(field_name, &value)?,
+                &actor,
+            )?;
         }
         Command::Migrate(MigrateCmd::Remove(field_name)) => {
-            registry.migrate_entries(::persistence::Migration::remove(field_name))?;
+            registry.migrate_entries(::persistence::Migration::remove(field_name), &actor)?;
         }
         Command::Migrate(MigrateCmd::GenerateUid(field_name)) => {
-            registry.migrate_entries(::persistence::Migration::generate_uid(field_name))?;
+            registry.migrate_entries(::persistence::Migration::generate_uid(field_name), &actor)?;
         }
         Command::User(UserCmd::List) => for user in registry.list_users()? {
             println!("{:?}", user);
         },
         Command::User(UserCmd::Add(user_telegram_id)) => {
-            let user = registry.find_or_create(TelegramId(user_telegram_id))?;
+            let user = registry.find_or_create(TelegramId(user_telegram_id), &actor)?;
             println!("{:?}", user);
         }
         Command::User(UserCmd::SetTimezone(offset_in_minutes)) => {
-            let user = registry.find_or_create(TelegramId(config.telegram_user_id))?;
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
             let offset = chrono::NaiveDateTime::from_timestamp(offset_in_minutes as i64 * 60, 0);
-            registry.update_user(user.id, |ref mut u| u.offset = Some(offset))?;
+            registry.update_user(user.id, |ref mut u| u.offset = Some(offset), &actor)?;
+        }
+        Command::User(UserCmd::SetLocale(locale)) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            registry.set_locale(user.id, locale, &actor)?;
         }
         Command::Category(CategoryCmd::List) => {
-            let user = registry.find_or_create(TelegramId(config.telegram_user_id))?;
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
             for category in registry.categories(user.id)? {
                 println!("{:?}", category);
             }
         }
         Command::Category(CategoryCmd::Add(product_name, category_name)) => {
-            let user = registry.find_or_create(TelegramId(config.telegram_user_id))?;
-            registry.add_category(user.id, product_name, category_name)?;
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            registry.add_category(user.id, product_name, category_name, &actor)?;
         }
         Command::Report(time_period, html) => {
-            let user = registry.find_or_create(TelegramId(config.telegram_user_id))?;
-            let stats = registry.statistics(user.id)?;
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            let locale = registry.locale(&user.id)?;
+            let stats = registry.statistics_for(user.id, &time_period)?;
             let err: FailureError = AppError::NoDataForPeriod.into();
             let report = stats.report(time_period)?.ok_or(err)?;
             if html {
                 println!(
                     "{}",
                     crate::representation::ReactReportRepresentation::from(report)
+                        .with_locale(locale)
+                );
+            } else {
+                println!(
+                    "{}",
+                    crate::representation::ReportRepresentation::from(report).with_locale(locale)
+                );
+            }
+        }
+        Command::Backup(BackupCmd::Create) => {
+            let backup = ::backup::create(
+                Path::new(&config.data_path),
+                &::backup::backup_path(&config),
+            )?;
+            println!("{}", backup.path.display());
+        }
+        Command::Backup(BackupCmd::List) => {
+            for backup in ::backup::list(&::backup::backup_path(&config))? {
+                println!(
+                    "{} {} ({} tables)",
+                    backup.manifest.created.format("%Y-%m-%d %H:%M:%S"),
+                    backup.path.display(),
+                    backup.manifest.files.len()
                 );
+            }
+        }
+        Command::Backup(BackupCmd::Restore(archive)) => {
+            ::backup::verify(&archive)?;
+            let safety_backup = ::backup::create(
+                Path::new(&config.data_path),
+                &::backup::backup_path(&config),
+            )?;
+            println!("current data saved to {}", safety_backup.path.display());
+            let manifest = ::backup::restore(&archive, Path::new(&config.data_path))?;
+            for file in manifest.files {
+                println!("restored {} ({} bytes)", file.name, file.size);
+            }
+        }
+        Command::Fsck(repair) => {
+            let report = registry.fsck()?;
+            for problem in &report.problems {
+                println!("{}", problem);
+            }
+            if report.is_clean() {
+                println!("no problems found");
+            } else if repair {
+                let quarantined = registry.repair(&report)?;
+                println!("{} lines moved to quarantine", quarantined);
             } else {
+                println!(
+                    "{} problems found, run with --repair to quarantine them",
+                    report.problems.len()
+                );
+            }
+        }
+        Command::Audit(filter) => {
+            for record in registry.audit_log(&filter)? {
+                println!(
+                    "{}",
+                    crate::representation::AuditRepresentation::from(record)
+                );
+            }
+        }
+        Command::Token(TokenCmd::New(name, scopes)) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            let (token, secret) = registry.issue_token(user.id, name, scopes, &actor)?;
+            println!(
+                "{}",
+                crate::representation::TokenRepresentation::from(token)
+            );
+            println!("{}", secret);
+        }
+        Command::Token(TokenCmd::List) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            for token in registry.tokens(user.id)? {
+                println!(
+                    "{}",
+                    crate::representation::TokenRepresentation::from(token)
+                );
+            }
+        }
+        Command::Token(TokenCmd::Revoke(token_id)) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            match registry.revoke_token(user.id, TokenId::new(token_id), &actor)? {
+                Some(token) => println!(
+                    "{}",
+                    crate::representation::TokenRepresentation::from(token)
+                ),
+                None => println!("no active token with such id"),
+            }
+        }
+        Command::Split(SplitCmd::Last(number, payer, participants)) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            let participants: Vec<&str> = participants.iter().map(String::as_str).collect();
+            let shares = Share::parse_list(&participants)?;
+            let locale = registry.locale(&user.id)?;
+            match registry.numbered_entry(user.id.clone(), number)? {
+                Some(entry) => {
+                    let payer = payer.unwrap_or(crate::accounting::user_key(&user.id));
+                    let split =
+                        registry.split_entry(user.id.clone(), &entry, &payer, shares, &actor)?;
+                    let participants = crate::representation::Participants::new(
+                        user.id.clone(),
+                        registry.member_names(user.id)?,
+                    );
+                    println!(
+                        "{}",
+                        crate::representation::SplitRepresentation::new(&entry, &split)
+                            .with_locale(locale)
+                            .with_participants(participants)
+                    );
+                }
+                None => println!("no entry to split with number {}", number),
+            }
+        }
+        Command::Split(SplitCmd::Paid(from, to, amount)) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            registry.add_settlement(Settlement::new(user.id, &from, &to, amount), &actor)?;
+        }
+        Command::Split(SplitCmd::Settle) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            let locale = registry.locale(&user.id)?;
+            let settle_up = registry.settle_up(user.id.clone())?;
+            let participants = crate::representation::Participants::new(
+                user.id.clone(),
+                registry.member_names(user.id)?,
+            );
+            println!(
+                "{}",
+                crate::representation::SettleUpRepresentation::from(&settle_up)
+                    .with_locale(locale)
+                    .with_participants(participants)
+            );
+        }
+        Command::Sms(SmsCmd::List(filter)) => {
+            let user = registry.find_or_create(TelegramId(config.telegram_user_id), &actor)?;
+            for sms in registry.search_sms(&user, &filter)? {
                 println!(
                     "{}",
-                    crate::representation::ReportRepresentation::from(report)
+                    crate::representation::SmsRepresentation::new(sms, &user)
                 );
             }
         }
+        Command::Convert(format) => {
+            let mut registry = registry;
+            registry.convert(format)?;
+            println!(
+                "converted to {:?}, set \"storage_format\" in config accordingly",
+                format
+            );
+        }
     }
 
     Ok(())
@@ -4,16 +4,52 @@ use std::path::PathBuf;
 
 use failure::Error as FailureError;
 
+use persistence::Format;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TelegramMode {
+    Polling,
+    Webhook,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub data_path: String,
+    pub storage_format: Option<Format>,
     pub telegram_user_id: i64,
     pub allowed_telegram_users: Vec<i64>,
+    pub admin_telegram_users: Option<Vec<i64>>,
     pub max_number_of_tries: i32,
+    pub telegram_mode: Option<TelegramMode>,
+    pub telegram_webhook_secret: Option<String>,
+    pub bot_dialog_timeout_minutes: Option<i64>,
     pub email_from: Option<String>,
     pub email_smtp_host: Option<String>,
     pub email_smtp_credential_username: Option<String>,
     pub email_smtp_credential_password: Option<String>,
+    pub report_pdf_command: Option<String>,
+    pub backup_path: Option<String>,
+    pub daily_backup: Option<bool>,
+    pub backup_retention: Option<usize>,
+    pub web_public_url: Option<String>,
+    pub session_secret: Option<String>,
+    pub session_lifetime_hours: Option<i64>,
+    pub web_bind: Option<Vec<String>>,
+    pub web_workers: Option<usize>,
+    pub web_json_limit_bytes: Option<usize>,
+    pub web_sms_limit_bytes: Option<usize>,
+    pub web_tls_cert: Option<String>,
+    pub web_tls_key: Option<String>,
+    pub web_keep_alive_seconds: Option<usize>,
+    pub web_client_timeout_ms: Option<u64>,
+    pub web_shutdown_timeout_seconds: Option<u16>,
+}
+
+impl Default for TelegramMode {
+    fn default() -> TelegramMode {
+        TelegramMode::Polling
+    }
 }
 
 pub fn config(config_path: &Option<PathBuf>) -> Result<Config, FailureError> {
@@ -6,8 +6,8 @@ pub enum AppError {
     ActixError { code: i32 },
     #[fail(display = "Not implemented yet")]
     NotImplementedYet,
-    #[fail(display = "{}", text)]
-    Any { text: &'static str },
+    #[fail(display = "a line must contain a product and a price")]
+    ProductAndPriceRequired,
     #[fail(display = "data path {:?} is not a directory", used_path)]
     InvalidRegistryPath { used_path: PathBuf },
     #[fail(display = "could not start bot after number of launches")]
@@ -31,6 +31,72 @@ pub enum AppError {
         property
     )]
     EmailNotSetUp { property: String },
+    #[fail(
+        display = "web login is not set up: add \"{}\" property in config",
+        property
+    )]
+    WebLoginNotSetUp { property: String },
+    #[fail(display = "could not bind web server to {}: {}", address, reason)]
+    WebBind { address: String, reason: String },
+    #[fail(display = "invalid web server config: {}", reason)]
+    InvalidWebConfig { reason: String },
+    #[fail(display = "invalid receipt QR code: {}", reason)]
+    InvalidReceipt { reason: String },
+    #[fail(display = "could not read QR code from the photo: {}", reason)]
+    QrCodeNotFound { reason: String },
+    #[fail(display = "telegram file transfer failed: {}", reason)]
+    TelegramFile { reason: String },
+    #[fail(
+        display = "pdf reports are not set up: add \"{}\" property in config",
+        property
+    )]
+    PdfNotSetUp { property: String },
+    #[fail(display = "could not convert report to pdf: {}", reason)]
+    PdfConversion { reason: String },
+    #[fail(display = "invalid split: {}", reason)]
+    InvalidSplit { reason: SplitError },
+    #[fail(display = "household: {}", reason)]
+    Household { reason: HouseholdError },
+    #[fail(display = "the entry belongs to another household member")]
+    NotYourEntry,
     #[fail(display = "wrong bot usage: {}", reason)]
     BotUsage { reason: String },
+    #[fail(display = "backup {:?} already exists", path)]
+    BackupAlreadyExists { path: PathBuf },
+    #[fail(display = "backup is corrupted: {}", reason)]
+    BackupCorrupted { reason: String },
+    #[fail(display = "backup retention must keep at least one backup")]
+    InvalidBackupRetention,
+    #[fail(
+        display = "migrations need json tables: run \"convert --to json\" first and \"convert --to bincode\" afterwards"
+    )]
+    MigrationNeedsJson,
+}
+
+#[derive(Debug, Fail)]
+pub enum HouseholdError {
+    #[fail(display = "already a member of a household")]
+    AlreadyMember,
+    #[fail(display = "only the owner can invite")]
+    OnlyOwnerInvites,
+    #[fail(display = "invite code is invalid or expired")]
+    InvalidInvite,
+    #[fail(display = "household no longer exists")]
+    HouseholdGone,
+    #[fail(display = "only the owner can remove members")]
+    OnlyOwnerRemoves,
+    #[fail(display = "not a member of a household")]
+    NotAMember,
+}
+
+#[derive(Debug, Fail)]
+pub enum SplitError {
+    #[fail(display = "at least one participant is required")]
+    ParticipantRequired,
+    #[fail(display = "participant name must not be empty")]
+    EmptyParticipant,
+    #[fail(display = "share of {} is not a number", _0)]
+    ShareNotANumber(String),
+    #[fail(display = "share of {} must be positive", _0)]
+    ShareNotPositive(String),
 }
@@ -0,0 +1,117 @@
+use super::Catalogue;
+
+pub static CATALOGUE: Catalogue = Catalogue {
+    months: [
+        "january", "february", "march", "april", "may", "june", "july", "august", "september",
+        "october", "november", "december",
+    ],
+    currency: "RUB",
+    thousands_separator: ',',
+    date_format: "%Y-%m-%d",
+    date_time_format: "%Y-%m-%d %H:%M",
+    me: "me",
+    error: "Error",
+    no_data: "no data for this period",
+    no_entries: "no entries",
+    total: "Total",
+    total_spent: "Total spent",
+    total_entries: "Entries",
+    by_category_header: "By category:                        spent (RUB),       %,  entries",
+    by_member: "By member:",
+    units: "pcs.",
+    member_since: "since",
+    added: "Added",
+    not_added: "Not added",
+    line: "line",
+    product_and_price_required: "A line must contain a product and a price",
+    household_error: "Household",
+    invalid_split: "Invalid split",
+    wrong_usage: "Wrong usage",
+    already_in_household: "already a member of a household",
+    only_owner_invites: "only the owner can invite",
+    invalid_invite: "invite code is invalid or expired",
+    household_gone: "household no longer exists",
+    only_owner_removes: "only the owner can remove members",
+    not_a_member: "not a member of a household",
+    participant_required: "at least one participant is required",
+    empty_participant: "participant name must not be empty",
+    share_not_a_number: "share of {} is not a number",
+    share_not_positive: "share of {} must be positive",
+    paid_by: "paid by",
+    no_debts: "No debts",
+    balances: "Balances:",
+    transfers: "Transfers:",
+    settlement_recorded: "recorded",
+    split_usage: "/split [number from /list] [payer=<name>] <participant[:share]>... splits an entry, the latest one paid by you (\"me\") by default",
+    settle_usage: "/settle [paid <from> <to> <amount>]",
+    list_page: "Entries {}-{} of {}:",
+    page_back: "← back",
+    page_next: "next →",
+    list_usage: "/list [count] [period] [product]",
+    cancelled: "Cancelled",
+    nothing_to_cancel: "Nothing to cancel",
+    confirm_delete: "Delete \"{}\"? yes/no, /cancel to abort",
+    deleted: "Deleted: {}",
+    already_deleted: "The entry is already deleted",
+    kept: "The entry is kept",
+    answer_yes_or_no: "Answer \"yes\" or \"no\", /cancel to abort",
+    send_category_name: "Send a category name, /cancel to abort",
+    new_category: "new…",
+    ask_new_category: "Send a name of the new category, /cancel to abort",
+    entry_not_found: "entry not found",
+    category_usage: "/category <category> <product>",
+    token_shown_once: "The token is shown only once:",
+    no_tokens: "No tokens",
+    token_not_found: "No active token found",
+    login_link: "The login link is valid for {} minutes:",
+    token_usage: "/token new NAME [sms-upload,read,write] | /token list | /token revoke ID",
+    receipt: "receipt",
+    receipt_of: "receipt of",
+    receipt_duplicate: "This receipt was already added {}",
+    no_qr_in_message: "the message has no receipt QR code",
+    no_qr_in_photo: "the photo has no receipt QR code",
+    report: "Report",
+    report_email_text: "Here is the report you requested",
+    report_sent: "The report is sent to {}",
+    report_caption: "Report for {} - {}",
+    report_usage: "two arguments are expected",
+    not_in_household: "You are not in a household. /household create <name>",
+    household_created: "Household \"{}\" is created. Invite members: /household invite",
+    household_invite: "The code is valid for {} h. and only once. Send it to the member:",
+    household_joined: "You are in household \"{}\"",
+    household_left: "You left household \"{}\"",
+    no_member_with_number: "no member with number {}",
+    member_removed: "{} is removed from the household",
+    cannot_remove_self: "You cannot remove yourself, use /household leave",
+    household_usage: "/household [info|create <name>|invite|join <code> [name]|leave|remove <number>|report [period]]",
+    access_greeting: "Hello! You do not have access to this bot yet.",
+    request_access: "Request access",
+    access_pending: "Access is requested, wait for the administrator's decision",
+    access_request_sent: "The request is sent to the administrator",
+    access_already_open: "You already have access, /help",
+    access_approved: "Access is granted, /help",
+    access_denied: "Access is denied",
+    access_closed: "Access is revoked",
+    no_access_requests: "No access requests",
+    asks_for_access: "{} ({}) asks for access to the bot",
+    allow: "Allow",
+    deny: "Deny",
+    admins_only: "available to administrators only",
+    access_usage: "/access [approve|deny|revoke <telegram id>]",
+    language_set: "Language: English",
+    language_usage: "/lang ru|en",
+    not_your_entry: "the entry belongs to another household member",
+    invalid_receipt: "the QR code is not a receipt code",
+    invalid_price: "could not calculate the price",
+    invalid_date: "invalid date",
+    invalid_value: "invalid value",
+    category_already_set: "product \"{}\" already has category \"{}\"",
+    sum_too_large: "the sum is too large",
+    email_not_set_up: "email sending is not set up, add \"{}\" to the config",
+    pdf_not_set_up: "pdf reports are not set up, add \"{}\" to the config",
+    web_login_not_set_up: "web login is not set up, add \"{}\" to the config",
+    pdf_conversion_failed: "could not convert the report to pdf",
+    telegram_file_failed: "could not transfer the file via Telegram",
+    unexpected_error: "could not complete the command, try again later",
+    help: "/help - list of commands\n<product> <price> - add an entry, e.g. \"bread 50\"\n/report [month|year] [email|html|csv|pdf] - report, for this week by default\n/category <category> <product> - set a category of a product\n/list [count] [period] [product] - latest entries\n/delete - delete the latest entry\n/cancel - cancel the current question\n/split - split an entry among participants\n/settle - debts and transfers\n/household - household\n/token - API tokens\n/login - web login link\n/access - access requests, for administrators\n/lang ru|en - language",
+};
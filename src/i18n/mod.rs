@@ -0,0 +1,341 @@
+use chrono::naive::NaiveDateTime;
+use failure::Error as FailureError;
+
+use std::fmt;
+use std::str::FromStr;
+
+use accounting::EvaluationError;
+use error::{AppError, HouseholdError, SplitError};
+
+mod en;
+mod ru;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    Ru,
+    En,
+}
+
+pub struct Catalogue {
+    pub months: [&'static str; 12],
+    pub currency: &'static str,
+    pub thousands_separator: char,
+    pub date_format: &'static str,
+    pub date_time_format: &'static str,
+    pub me: &'static str,
+    pub error: &'static str,
+    pub no_data: &'static str,
+    pub no_entries: &'static str,
+    pub total: &'static str,
+    pub total_spent: &'static str,
+    pub total_entries: &'static str,
+    pub by_category_header: &'static str,
+    pub by_member: &'static str,
+    pub units: &'static str,
+    pub member_since: &'static str,
+    pub added: &'static str,
+    pub not_added: &'static str,
+    pub line: &'static str,
+    pub product_and_price_required: &'static str,
+    pub household_error: &'static str,
+    pub invalid_split: &'static str,
+    pub wrong_usage: &'static str,
+    pub already_in_household: &'static str,
+    pub only_owner_invites: &'static str,
+    pub invalid_invite: &'static str,
+    pub household_gone: &'static str,
+    pub only_owner_removes: &'static str,
+    pub not_a_member: &'static str,
+    pub participant_required: &'static str,
+    pub empty_participant: &'static str,
+    pub share_not_a_number: &'static str,
+    pub share_not_positive: &'static str,
+    pub paid_by: &'static str,
+    pub no_debts: &'static str,
+    pub balances: &'static str,
+    pub transfers: &'static str,
+    pub settlement_recorded: &'static str,
+    pub split_usage: &'static str,
+    pub settle_usage: &'static str,
+    pub list_page: &'static str,
+    pub page_back: &'static str,
+    pub page_next: &'static str,
+    pub list_usage: &'static str,
+    pub cancelled: &'static str,
+    pub nothing_to_cancel: &'static str,
+    pub confirm_delete: &'static str,
+    pub deleted: &'static str,
+    pub already_deleted: &'static str,
+    pub kept: &'static str,
+    pub answer_yes_or_no: &'static str,
+    pub send_category_name: &'static str,
+    pub new_category: &'static str,
+    pub ask_new_category: &'static str,
+    pub entry_not_found: &'static str,
+    pub category_usage: &'static str,
+    pub token_shown_once: &'static str,
+    pub no_tokens: &'static str,
+    pub token_not_found: &'static str,
+    pub login_link: &'static str,
+    pub token_usage: &'static str,
+    pub receipt: &'static str,
+    pub receipt_of: &'static str,
+    pub receipt_duplicate: &'static str,
+    pub no_qr_in_message: &'static str,
+    pub no_qr_in_photo: &'static str,
+    pub report: &'static str,
+    pub report_email_text: &'static str,
+    pub report_sent: &'static str,
+    pub report_caption: &'static str,
+    pub report_usage: &'static str,
+    pub not_in_household: &'static str,
+    pub household_created: &'static str,
+    pub household_invite: &'static str,
+    pub household_joined: &'static str,
+    pub household_left: &'static str,
+    pub no_member_with_number: &'static str,
+    pub member_removed: &'static str,
+    pub cannot_remove_self: &'static str,
+    pub household_usage: &'static str,
+    pub access_greeting: &'static str,
+    pub request_access: &'static str,
+    pub access_pending: &'static str,
+    pub access_request_sent: &'static str,
+    pub access_already_open: &'static str,
+    pub access_approved: &'static str,
+    pub access_denied: &'static str,
+    pub access_closed: &'static str,
+    pub no_access_requests: &'static str,
+    pub asks_for_access: &'static str,
+    pub allow: &'static str,
+    pub deny: &'static str,
+    pub admins_only: &'static str,
+    pub access_usage: &'static str,
+    pub language_set: &'static str,
+    pub language_usage: &'static str,
+    pub not_your_entry: &'static str,
+    pub invalid_receipt: &'static str,
+    pub invalid_price: &'static str,
+    pub invalid_date: &'static str,
+    pub invalid_value: &'static str,
+    pub category_already_set: &'static str,
+    pub sum_too_large: &'static str,
+    pub email_not_set_up: &'static str,
+    pub pdf_not_set_up: &'static str,
+    pub web_login_not_set_up: &'static str,
+    pub pdf_conversion_failed: &'static str,
+    pub telegram_file_failed: &'static str,
+    pub unexpected_error: &'static str,
+    pub help: &'static str,
+}
+
+impl Locale {
+    pub fn all() -> Vec<Locale> {
+        vec![Locale::Ru, Locale::En]
+    }
+
+    pub fn catalogue(&self) -> &'static Catalogue {
+        match *self {
+            Locale::Ru => &ru::CATALOGUE,
+            Locale::En => &en::CATALOGUE,
+        }
+    }
+
+    pub fn month_name(&self, month: u32) -> &'static str {
+        self.catalogue().months[(month as usize + 11) % 12]
+    }
+
+    pub fn amount<N: Into<i64>>(&self, amount: N) -> String {
+        let amount = amount.into();
+        let digits = amount.abs().to_string();
+        let mut grouped = String::new();
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index) % 3 == 0 {
+                grouped.push(self.catalogue().thousands_separator);
+            }
+            grouped.push(digit);
+        }
+        let sign = if amount < 0 { "-" } else { "" };
+        format!("{}{} {}", sign, grouped, self.catalogue().currency)
+    }
+
+    pub fn date(&self, time: NaiveDateTime) -> String {
+        time.format(self.catalogue().date_format).to_string()
+    }
+
+    pub fn date_time(&self, time: NaiveDateTime) -> String {
+        time.format(self.catalogue().date_time_format).to_string()
+    }
+
+    pub fn error(&self, error: &FailureError) -> String {
+        let text = self.catalogue();
+        let message = match error.downcast_ref::<AppError>() {
+            Some(AppError::ProductAndPriceRequired) => text.product_and_price_required.to_owned(),
+            Some(AppError::NoDataForPeriod) => text.no_data.to_owned(),
+            Some(AppError::InvalidDate) => text.invalid_date.to_owned(),
+            Some(AppError::InvalidEnumVariant) => text.invalid_value.to_owned(),
+            Some(AppError::Calculation { .. }) => text.sum_too_large.to_owned(),
+            Some(AppError::ProductAlreadyHasCategory { product, category }) => {
+                fill(text.category_already_set, &[product, category])
+            }
+            Some(AppError::EmailNotSetUp { property }) => fill(text.email_not_set_up, &[property]),
+            Some(AppError::PdfNotSetUp { property }) => fill(text.pdf_not_set_up, &[property]),
+            Some(AppError::WebLoginNotSetUp { property }) => {
+                fill(text.web_login_not_set_up, &[property])
+            }
+            Some(AppError::PdfConversion { .. }) => text.pdf_conversion_failed.to_owned(),
+            Some(AppError::TelegramFile { .. }) => text.telegram_file_failed.to_owned(),
+            Some(AppError::InvalidReceipt { .. }) => text.invalid_receipt.to_owned(),
+            Some(AppError::QrCodeNotFound { reason }) => reason.to_owned(),
+            Some(AppError::Household { reason }) => {
+                format!("{}: {}", text.household_error, self.household_error(reason))
+            }
+            Some(AppError::InvalidSplit { reason }) => {
+                format!("{}: {}", text.invalid_split, self.split_error(reason))
+            }
+            Some(AppError::NotYourEntry) => text.not_your_entry.to_owned(),
+            Some(AppError::BotUsage { reason }) => format!("{}: {}", text.wrong_usage, reason),
+            Some(_) => text.unexpected_error.to_owned(),
+            None if error.downcast_ref::<EvaluationError>().is_some() => {
+                text.invalid_price.to_owned()
+            }
+            None => text.unexpected_error.to_owned(),
+        };
+        format!("{}: {}", text.error, message)
+    }
+
+    fn household_error(&self, error: &HouseholdError) -> &'static str {
+        let text = self.catalogue();
+        match *error {
+            HouseholdError::AlreadyMember => text.already_in_household,
+            HouseholdError::OnlyOwnerInvites => text.only_owner_invites,
+            HouseholdError::InvalidInvite => text.invalid_invite,
+            HouseholdError::HouseholdGone => text.household_gone,
+            HouseholdError::OnlyOwnerRemoves => text.only_owner_removes,
+            HouseholdError::NotAMember => text.not_a_member,
+        }
+    }
+
+    fn split_error(&self, error: &SplitError) -> String {
+        let text = self.catalogue();
+        match *error {
+            SplitError::ParticipantRequired => text.participant_required.to_owned(),
+            SplitError::EmptyParticipant => text.empty_participant.to_owned(),
+            SplitError::ShareNotANumber(ref participant) => {
+                fill(text.share_not_a_number, &[participant])
+            }
+            SplitError::ShareNotPositive(ref participant) => {
+                fill(text.share_not_positive, &[participant])
+            }
+        }
+    }
+}
+
+pub fn parse_month(word: &str) -> Option<u32> {
+    let word = word.to_lowercase();
+    Locale::all()
+        .into_iter()
+        .filter_map(|locale| {
+            locale
+                .catalogue()
+                .months
+                .iter()
+                .position(|month| *month == word)
+        }).next()
+        .map(|index| index as u32 + 1)
+}
+
+pub fn fill(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    let mut filled = parts.next().unwrap_or("").to_owned();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            filled.push_str(&arg.to_string());
+        }
+        filled.push_str(part);
+    }
+    filled
+}
+
+impl Default for Locale {
+    fn default() -> Locale {
+        Locale::Ru
+    }
+}
+
+impl FromStr for Locale {
+    type Err = FailureError;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.to_lowercase().as_str() {
+            "ru" | "русский" => Ok(Locale::Ru),
+            "en" | "english" => Ok(Locale::En),
+            _ => Err(AppError::InvalidEnumVariant.into()),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Locale::Ru => write!(f, "ru"),
+            Locale::En => write!(f, "en"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_parses_in_both_locales() {
+        assert_eq!(Locale::Ru.amount(1234567), "1 234 567 руб.");
+        assert_eq!(Locale::En.amount(-1500), "-1,500 RUB");
+        assert_eq!(Locale::En.amount(50), "50 RUB");
+        assert_eq!(Locale::Ru.month_name(1), "январь");
+        assert_eq!(Locale::En.month_name(12), "december");
+        assert_eq!(parse_month("Март"), Some(3));
+        assert_eq!(parse_month("march"), Some(3));
+        assert_eq!(parse_month("2018"), None);
+        assert_eq!(fill("Записи {}-{} из {}:", &[&1, &10, &42]), "Записи 1-10 из 42:");
+    }
+
+    #[test]
+    fn translates_errors_shown_to_bot_users() {
+        let invalid_share = ::accounting::Share::parse_list(&["Петя:x"]).unwrap_err();
+        assert_eq!(
+            Locale::Ru.error(&invalid_share),
+            "Ошибка: Неверное деление: доля Петя должна быть числом"
+        );
+        let usage: FailureError = AppError::BotUsage {
+            reason: Locale::Ru.catalogue().list_usage.to_owned(),
+        }.into();
+        assert_eq!(
+            Locale::Ru.error(&usage),
+            "Ошибка: Неверная команда: /list [количество] [период] [продукт]"
+        );
+        let no_data: FailureError = AppError::NoDataForPeriod.into();
+        assert_eq!(Locale::En.error(&no_data), "Error: no data for this period");
+        let household: FailureError = AppError::Household {
+            reason: HouseholdError::InvalidInvite,
+        }.into();
+        assert_eq!(
+            Locale::Ru.error(&household),
+            "Ошибка: Семья: код приглашения неверный или устарел"
+        );
+        let internal: FailureError = AppError::InvalidRegistryPath {
+            used_path: "/data".into(),
+        }.into();
+        assert_eq!(
+            Locale::Ru.error(&internal),
+            "Ошибка: не удалось выполнить команду, попробуйте позже"
+        );
+        let price = "хлеб 5x".parse::<::accounting::Product>().unwrap_err();
+        assert_eq!(
+            Locale::En.error(&price),
+            "Error: could not calculate the price"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use super::Catalogue;
+
+pub static CATALOGUE: Catalogue = Catalogue {
+    months: [
+        "январь", "февраль", "март", "апрель", "май", "июнь", "июль", "август", "сентябрь",
+        "октябрь", "ноябрь", "декабрь",
+    ],
+    currency: "руб.",
+    thousands_separator: ' ',
+    date_format: "%d.%m.%Y",
+    date_time_format: "%d.%m.%Y %H:%M",
+    me: "я",
+    error: "Ошибка",
+    no_data: "нет данных за этот период",
+    no_entries: "нет записей",
+    total: "Итого",
+    total_spent: "Всего потрачено",
+    total_entries: "Всего записей",
+    by_category_header: "По категориям:                      потрачено (руб),   %,  записей",
+    by_member: "По участникам:",
+    units: "ед.",
+    member_since: "с",
+    added: "Добавлено",
+    not_added: "Не добавлено",
+    line: "строка",
+    product_and_price_required: "В строке должны быть указаны продукт и цена",
+    household_error: "Семья",
+    invalid_split: "Неверное деление",
+    wrong_usage: "Неверная команда",
+    already_in_household: "вы уже состоите в семье",
+    only_owner_invites: "приглашать может только владелец",
+    invalid_invite: "код приглашения неверный или устарел",
+    household_gone: "семьи больше нет",
+    only_owner_removes: "удалять участников может только владелец",
+    not_a_member: "вы не состоите в семье",
+    participant_required: "нужен хотя бы один участник",
+    empty_participant: "имя участника не может быть пустым",
+    share_not_a_number: "доля {} должна быть числом",
+    share_not_positive: "доля {} должна быть больше нуля",
+    paid_by: "платил",
+    no_debts: "Долгов нет",
+    balances: "Баланс:",
+    transfers: "Переводы:",
+    settlement_recorded: "записано",
+    split_usage: "/split [номер из /list] [платил=<имя>] <участник[:доля]>... делит запись, по умолчанию последнюю, платили вы (\"я\")",
+    settle_usage: "/settle [paid <кто> <кому> <сумма>]",
+    list_page: "Записи {}-{} из {}:",
+    page_back: "← назад",
+    page_next: "далее →",
+    list_usage: "/list [количество] [период] [продукт]",
+    cancelled: "Отменено",
+    nothing_to_cancel: "Нечего отменять",
+    confirm_delete: "Удалить \"{}\"? да/нет, /cancel для отмены",
+    deleted: "Удалено: {}",
+    already_deleted: "Запись уже удалена",
+    kept: "Запись оставлена",
+    answer_yes_or_no: "Ответьте \"да\" или \"нет\", /cancel для отмены",
+    send_category_name: "Отправьте название категории, /cancel для отмены",
+    new_category: "новая…",
+    ask_new_category: "Отправьте название новой категории, /cancel для отмены",
+    entry_not_found: "запись не найдена",
+    category_usage: "кат <категория> <продукт>",
+    token_shown_once: "Токен показывается один раз:",
+    no_tokens: "Токенов нет",
+    token_not_found: "Активный токен не найден",
+    login_link: "Ссылка для входа действует {} минут:",
+    token_usage: "/token new ИМЯ [sms-upload,read,write] | /token list | /token revoke ID",
+    receipt: "чек",
+    receipt_of: "чек от",
+    receipt_duplicate: "Этот чек уже добавлен {}",
+    no_qr_in_message: "в сообщении нет QR-кода чека",
+    no_qr_in_photo: "на фотографии нет QR-кода чека",
+    report: "Отчет",
+    report_email_text: "Высылаем желаемый отчет",
+    report_sent: "Отчет выслан на {}",
+    report_caption: "Отчет за {} - {}",
+    report_usage: "ожидается два аргумента",
+    not_in_household: "Вы не состоите в семье. /household create <название>",
+    household_created: "Семья \"{}\" создана. Пригласите участников: /household invite",
+    household_invite: "Код действует {} ч. и только один раз. Отправьте его участнику:",
+    household_joined: "Вы в семье \"{}\"",
+    household_left: "Вы вышли из семьи \"{}\"",
+    no_member_with_number: "нет участника с номером {}",
+    member_removed: "{} удален из семьи",
+    cannot_remove_self: "Себя удалить нельзя, используйте /household leave",
+    household_usage: "/household [info|create <название>|invite|join <код> [имя]|leave|remove <номер>|report [период]]",
+    access_greeting: "Здравствуйте! У вас пока нет доступа к этому боту.",
+    request_access: "Запросить доступ",
+    access_pending: "Запрос доступа отправлен, дождитесь решения администратора",
+    access_request_sent: "Запрос отправлен администратору",
+    access_already_open: "Доступ уже открыт, /help",
+    access_approved: "Доступ открыт, /help",
+    access_denied: "В доступе отказано",
+    access_closed: "Доступ закрыт",
+    no_access_requests: "Запросов доступа нет",
+    asks_for_access: "{} ({}) просит доступ к боту",
+    allow: "Разрешить",
+    deny: "Отказать",
+    admins_only: "доступно только администраторам",
+    access_usage: "/access [approve|deny|revoke <telegram id>]",
+    language_set: "Язык: русский",
+    language_usage: "/lang ru|en",
+    not_your_entry: "запись принадлежит другому участнику семьи",
+    invalid_receipt: "QR-код не похож на код чека",
+    invalid_price: "не удалось вычислить цену",
+    invalid_date: "неверная дата",
+    invalid_value: "неверное значение",
+    category_already_set: "у продукта \"{}\" уже есть категория \"{}\"",
+    sum_too_large: "слишком большая сумма",
+    email_not_set_up: "отправка почты не настроена, добавьте \"{}\" в конфигурацию",
+    pdf_not_set_up: "отчеты в pdf не настроены, добавьте \"{}\" в конфигурацию",
+    web_login_not_set_up: "вход на сайт не настроен, добавьте \"{}\" в конфигурацию",
+    pdf_conversion_failed: "не удалось преобразовать отчет в pdf",
+    telegram_file_failed: "не удалось передать файл через Telegram",
+    unexpected_error: "не удалось выполнить команду, попробуйте позже",
+    help: "/help - список команд\n<продукт> <цена> - добавить запись, например \"хлеб 50\"\n/отчет [месяц|год] [email|html|csv|pdf] - отчет, по умолчанию за неделю\nкат <категория> <продукт> - задать категорию продукта\n/list [количество] [период] [продукт] - последние записи\n/delete - удалить последнюю запись\n/cancel - отменить текущий вопрос\n/split - разделить запись между участниками\n/settle - долги и переводы\n/household - семья\n/token - токены для API\n/login - ссылка для входа на сайт\n/access - запросы доступа, для администраторов\n/lang ru|en - язык",
+};
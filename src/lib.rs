@@ -1,5 +1,6 @@
 #![recursion_limit = "128"]
 
+extern crate bardecoder;
 extern crate base64;
 extern crate bincode;
 extern crate chrono;
@@ -9,12 +10,16 @@ extern crate env_logger;
 extern crate failure;
 #[macro_use]
 extern crate failure_derive;
+extern crate flate2;
 extern crate futures;
+extern crate image;
 extern crate lettre;
 extern crate lettre_email;
 #[macro_use]
 extern crate log;
 extern crate mime;
+extern crate openssl;
+extern crate reqwest;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
@@ -22,18 +27,24 @@ extern crate actix;
 extern crate actix_web;
 extern crate serde_json;
 extern crate serde_yaml;
+extern crate sha2;
+extern crate tar;
 extern crate telegram_bot;
 extern crate tokio_core;
 extern crate uuid;
 
 mod accounting;
+mod backup;
 pub mod bot;
 pub mod cli;
 mod config;
 mod dates;
 mod error;
+mod i18n;
 pub mod log_util;
 mod persistence;
 mod registry;
 mod representation;
+#[cfg(test)]
+mod testing;
 pub mod web;
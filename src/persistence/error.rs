@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use super::Format;
+
 #[derive(Debug, Fail)]
 pub enum PersistenceError {
     #[fail(display = "Path {:?} already in use", used_path)]
@@ -12,4 +14,12 @@ pub enum PersistenceError {
     NoSuchKeyInJsonValue,
     #[fail(display = "Key was already in object")]
     KeyWasAlreadyInObject,
+    #[fail(display = "Unknown storage format \"{}\"", name)]
+    UnknownFormat { name: String },
+    #[fail(display = "Unsupported storage format version {}", version)]
+    UnsupportedFormatVersion { version: u8 },
+    #[fail(display = "Operation is not supported for {:?} tables", format)]
+    UnsupportedForFormat { format: Format },
+    #[fail(display = "Truncated record")]
+    TruncatedRecord,
 }
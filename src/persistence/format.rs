@@ -0,0 +1,176 @@
+use failure::Error as FailureError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use persistence::error::PersistenceError;
+
+const BINCODE_MAGIC: &'static [u8] = b"ACCTBIN";
+const BINCODE_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Json,
+    Bincode,
+}
+
+pub struct RecordReader {
+    inner: BufReader<File>,
+    format: Format,
+}
+
+impl Default for Format {
+    fn default() -> Format {
+        Format::Json
+    }
+}
+
+impl FromStr for Format {
+    type Err = FailureError;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw {
+            "json" => Ok(Format::Json),
+            "bincode" => Ok(Format::Bincode),
+            _ => Err(PersistenceError::UnknownFormat {
+                name: raw.to_owned(),
+            }.into()),
+        }
+    }
+}
+
+impl Format {
+    pub fn detect(path: &Path) -> Result<Format, FailureError> {
+        let mut file = File::open(path)?;
+        let mut header = [0u8; 8];
+        let mut read = 0;
+        while read < header.len() {
+            match file.read(&mut header[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        if read < BINCODE_MAGIC.len() || &header[..BINCODE_MAGIC.len()] != BINCODE_MAGIC {
+            return Ok(Format::Json);
+        }
+        match header.get(BINCODE_MAGIC.len()) {
+            Some(&BINCODE_VERSION) if read == header.len() => Ok(Format::Bincode),
+            Some(&version) if read == header.len() => {
+                Err(PersistenceError::UnsupportedFormatVersion { version }.into())
+            }
+            _ => Err(PersistenceError::TruncatedRecord.into()),
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, FailureError> {
+        match *self {
+            Format::Json => Ok(::serde_json::to_vec(value)?),
+            Format::Bincode => Ok(::bincode::serialize(value, ::bincode::Infinite)?),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, record: &[u8]) -> Result<T, FailureError> {
+        match *self {
+            Format::Json => Ok(::serde_json::from_slice(record)?),
+            Format::Bincode => Ok(::bincode::deserialize(record)?),
+        }
+    }
+
+    pub fn write_header<W: Write>(&self, writer: &mut W) -> Result<(), FailureError> {
+        if *self == Format::Bincode {
+            writer.write_all(BINCODE_MAGIC)?;
+            writer.write_all(&[BINCODE_VERSION])?;
+        }
+        Ok(())
+    }
+
+    pub fn write_record<W: Write>(&self, writer: &mut W, record: &[u8]) -> Result<(), FailureError> {
+        match *self {
+            Format::Json => {
+                writer.write_all(record)?;
+                writer.write_all(b"\n")?;
+            }
+            Format::Bincode => {
+                let length = record.len() as u32;
+                writer.write_all(&[
+                    length as u8,
+                    (length >> 8) as u8,
+                    (length >> 16) as u8,
+                    (length >> 24) as u8,
+                ])?;
+                writer.write_all(record)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RecordReader {
+    pub fn open(path: &Path) -> Result<RecordReader, FailureError> {
+        let format = Format::detect(path)?;
+        let mut file = File::open(path)?;
+        if format == Format::Bincode {
+            file.seek(SeekFrom::Start(BINCODE_MAGIC.len() as u64 + 1))?;
+        }
+        Ok(RecordReader {
+            inner: BufReader::new(file),
+            format,
+        })
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    fn next_line(&mut self) -> Result<Option<Vec<u8>>, FailureError> {
+        let mut line = Vec::new();
+        if self.inner.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        Ok(Some(line))
+    }
+
+    fn next_length_prefixed(&mut self) -> Result<Option<Vec<u8>>, FailureError> {
+        let mut length = [0u8; 4];
+        let mut read = 0;
+        while read < length.len() {
+            match self.inner.read(&mut length[read..])? {
+                0 if read == 0 => return Ok(None),
+                0 => return Err(PersistenceError::TruncatedRecord.into()),
+                n => read += n,
+            }
+        }
+        let length = length[0] as usize
+            | (length[1] as usize) << 8
+            | (length[2] as usize) << 16
+            | (length[3] as usize) << 24;
+        let mut record = vec![0u8; length];
+        self.inner
+            .read_exact(&mut record)
+            .map_err(|_| PersistenceError::TruncatedRecord)?;
+        Ok(Some(record))
+    }
+}
+
+impl Iterator for RecordReader {
+    type Item = Result<Vec<u8>, FailureError>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, FailureError>> {
+        let record = match self.format {
+            Format::Json => self.next_line(),
+            Format::Bincode => self.next_length_prefixed(),
+        };
+        match record {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
@@ -2,9 +2,10 @@ use failure::Error as FailureError;
 use serde_json::Value;
 use uuid::Uuid;
 
+use std::fs;
 use std::fs::File;
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
 use persistence::error::PersistenceError;
 
@@ -20,31 +21,34 @@ pub fn migrate(table_file_path: PathBuf, migration: Migration) -> Result<(), Fai
         "migrating table {:?} with migration: {:?}",
         &table_file_path, &migration
     );
-    let migration_data = migrate_to_string(table_file_path.clone(), migration)?;
-    let mut overridden = File::create(table_file_path)?;
-    overridden.write_all(migration_data.as_bytes())?;
+    let mut migrated_file_path = table_file_path.clone();
+    migrated_file_path.set_extension("migrating");
+    if let Err(e) = migrate_to_file(&table_file_path, &migrated_file_path, &migration) {
+        fs::remove_file(&migrated_file_path)?;
+        return Err(e);
+    }
+    fs::rename(&migrated_file_path, &table_file_path)?;
     Ok(())
 }
 
-fn migrate_to_string(
-    table_file_path: PathBuf,
-    migration: Migration,
-) -> Result<String, FailureError> {
-    let mut table_file = File::open(&table_file_path)?;
-    let mut content = String::with_capacity(2048);
-    table_file.read_to_string(&mut content)?;
+fn migrate_to_file(
+    table_file_path: &Path,
+    migrated_file_path: &Path,
+    migration: &Migration,
+) -> Result<(), FailureError> {
+    let table_file = BufReader::new(File::open(table_file_path)?);
+    let mut migrated_file = BufWriter::new(File::create(migrated_file_path)?);
 
-    let mut result = String::with_capacity(2048);
-    for line in content.lines() {
-        let entry: Value = ::serde_json::from_str(line)?;
+    for line in table_file.lines() {
+        let entry: Value = ::serde_json::from_str(&line?)?;
         trace!("{:?}", &entry);
         let migrated_entry = migration.apply(entry)?;
-        let json_serialized = ::serde_json::to_string(&migrated_entry)?;
-        result.push_str(&json_serialized);
-        result.push('\n');
+        ::serde_json::to_writer(&mut migrated_file, &migrated_entry)?;
+        migrated_file.write_all(b"\n")?;
     }
 
-    Ok(result)
+    migrated_file.flush()?;
+    Ok(())
 }
 
 impl Migration {
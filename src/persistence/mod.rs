@@ -5,20 +5,37 @@ use serde::Serialize;
 use std::fmt::Debug;
 use std::fs;
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{BufWriter, Write};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 
 pub mod error;
+mod format;
 mod migrate;
 
 use self::error::PersistenceError;
+pub use self::format::Format;
+use self::format::RecordReader;
 pub use self::migrate::Migration;
 
 #[derive(Debug)]
 pub struct Table<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug> {
     name: String,
     base_path: PathBuf,
+    format: Format,
+    p_: PhantomData<P>,
+    r_: PhantomData<R>,
+}
+
+#[derive(Debug)]
+pub struct ScannedLine<R> {
+    pub number: usize,
+    pub raw: Vec<u8>,
+    pub value: Result<R, String>,
+}
+
+pub struct TableIter<P, R> {
+    records: RecordReader,
     p_: PhantomData<P>,
     r_: PhantomData<R>,
 }
@@ -34,6 +51,7 @@ impl<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug> Tabl
     pub fn create<T: Into<PathBuf>, S: Into<String>>(
         path: T,
         name: S,
+        format: Format,
     ) -> Result<Table<P, R>, FailureError> {
         let base_path = path.into();
         let name = name.into();
@@ -54,11 +72,13 @@ impl<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug> Tabl
             fs::create_dir_all(parent_directory)?;
         }
 
-        let _ = File::create(full_path)?;
+        let mut file = File::create(full_path)?;
+        format.write_header(&mut file)?;
 
         Ok(Table {
             name,
             base_path,
+            format,
             p_: PhantomData,
             r_: PhantomData,
         })
@@ -71,9 +91,13 @@ impl<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug> Tabl
         let base_path = path.into();
         let name = name.into();
         info!("loading existing table \"{}\" at {:?}", &name, &base_path);
+        let mut full_path = base_path.clone();
+        full_path.push(format!("{}.table", &name));
+        let format = Format::detect(&full_path)?;
         Ok(Table {
             name: name,
             base_path,
+            format,
             p_: PhantomData,
             r_: PhantomData,
         })
@@ -81,27 +105,39 @@ impl<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug> Tabl
 
     pub fn select<F: Fn(&R) -> bool>(&self, predicate: F) -> Result<Vec<R>, FailureError> {
         debug!("selecting data");
-        let mut file = self.file_read()?;
-        let mut content = String::with_capacity(2048);
-        file.read_to_string(&mut content)?;
-        let mut entries = Vec::new();
-        for line in content.lines() {
-            let entry: P = ::serde_json::from_str::<P>(line)?;
-            let representation: R = entry.into();
+        self.fold(Vec::new(), |mut entries, representation| {
             if predicate(&representation) {
                 entries.push(representation);
             }
+            entries
+        })
+    }
+
+    pub fn iter(&self) -> Result<TableIter<P, R>, FailureError> {
+        debug!("iterating over table \"{}\"", &self.name);
+        Ok(TableIter {
+            records: RecordReader::open(&self.table_path())?,
+            p_: PhantomData,
+            r_: PhantomData,
+        })
+    }
+
+    pub fn fold<B, F: FnMut(B, R) -> B>(&self, init: B, mut folder: F) -> Result<B, FailureError> {
+        let mut accumulator = init;
+        for representation in self.iter()? {
+            accumulator = folder(accumulator, representation?);
         }
-        Ok(entries)
+        Ok(accumulator)
     }
 
     pub fn insert(&self, value: R) -> Result<(), FailureError> {
         debug!("inserting data {:?}", value);
         let mut file = self.file_append()?;
         let persistence_entry: P = P::from(value);
-        let json_serialized = ::serde_json::to_string(&persistence_entry)?;
-        file.write_all(json_serialized.as_bytes())?;
-        file.write_all(b"\n")?;
+        let record = self.format.encode(&persistence_entry)?;
+        let mut buffer = Vec::with_capacity(record.len() + 4);
+        self.format.write_record(&mut buffer, &record)?;
+        file.write_all(&buffer)?;
         Ok(())
     }
 
@@ -109,29 +145,166 @@ impl<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug> Tabl
         &self,
         predicate: F,
         transformer: T,
-    ) -> Result<(), FailureError> {
+    ) -> Result<Vec<(R, R)>, FailureError>
+    where
+        R: Clone,
+    {
         debug!("updating data");
-        let original_entries = self.select(|_| true)?;
-        let mut updated_entries = Vec::new();
-        for mut entry in original_entries {
-            if predicate(&entry) {
-                transformer(&mut entry);
+        let original_entries = self.iter()?;
+        let format = self.format;
+        let mut changes = Vec::new();
+        self.rewrite(format, |writer| {
+            for entry in original_entries {
+                let mut entry = entry?;
+                if predicate(&entry) {
+                    let before = entry.clone();
+                    transformer(&mut entry);
+                    changes.push((before, entry.clone()));
+                }
+                let persistence_entry: P = P::from(entry);
+                format.write_record(writer, &format.encode(&persistence_entry)?)?;
+            }
+            Ok(())
+        })?;
+        Ok(changes)
+    }
+
+    pub fn delete<F: Fn(&R) -> bool>(&self, predicate: F) -> Result<Vec<R>, FailureError> {
+        debug!("deleting data");
+        let original_entries = self.iter()?;
+        let format = self.format;
+        let mut deleted = Vec::new();
+        self.rewrite(format, |writer| {
+            for entry in original_entries {
+                let entry = entry?;
+                if predicate(&entry) {
+                    deleted.push(entry);
+                    continue;
+                }
+                let persistence_entry: P = P::from(entry);
+                format.write_record(writer, &format.encode(&persistence_entry)?)?;
             }
-            updated_entries.push(entry);
+            Ok(())
+        })?;
+        Ok(deleted)
+    }
+
+    pub fn scan(&self) -> Result<Vec<ScannedLine<R>>, FailureError> {
+        debug!("scanning table \"{}\"", &self.name);
+        let mut scanned = Vec::new();
+        for (index, line) in RecordReader::open(&self.table_path())?.enumerate() {
+            let line = line?;
+            let value = self
+                .format
+                .decode::<P>(&line)
+                .map(|entry| entry.into())
+                .map_err(|e| format!("{}", e));
+            scanned.push(ScannedLine {
+                number: index + 1,
+                raw: line,
+                value,
+            });
+        }
+        Ok(scanned)
+    }
+
+    pub fn quarantine(&self, line_numbers: &[usize]) -> Result<usize, FailureError> {
+        info!(
+            "quarantining {} lines of table \"{}\"",
+            line_numbers.len(),
+            &self.name
+        );
+        if line_numbers.is_empty() {
+            return Ok(0);
         }
-        self.replace(updated_entries)
+
+        let original_lines = RecordReader::open(&self.table_path())?;
+        let format = self.format;
+        let mut quarantine_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.quarantine_path())?;
+        if quarantine_file.metadata()?.len() == 0 {
+            format.write_header(&mut quarantine_file)?;
+        }
+        let mut quarantined = 0;
+        self.rewrite(format, |writer| {
+            for (index, line) in original_lines.enumerate() {
+                let line = line?;
+                if line_numbers.contains(&(index + 1)) {
+                    format.write_record(&mut quarantine_file, &line)?;
+                    quarantined += 1;
+                } else {
+                    format.write_record(writer, &line)?;
+                }
+            }
+            quarantine_file.flush()?;
+            Ok(())
+        })?;
+        Ok(quarantined)
     }
 
     pub fn migrate(&self, migration: Migration) -> Result<(), FailureError> {
+        if self.format != Format::Json {
+            return Err(PersistenceError::UnsupportedForFormat { format: self.format }.into());
+        }
         migrate::migrate(self.table_path(), migration)
     }
 
-    fn file_read(&self) -> Result<File, FailureError> {
-        let full_path = self.table_path();
+    pub fn format(&self) -> Format {
+        self.format
+    }
 
-        let file = OpenOptions::new().read(true).open(full_path)?;
+    pub fn convert(&mut self, format: Format) -> Result<(), FailureError> {
+        if self.format == format {
+            return Ok(());
+        }
+        info!(
+            "converting table \"{}\" from {:?} to {:?}",
+            &self.name, self.format, format
+        );
+        let original_entries = self.iter()?;
+        self.rewrite(format, |writer| {
+            for entry in original_entries {
+                let persistence_entry: P = P::from(entry?);
+                format.write_record(writer, &format.encode(&persistence_entry)?)?;
+            }
+            Ok(())
+        })?;
+        self.format = format;
+        Ok(())
+    }
 
-        Ok(file)
+    pub fn upgrade<L: DeserializeOwned + Into<R>>(&self) -> Result<usize, FailureError> {
+        let mut legacy = false;
+        for record in RecordReader::open(&self.table_path())? {
+            if self.format.decode::<P>(&record?).is_err() {
+                legacy = true;
+                break;
+            }
+        }
+        if !legacy {
+            return Ok(0);
+        }
+        info!("upgrading legacy records of table \"{}\"", &self.name);
+        let format = self.format;
+        let records = RecordReader::open(&self.table_path())?;
+        let mut upgraded = 0;
+        self.rewrite(format, |writer| {
+            for record in records {
+                let record = record?;
+                let representation: R = match format.decode::<P>(&record) {
+                    Ok(current) => current.into(),
+                    Err(_) => {
+                        upgraded += 1;
+                        format.decode::<L>(&record)?.into()
+                    }
+                };
+                format.write_record(writer, &format.encode(&P::from(representation))?)?;
+            }
+            Ok(())
+        })?;
+        Ok(upgraded)
     }
 
     fn file_append(&self) -> Result<File, FailureError> {
@@ -142,23 +315,26 @@ impl<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug> Tabl
         Ok(file)
     }
 
-    fn replace(&self, new_entries: Vec<R>) -> Result<(), FailureError> {
-        let mut backup_path = self.base_path.clone();
-        backup_path.push(format!("{}_backup.table", &self.name));
-        let mut backup_file = File::create(&backup_path)?;
-
-        for entry in new_entries {
-            let persistence_entry: P = P::from(entry);
-            let json_serialized = ::serde_json::to_string(&persistence_entry)?;
-            backup_file.write_all(json_serialized.as_bytes())?;
-            backup_file.write_all(b"\n")?;
+    fn rewrite<F>(&self, format: Format, writer: F) -> Result<(), FailureError>
+    where
+        F: FnOnce(&mut BufWriter<File>) -> Result<(), FailureError>,
+    {
+        let mut rewritten_path = self.base_path.clone();
+        rewritten_path.push(format!("{}.rewriting", &self.name));
+        {
+            let mut rewritten_file = BufWriter::new(File::create(&rewritten_path)?);
+            let written = format
+                .write_header(&mut rewritten_file)
+                .and_then(|_| writer(&mut rewritten_file))
+                .and_then(|_| rewritten_file.into_inner().map_err(|e| e.into_error().into()))
+                .and_then(|file| file.sync_all().map_err(FailureError::from));
+            if let Err(e) = written {
+                fs::remove_file(&rewritten_path)?;
+                return Err(e);
+            }
         }
 
-        backup_file.flush()?;
-
-        fs::copy(&backup_path, self.table_path())?;
-
-        fs::remove_file(&backup_path)?;
+        fs::rename(&rewritten_path, self.table_path())?;
 
         Ok(())
     }
@@ -168,4 +344,127 @@ impl<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug> Tabl
         full_path.push(format!("{}.table", &self.name));
         full_path
     }
+
+    fn quarantine_path(&self) -> PathBuf {
+        let mut full_path = self.base_path.clone();
+        full_path.push(format!("{}.quarantine", &self.name));
+        full_path
+    }
+}
+
+impl<P: DeserializeOwned + Into<R>, R> Iterator for TableIter<P, R> {
+    type Item = Result<R, FailureError>;
+
+    fn next(&mut self) -> Option<Result<R, FailureError>> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        let entry = match self.records.format().decode::<P>(&record) {
+            Ok(entry) => entry,
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(Ok(entry.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::TemporaryDir;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Record {
+        id: i32,
+    }
+
+    #[test]
+    fn folds_and_updates_lazily() {
+        let base_path = TemporaryDir::new();
+        let table: Table<Record, Record> =
+            Table::create(base_path.as_path(), "records", Format::Json).unwrap();
+        for id in 1..5 {
+            table.insert(Record { id }).unwrap();
+        }
+
+        assert_eq!(table.fold(0, |sum, record| sum + record.id).unwrap(), 10);
+        table.update(|r| r.id % 2 == 0, |r| r.id *= 10).unwrap();
+        assert_eq!(
+            table.select(|r| r.id > 2).unwrap(),
+            vec![Record { id: 20 }, Record { id: 3 }, Record { id: 40 }]
+        );
+    }
+
+    #[test]
+    fn converts_between_formats_losslessly() {
+        let base_path = TemporaryDir::new();
+        let mut table: Table<Record, Record> =
+            Table::create(base_path.as_path(), "records", Format::Json).unwrap();
+        for id in 1..5 {
+            table.insert(Record { id }).unwrap();
+        }
+        let json_content = fs::read(table.table_path()).unwrap();
+
+        table.convert(Format::Bincode).unwrap();
+        assert_eq!(Format::detect(&table.table_path()).unwrap(), Format::Bincode);
+        table.insert(Record { id: 5 }).unwrap();
+        assert_eq!(table.fold(0, |sum, record| sum + record.id).unwrap(), 15);
+
+        table.update(|r| r.id == 5, |r| r.id = 0).unwrap();
+        table.quarantine(&[5]).unwrap();
+        table.convert(Format::Json).unwrap();
+        assert_eq!(fs::read(table.table_path()).unwrap(), json_content);
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Named {
+        id: i32,
+        name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Unnamed {
+        id: i32,
+    }
+
+    impl Into<Named> for Unnamed {
+        fn into(self) -> Named {
+            Named {
+                id: self.id,
+                name: String::new(),
+            }
+        }
+    }
+
+    #[test]
+    fn upgrades_legacy_records_after_current_ones() {
+        let base_path = TemporaryDir::new();
+        let table: Table<Named, Named> =
+            Table::create(base_path.as_path(), "records", Format::Json).unwrap();
+        table
+            .insert(Named {
+                id: 1,
+                name: "first".to_owned(),
+            }).unwrap();
+        let mut file = table.file_append().unwrap();
+        Format::Json.write_record(&mut file, b"{\"id\":2}").unwrap();
+        drop(file);
+
+        assert_eq!(table.upgrade::<Unnamed>().unwrap(), 1);
+        assert_eq!(table.upgrade::<Unnamed>().unwrap(), 0);
+        assert_eq!(
+            table.select(|_| true).unwrap(),
+            vec![
+                Named {
+                    id: 1,
+                    name: "first".to_owned(),
+                },
+                Named {
+                    id: 2,
+                    name: String::new(),
+                },
+            ]
+        );
+        assert_eq!(fs::read_dir(base_path.as_path()).unwrap().count(), 1);
+    }
 }
@@ -0,0 +1,333 @@
+use chrono::prelude::*;
+use chrono::Duration;
+use failure::Error as FailureError;
+
+use std::collections::HashSet;
+use std::fmt;
+
+use super::Registry;
+use accounting::UserId;
+use persistence::ScannedLine;
+
+#[derive(Debug)]
+pub struct FsckReport {
+    pub problems: Vec<Problem>,
+}
+
+#[derive(Debug)]
+pub struct Problem {
+    pub table: &'static str,
+    pub line: usize,
+    pub kind: ProblemKind,
+}
+
+#[derive(Debug)]
+pub enum ProblemKind {
+    Malformed(String),
+    DuplicateId(String),
+    UnknownUser(UserId),
+    ImpossibleDate(NaiveDateTime),
+}
+
+impl Registry {
+    pub fn fsck(&self) -> Result<FsckReport, FailureError> {
+        info!("checking registry at {:?}", &self.path);
+        let mut problems = Vec::new();
+
+        let users = self.users.scan()?;
+        check_lines("users", &users, |u| u.id.0.clone(), &mut problems);
+        let known_users: HashSet<UserId> = users
+            .iter()
+            .filter_map(|line| line.value.as_ref().ok())
+            .map(|user| user.id.clone())
+            .collect();
+
+        let entries = self.entries.scan()?;
+        check_lines("entries", &entries, |e| e.id.0.clone(), &mut problems);
+        for line in &entries {
+            if let Ok(ref entry) = line.value {
+                check_user("entries", line.number, &entry.user_id, &known_users, &mut problems);
+                check_date("entries", line.number, entry.time, &mut problems);
+            }
+        }
+
+        let categories = self.categories.scan()?;
+        check_lines("categories", &categories, |c| c.id.0.clone(), &mut problems);
+        for line in &categories {
+            if let Ok(ref category) = line.value {
+                check_user(
+                    "categories",
+                    line.number,
+                    &category.user_id,
+                    &known_users,
+                    &mut problems,
+                );
+            }
+        }
+
+        let sms = self.sms.scan()?;
+        check_lines("sms", &sms, |s| s.id.0.clone(), &mut problems);
+        for line in &sms {
+            if let Ok(ref sms) = line.value {
+                check_user("sms", line.number, &sms.user, &known_users, &mut problems);
+                check_date("sms", line.number, sms.time.naive_utc(), &mut problems);
+            }
+        }
+
+        let tokens = self.tokens.scan()?;
+        check_lines("tokens", &tokens, |t| t.id.0.clone(), &mut problems);
+        for line in &tokens {
+            if let Ok(ref token) = line.value {
+                check_user("tokens", line.number, &token.user_id, &known_users, &mut problems);
+                check_date("tokens", line.number, token.created, &mut problems);
+            }
+        }
+
+        let logins = self.logins.scan()?;
+        check_lines("logins", &logins, |l| l.id.0.clone(), &mut problems);
+        for line in &logins {
+            if let Ok(ref login) = line.value {
+                check_user("logins", line.number, &login.user_id, &known_users, &mut problems);
+            }
+        }
+
+        let conversations = self.conversations.scan()?;
+        check_lines("conversations", &conversations, |c| c.id.0.clone(), &mut problems);
+        for line in &conversations {
+            if let Ok(ref conversation) = line.value {
+                check_user(
+                    "conversations",
+                    line.number,
+                    &conversation.user_id,
+                    &known_users,
+                    &mut problems,
+                );
+            }
+        }
+
+        let receipts = self.receipts.scan()?;
+        check_lines("receipts", &receipts, |r| r.id.0.clone(), &mut problems);
+        for line in &receipts {
+            if let Ok(ref receipt) = line.value {
+                check_user("receipts", line.number, &receipt.user_id, &known_users, &mut problems);
+                check_date("receipts", line.number, receipt.code.time, &mut problems);
+            }
+        }
+
+        let households = self.households.scan()?;
+        check_lines("households", &households, |h| h.id.0.clone(), &mut problems);
+
+        let members = self.members.scan()?;
+        check_lines("members", &members, |m| m.id.0.clone(), &mut problems);
+        for line in &members {
+            if let Ok(ref member) = line.value {
+                check_user("members", line.number, &member.user_id, &known_users, &mut problems);
+            }
+        }
+
+        let invites = self.invites.scan()?;
+        check_lines("invites", &invites, |i| i.id.0.clone(), &mut problems);
+
+        let splits = self.splits.scan()?;
+        check_lines("splits", &splits, |s| s.id.0.clone(), &mut problems);
+        for line in &splits {
+            if let Ok(ref split) = line.value {
+                check_user("splits", line.number, &split.user_id, &known_users, &mut problems);
+            }
+        }
+
+        let settlements = self.settlements.scan()?;
+        check_lines("settlements", &settlements, |s| s.id.0.clone(), &mut problems);
+        for line in &settlements {
+            if let Ok(ref settlement) = line.value {
+                check_user(
+                    "settlements",
+                    line.number,
+                    &settlement.user_id,
+                    &known_users,
+                    &mut problems,
+                );
+                check_date("settlements", line.number, settlement.time, &mut problems);
+            }
+        }
+
+        let access = self.access.scan()?;
+        check_lines("access", &access, |a| a.id.0.clone(), &mut problems);
+        for line in &access {
+            if let Ok(ref access) = line.value {
+                check_date("access", line.number, access.requested, &mut problems);
+            }
+        }
+
+        let audit = self.audit.scan()?;
+        check_lines("audit", &audit, |r| r.id.0.clone(), &mut problems);
+
+        Ok(FsckReport { problems })
+    }
+
+    pub fn repair(&self, report: &FsckReport) -> Result<usize, FailureError> {
+        let mut quarantined = 0;
+        quarantined += self.users.quarantine(&report.lines("users"))?;
+        quarantined += self.entries.quarantine(&report.lines("entries"))?;
+        quarantined += self.categories.quarantine(&report.lines("categories"))?;
+        quarantined += self.sms.quarantine(&report.lines("sms"))?;
+        quarantined += self.tokens.quarantine(&report.lines("tokens"))?;
+        quarantined += self.logins.quarantine(&report.lines("logins"))?;
+        quarantined += self.conversations.quarantine(&report.lines("conversations"))?;
+        quarantined += self.receipts.quarantine(&report.lines("receipts"))?;
+        quarantined += self.households.quarantine(&report.lines("households"))?;
+        quarantined += self.members.quarantine(&report.lines("members"))?;
+        quarantined += self.invites.quarantine(&report.lines("invites"))?;
+        quarantined += self.splits.quarantine(&report.lines("splits"))?;
+        quarantined += self.settlements.quarantine(&report.lines("settlements"))?;
+        quarantined += self.access.quarantine(&report.lines("access"))?;
+        quarantined += self.audit.quarantine(&report.lines("audit"))?;
+        Ok(quarantined)
+    }
+}
+
+impl FsckReport {
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn lines(&self, table: &str) -> Vec<usize> {
+        let mut lines: Vec<usize> = self
+            .problems
+            .iter()
+            .filter(|p| p.table == table)
+            .map(|p| p.line)
+            .collect();
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.table:{}: ", self.table, self.line)?;
+        match self.kind {
+            ProblemKind::Malformed(ref reason) => write!(f, "malformed line: {}", reason),
+            ProblemKind::DuplicateId(ref id) => write!(f, "duplicate id {}", id),
+            ProblemKind::UnknownUser(ref user) => write!(f, "unknown user {}", user),
+            ProblemKind::ImpossibleDate(ref time) => write!(f, "impossible date {}", time),
+        }
+    }
+}
+
+fn check_lines<R, F: Fn(&R) -> String>(
+    table: &'static str,
+    lines: &[ScannedLine<R>],
+    id: F,
+    problems: &mut Vec<Problem>,
+) {
+    let mut seen = HashSet::new();
+    for line in lines {
+        match line.value {
+            Err(ref reason) => problems.push(Problem {
+                table,
+                line: line.number,
+                kind: ProblemKind::Malformed(reason.clone()),
+            }),
+            Ok(ref value) => {
+                let id = id(value);
+                if !seen.insert(id.clone()) {
+                    problems.push(Problem {
+                        table,
+                        line: line.number,
+                        kind: ProblemKind::DuplicateId(id),
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn check_user(
+    table: &'static str,
+    line: usize,
+    user: &UserId,
+    known_users: &HashSet<UserId>,
+    problems: &mut Vec<Problem>,
+) {
+    if !known_users.contains(user) {
+        problems.push(Problem {
+            table,
+            line,
+            kind: ProblemKind::UnknownUser(user.clone()),
+        });
+    }
+}
+
+fn check_date(table: &'static str, line: usize, time: NaiveDateTime, problems: &mut Vec<Problem>) {
+    let earliest = NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0);
+    let latest = ::chrono::offset::Local::now().naive_local() + Duration::days(1);
+    if time < earliest || time > latest {
+        problems.push(Problem {
+            table,
+            line,
+            kind: ProblemKind::ImpossibleDate(time),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::table::RawEntry;
+    use super::*;
+    use accounting::{Actor, Entry, Product, TelegramId};
+
+    use std::fs;
+    use std::io::Write;
+    use std::str::FromStr;
+
+    #[test]
+    fn reports_and_quarantines_broken_lines() {
+        let (registry, path) = super::super::tests::temporary();
+        let user = registry.find_or_create(TelegramId(1), &Actor::Cli).unwrap();
+        let entry = Entry::new(user.id, Product::from_str("хлеб 50").unwrap());
+        registry.add_entry(entry.clone(), &Actor::Cli).unwrap();
+        let orphan = Entry::new(UserId::generate(), Product::from_str("молоко 70").unwrap());
+        let mut table_path = path.clone();
+        table_path.push("entries.table");
+        {
+            let mut table = fs::OpenOptions::new().append(true).open(&table_path).unwrap();
+            let duplicate = ::serde_json::to_string(&RawEntry::from(entry)).unwrap();
+            let orphan = ::serde_json::to_string(&RawEntry::from(orphan)).unwrap();
+            write!(table, "{}\n{{not json\n{}\n", duplicate, orphan).unwrap();
+        }
+
+        let report = registry.fsck().unwrap();
+        let problems: Vec<(&str, usize, String)> = report
+            .problems
+            .iter()
+            .map(|p| {
+                let kind = match p.kind {
+                    ProblemKind::Malformed(_) => "malformed".to_owned(),
+                    ProblemKind::DuplicateId(_) => "duplicate".to_owned(),
+                    ProblemKind::UnknownUser(_) => "unknown user".to_owned(),
+                    ProblemKind::ImpossibleDate(_) => "impossible date".to_owned(),
+                };
+                (p.table, p.line, kind)
+            }).collect();
+        assert_eq!(
+            problems,
+            vec![
+                ("entries", 2, "duplicate".to_owned()),
+                ("entries", 3, "malformed".to_owned()),
+                ("entries", 4, "unknown user".to_owned()),
+            ]
+        );
+
+        assert_eq!(registry.repair(&report).unwrap(), 3);
+        let mut quarantine_path = path.clone();
+        quarantine_path.push("entries.quarantine");
+        let quarantined = fs::read_to_string(&quarantine_path).unwrap();
+        assert_eq!(quarantined.lines().count(), 3);
+        assert!(quarantined.contains("{not json"));
+        assert!(quarantined.contains("молоко"));
+        assert_eq!(fs::read_to_string(&table_path).unwrap().lines().count(), 1);
+        assert!(registry.fsck().unwrap().is_clean());
+    }
+}
@@ -2,17 +2,28 @@ use failure::Error as FailureError;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::path::PathBuf;
 
-use accounting::statistics::Statistics;
-use accounting::{Category, Entry, NewSms, Sms, TelegramId, User, UserId};
-use error::AppError;
-use persistence::{Migration, Table};
+use accounting::statistics::{Statistics, TimePeriod};
+use accounting::{
+    Access, AccessStatus, Actor, AndroidAuth, ApiToken, AuditAction, AuditFilter, AuditRecord,
+    Category, CategoryId, Conversation, Entry, EntryId, FiscalCode, Household, Invite, LoginCode,
+    Member, MemberId, NewSms, Receipt, ReceiptStatus, Role, Scope, SettleUp, Settlement, Share,
+    Sms, SmsFilter, SmsId, SmsStatus, Split, TelegramId, TokenId, User, UserId,
+};
+use error::{AppError, HouseholdError};
+use i18n::Locale;
+use persistence::{Format, Migration, Table};
 
+mod fsck;
 mod table;
 
-use self::table::{RawCategory, RawEntry, RawUser};
+pub use self::fsck::FsckReport;
+use self::table::{
+    RawAuditRecord, RawCategory, RawEntry, RawLoginCode, RawSmsV1, RawToken, RawUser, RawUserV1,
+};
 
 #[derive(Debug)]
 pub struct Registry {
@@ -21,19 +32,43 @@ pub struct Registry {
     users: Table<RawUser, User>,
     categories: Table<RawCategory, Category>,
     sms: Table<Sms, Sms>,
+    audit: Table<RawAuditRecord, AuditRecord>,
+    tokens: Table<RawToken, ApiToken>,
+    logins: Table<RawLoginCode, LoginCode>,
+    conversations: Table<Conversation, Conversation>,
+    receipts: Table<Receipt, Receipt>,
+    households: Table<Household, Household>,
+    members: Table<Member, Member>,
+    invites: Table<Invite, Invite>,
+    splits: Table<Split, Split>,
+    settlements: Table<Settlement, Settlement>,
+    access: Table<Access, Access>,
 }
 
 impl Registry {
-    pub fn new(path: PathBuf) -> Result<Registry, FailureError> {
+    pub fn new(path: PathBuf, format: Format) -> Result<Registry, FailureError> {
         debug!("creating registry at {:?}", &path);
         if !path.is_dir() {
             return Err(AppError::InvalidRegistryPath { used_path: path }.into());
         }
 
-        let entries = table(path.clone(), "entries")?;
-        let users = table(path.clone(), "users")?;
-        let categories = table(path.clone(), "categories")?;
-        let sms = table(path.clone(), "sms")?;
+        let entries = table(path.clone(), "entries", format)?;
+        let users: Table<RawUser, User> = table(path.clone(), "users", format)?;
+        users.upgrade::<RawUserV1>()?;
+        let categories = table(path.clone(), "categories", format)?;
+        let sms: Table<Sms, Sms> = table(path.clone(), "sms", format)?;
+        sms.upgrade::<RawSmsV1>()?;
+        let audit = table(path.clone(), "audit", format)?;
+        let tokens = table(path.clone(), "tokens", format)?;
+        let logins = table(path.clone(), "logins", format)?;
+        let conversations = table(path.clone(), "conversations", format)?;
+        let receipts = table(path.clone(), "receipts", format)?;
+        let households = table(path.clone(), "households", format)?;
+        let members = table(path.clone(), "members", format)?;
+        let invites = table(path.clone(), "invites", format)?;
+        let splits = table(path.clone(), "splits", format)?;
+        let settlements = table(path.clone(), "settlements", format)?;
+        let access = table(path.clone(), "access", format)?;
 
         Ok(Registry {
             path,
@@ -41,10 +76,123 @@ impl Registry {
             users,
             categories,
             sms,
+            audit,
+            tokens,
+            logins,
+            conversations,
+            receipts,
+            households,
+            members,
+            invites,
+            splits,
+            settlements,
+            access,
         })
     }
 
-    pub fn find_or_create(&self, telegram_id: TelegramId) -> Result<User, FailureError> {
+    pub fn access(&self, telegram_id: i64) -> Result<Option<Access>, FailureError> {
+        Ok(self.access.select(|a| a.telegram_id == telegram_id)?.pop())
+    }
+
+    pub fn access_list(&self) -> Result<Vec<Access>, FailureError> {
+        let mut access = self.access.select(|_| true)?;
+        access.sort_by(|a, b| a.requested.cmp(&b.requested));
+        Ok(access)
+    }
+
+    pub fn request_access(
+        &self,
+        telegram_id: i64,
+        name: String,
+        actor: &Actor,
+    ) -> Result<Option<Access>, FailureError> {
+        debug!("{} requests access", telegram_id);
+        let existing = self.access(telegram_id)?;
+        if let Some(ref existing) = existing {
+            if !existing.can_request_again() {
+                return Ok(None);
+            }
+        }
+        let changes = self.access.update(
+            |a| a.telegram_id == telegram_id,
+            |a| *a = Access {
+                id: a.id.clone(),
+                ..Access::request(telegram_id, name.clone())
+            },
+        )?;
+        let mut requested = None;
+        for (before, after) in changes {
+            self.record(
+                actor,
+                AuditAction::Update,
+                "access",
+                after.id.0.clone(),
+                Some(before),
+                Some(after.clone()),
+            )?;
+            requested = Some(after);
+        }
+        if requested.is_some() {
+            return Ok(requested);
+        }
+        let access = Access::request(telegram_id, name);
+        self.access.insert(access.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "access",
+            access.id.0.clone(),
+            None,
+            Some(access.clone()),
+        )?;
+        Ok(Some(access))
+    }
+
+    pub fn decide_access(
+        &self,
+        telegram_id: i64,
+        status: AccessStatus,
+        admin: i64,
+        actor: &Actor,
+    ) -> Result<Access, FailureError> {
+        debug!("{} sets access of {} to {}", admin, telegram_id, status);
+        let changes = self
+            .access
+            .update(|a| a.telegram_id == telegram_id, |a| a.decide(status, admin))?;
+        let mut decided = None;
+        for (before, after) in changes {
+            self.record(
+                actor,
+                AuditAction::Update,
+                "access",
+                after.id.0.clone(),
+                Some(before),
+                Some(after.clone()),
+            )?;
+            decided = Some(after);
+        }
+        if let Some(decided) = decided {
+            return Ok(decided);
+        }
+        let mut access = Access::request(telegram_id, format!("{}", telegram_id));
+        access.decide(status, admin);
+        self.access.insert(access.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "access",
+            access.id.0.clone(),
+            None,
+            Some(access.clone()),
+        )?;
+        Ok(access)
+    }
+
+    pub fn find_or_create(
+        &self,
+        telegram_id: TelegramId,
+        actor: &Actor,
+    ) -> Result<User, FailureError> {
         debug!("finding or creating user with {:?}", &telegram_id);
         let users: Vec<User> = self
             .users
@@ -53,6 +201,14 @@ impl Registry {
             None => {
                 let new_user = User::with_telegram_id(telegram_id);
                 self.users.insert(new_user.clone())?;
+                self.record(
+                    actor,
+                    AuditAction::Insert,
+                    "users",
+                    new_user.id.0.clone(),
+                    None,
+                    Some(audited_user(new_user.clone())),
+                )?;
                 new_user
             }
             Some(user) => user,
@@ -60,6 +216,25 @@ impl Registry {
         Ok(user)
     }
 
+    pub fn locale(&self, user: &UserId) -> Result<Locale, FailureError> {
+        Ok(self
+            .users
+            .select(|u| u.id == *user)?
+            .pop()
+            .and_then(|u| u.locale)
+            .unwrap_or_default())
+    }
+
+    pub fn set_locale(
+        &self,
+        user: UserId,
+        locale: Locale,
+        actor: &Actor,
+    ) -> Result<(), FailureError> {
+        debug!("setting locale of {:?} to {}", &user, locale);
+        self.update_user(user, |u| u.locale = Some(locale), actor)
+    }
+
     pub fn find_user<F: Fn(&User) -> bool>(
         &self,
         predicate: F,
@@ -72,9 +247,18 @@ impl Registry {
         Ok(users.pop())
     }
 
-    pub fn add_entry(&self, entry: Entry) -> Result<(), FailureError> {
+    pub fn add_entry(&self, entry: Entry, actor: &Actor) -> Result<(), FailureError> {
         debug!("adding entry {:?}", &entry);
-        self.entries.insert(entry)?;
+        let entry_id = entry.id.0.clone();
+        self.entries.insert(entry.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "entries",
+            entry_id,
+            None,
+            Some(RawEntry::from(entry)),
+        )?;
         Ok(())
     }
 
@@ -84,6 +268,129 @@ impl Registry {
         Ok(entries)
     }
 
+    pub fn visible_entries(&self, user: UserId) -> Result<Vec<Entry>, FailureError> {
+        debug!("listing entries visible to {:?}", &user);
+        let ledger = self.ledger(user)?;
+        self.entries.select(|e| ledger.contains(&e.user_id))
+    }
+
+    pub fn latest_entry(&self, user: UserId) -> Result<Option<Entry>, FailureError> {
+        Ok(self.list(user)?.into_iter().max_by_key(|e| e.time))
+    }
+
+    pub fn numbered_entry(
+        &self,
+        user: UserId,
+        number: usize,
+    ) -> Result<Option<Entry>, FailureError> {
+        let mut entries = self.visible_entries(user)?;
+        entries.sort_by(|a, b| b.time.cmp(&a.time));
+        Ok(number
+            .checked_sub(1)
+            .and_then(|index| entries.into_iter().nth(index)))
+    }
+
+    pub fn update_entry<F: Fn(&mut Entry)>(
+        &self,
+        user: UserId,
+        entry_id: EntryId,
+        update: F,
+        actor: &Actor,
+    ) -> Result<Option<Entry>, FailureError> {
+        debug!("updating entry {:?} of {:?}", &entry_id, &user);
+        self.check_entry_owner(&user, &entry_id)?;
+        let changes = self
+            .entries
+            .update(|e| e.id == entry_id && e.user_id == user, update)?;
+        let mut updated = None;
+        for (before, after) in changes {
+            self.record(
+                actor,
+                AuditAction::Update,
+                "entries",
+                after.id.0.clone(),
+                Some(RawEntry::from(before.clone())),
+                Some(RawEntry::from(after.clone())),
+            )?;
+            if before.product.price != after.product.price {
+                let price = after.product.price;
+                let splits = self
+                    .splits
+                    .update(|s| s.entry_id == after.id, |s| s.amount = price)?;
+                for (before, after) in splits {
+                    self.record(
+                        actor,
+                        AuditAction::Update,
+                        "splits",
+                        after.id.0.clone(),
+                        Some(before),
+                        Some(after),
+                    )?;
+                }
+            }
+            updated = Some(after);
+        }
+        Ok(updated)
+    }
+
+    pub fn delete_entry(
+        &self,
+        user: UserId,
+        entry_id: EntryId,
+        actor: &Actor,
+    ) -> Result<Option<Entry>, FailureError> {
+        debug!("deleting entry {:?} of {:?}", &entry_id, &user);
+        self.check_entry_owner(&user, &entry_id)?;
+        let mut deleted = self
+            .entries
+            .delete(|e| e.id == entry_id && e.user_id == user)?;
+        for entry in &deleted {
+            self.record(
+                actor,
+                AuditAction::Delete,
+                "entries",
+                entry.id.0.clone(),
+                Some(RawEntry::from(entry.clone())),
+                None,
+            )?;
+            for receipt in self.receipts.delete(|r| r.entry_id == entry.id)? {
+                self.record(
+                    actor,
+                    AuditAction::Delete,
+                    "receipts",
+                    receipt.id.0.clone(),
+                    Some(receipt),
+                    None,
+                )?;
+            }
+            for split in self.splits.delete(|s| s.entry_id == entry.id)? {
+                self.record(
+                    actor,
+                    AuditAction::Delete,
+                    "splits",
+                    split.id.0.clone(),
+                    Some(split),
+                    None,
+                )?;
+            }
+        }
+        Ok(deleted.pop())
+    }
+
+    fn check_entry_owner(&self, user: &UserId, entry_id: &EntryId) -> Result<(), FailureError> {
+        let others = self
+            .entries
+            .select(|e| e.id == *entry_id && e.user_id != *user)?;
+        if others.is_empty() {
+            return Ok(());
+        }
+        let ledger = self.ledger(user.clone())?;
+        if others.iter().any(|e| ledger.contains(&e.user_id)) {
+            return Err(AppError::NotYourEntry.into());
+        }
+        Ok(())
+    }
+
     pub fn list_users(&self) -> Result<Vec<User>, FailureError> {
         debug!("listing users");
         let users = self.users.select(|_| true)?;
@@ -94,15 +401,57 @@ impl Registry {
         &self,
         user: UserId,
         update: F,
+        actor: &Actor,
     ) -> Result<(), FailureError> {
         debug!("updating user {:?}", &user);
-        self.users.update(|u| u.id == user, update)?;
+        for (before, after) in self.users.update(|u| u.id == user, update)? {
+            self.record(
+                actor,
+                AuditAction::Update,
+                "users",
+                after.id.0.clone(),
+                Some(audited_user(before)),
+                Some(audited_user(after)),
+            )?;
+        }
         Ok(())
     }
 
-    pub fn migrate_entries(&self, migration: Migration) -> Result<(), FailureError> {
+    pub fn migrate_entries(&self, migration: Migration, actor: &Actor) -> Result<(), FailureError> {
         debug!("migrating entries with {:?}", &migration);
+        if self.entries.format() != Format::Json {
+            return Err(AppError::MigrationNeedsJson.into());
+        }
+        let description = format!("{:?}", &migration);
         self.entries.migrate(migration)?;
+        self.audit.insert(AuditRecord::new(
+            actor.clone(),
+            AuditAction::Migrate,
+            "entries",
+            "*".to_owned(),
+            None,
+            Some(description),
+        ))?;
+        Ok(())
+    }
+
+    pub fn convert(&mut self, format: Format) -> Result<(), FailureError> {
+        debug!("converting registry to {:?}", format);
+        self.entries.convert(format)?;
+        self.users.convert(format)?;
+        self.categories.convert(format)?;
+        self.sms.convert(format)?;
+        self.audit.convert(format)?;
+        self.tokens.convert(format)?;
+        self.logins.convert(format)?;
+        self.conversations.convert(format)?;
+        self.receipts.convert(format)?;
+        self.households.convert(format)?;
+        self.members.convert(format)?;
+        self.invites.convert(format)?;
+        self.splits.convert(format)?;
+        self.settlements.convert(format)?;
+        self.access.convert(format)?;
         Ok(())
     }
 
@@ -117,7 +466,8 @@ impl Registry {
         user: UserId,
         product_name: String,
         category_name: String,
-    ) -> Result<(), FailureError> {
+        actor: &Actor,
+    ) -> Result<Category, FailureError> {
         debug!(
             "adding categories for {:?}: {} - {}",
             &user, &product_name, &category_name
@@ -130,39 +480,1005 @@ impl Registry {
             }.into());
         }
         let new_category = Category::new(user, product_name, category_name);
-        self.categories.insert(new_category)?;
-        Ok(())
+        let category_id = new_category.id.0.clone();
+        self.categories.insert(new_category.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "categories",
+            category_id,
+            None,
+            Some(RawCategory::from(new_category.clone())),
+        )?;
+        Ok(new_category)
+    }
+
+    pub fn delete_category(
+        &self,
+        user: UserId,
+        category_id: CategoryId,
+        actor: &Actor,
+    ) -> Result<Option<Category>, FailureError> {
+        debug!("deleting category {:?} of {:?}", &category_id, &user);
+        let mut deleted = self
+            .categories
+            .delete(|c| c.id == category_id && c.user_id == user)?;
+        for category in &deleted {
+            self.record(
+                actor,
+                AuditAction::Delete,
+                "categories",
+                category.id.0.clone(),
+                Some(RawCategory::from(category.clone())),
+                None,
+            )?;
+        }
+        Ok(deleted.pop())
+    }
+
+    pub fn statistics_for(
+        &self,
+        user: UserId,
+        period: &TimePeriod,
+    ) -> Result<Statistics, FailureError> {
+        debug!("getting statistics for {:?} in {:?}", &user, period);
+        let now = ::chrono::offset::Local::now().naive_local();
+        let (from, till) = period.bounds(now);
+        let ledger = self.ledger(user.clone())?;
+        let mut categories = self
+            .categories
+            .select(|c| ledger.contains(&c.user_id) && c.user_id != user)?;
+        categories.extend(self.categories(user)?);
+        self.entries.fold(
+            Statistics::at(Vec::new(), categories, now),
+            |mut statistics, e| {
+                if ledger.contains(&e.user_id) && e.time >= from && e.time <= till {
+                    statistics.add(e);
+                }
+                statistics
+            },
+        )
+    }
+
+    pub fn household_statistics(
+        &self,
+        user: UserId,
+        period: &TimePeriod,
+    ) -> Result<(Statistics, Vec<Member>), FailureError> {
+        debug!("getting household statistics for {:?} in {:?}", &user, period);
+        let (_, members) = self.household_of(user)?.ok_or(not_in_household())?;
+        let member_ids: Vec<UserId> = members.iter().map(|m| m.user_id.clone()).collect();
+        let now = ::chrono::offset::Local::now().naive_local();
+        let (from, till) = period.bounds(now);
+        let categories = self
+            .categories
+            .select(|c| member_ids.contains(&c.user_id))?;
+        let statistics = self.entries.fold(
+            Statistics::at(Vec::new(), categories, now),
+            |mut statistics, e| {
+                if member_ids.contains(&e.user_id) && e.time >= from && e.time <= till {
+                    statistics.add(e);
+                }
+                statistics
+            },
+        )?;
+        Ok((statistics, members))
+    }
+
+    fn ledger(&self, user: UserId) -> Result<Vec<UserId>, FailureError> {
+        Ok(match self.household_of(user.clone())? {
+            Some((_, members)) => members.into_iter().map(|m| m.user_id).collect(),
+            None => vec![user],
+        })
+    }
+
+    pub fn member_names(&self, user: UserId) -> Result<HashMap<UserId, String>, FailureError> {
+        Ok(match self.household_of(user)? {
+            Some((_, members)) => members.into_iter().map(|m| (m.user_id, m.name)).collect(),
+            None => HashMap::new(),
+        })
+    }
+
+    pub fn household_of(
+        &self,
+        user: UserId,
+    ) -> Result<Option<(Household, Vec<Member>)>, FailureError> {
+        let membership = match self.members.select(|m| m.user_id == user)?.pop() {
+            Some(membership) => membership,
+            None => return Ok(None),
+        };
+        let household = self
+            .households
+            .select(|h| h.id == membership.household_id)?
+            .pop();
+        match household {
+            Some(household) => {
+                let mut members = self.members.select(|m| m.household_id == household.id)?;
+                members.sort_by(|a, b| a.joined.cmp(&b.joined));
+                Ok(Some((household, members)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn create_household(
+        &self,
+        user: UserId,
+        name: String,
+        member_name: String,
+        actor: &Actor,
+    ) -> Result<Household, FailureError> {
+        debug!("creating household {:?} for {:?}", &name, &user);
+        if self.household_of(user.clone())?.is_some() {
+            return Err(AppError::Household {
+                reason: HouseholdError::AlreadyMember,
+            }.into());
+        }
+        let household = Household::new(name);
+        self.households.insert(household.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "households",
+            household.id.0.clone(),
+            None,
+            Some(household.clone()),
+        )?;
+        self.insert_member(
+            Member::new(household.id.clone(), user, member_name, Role::Owner),
+            actor,
+        )?;
+        Ok(household)
     }
 
-    pub fn statistics(&self, user: UserId) -> Result<Statistics, FailureError> {
-        debug!("getting statistics for {:?}", &user);
-        let entries = self.list(user.clone())?;
-        let categiries = self.categories(user.clone())?;
-        Ok(Statistics::new(entries, categiries))
+    pub fn invite_to_household(
+        &self,
+        user: UserId,
+        lifetime: ::chrono::Duration,
+        actor: &Actor,
+    ) -> Result<String, FailureError> {
+        let (household, members) = self.household_of(user.clone())?.ok_or(not_in_household())?;
+        if !members
+            .iter()
+            .any(|m| m.user_id == user && m.role == Role::Owner)
+        {
+            return Err(AppError::Household {
+                reason: HouseholdError::OnlyOwnerInvites,
+            }.into());
+        }
+        debug!("inviting to household {:?}", &household.id);
+        let (invite, code) = Invite::issue(household.id, lifetime);
+        self.invites.insert(invite.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "invites",
+            invite.id.0.clone(),
+            None,
+            Some(invite),
+        )?;
+        Ok(code)
     }
 
-    pub fn add_sms(&self, sms_list: Vec<NewSms>) -> Result<(), FailureError> {
+    pub fn join_household(
+        &self,
+        user: UserId,
+        code: &str,
+        member_name: String,
+        actor: &Actor,
+    ) -> Result<Household, FailureError> {
+        if self.household_of(user.clone())?.is_some() {
+            return Err(AppError::Household {
+                reason: HouseholdError::AlreadyMember,
+            }.into());
+        }
+        let now = ::chrono::offset::Local::now().naive_local();
+        let changes = self
+            .invites
+            .update(|i| i.redeemable(code, now), |i| i.used = true)?;
+        let mut household_id = None;
+        for (before, after) in changes {
+            self.record(
+                actor,
+                AuditAction::Update,
+                "invites",
+                after.id.0.clone(),
+                Some(before),
+                Some(after.clone()),
+            )?;
+            household_id = Some(after.household_id);
+        }
+        let household_id = household_id.ok_or(AppError::Household {
+            reason: HouseholdError::InvalidInvite,
+        })?;
+        let household = self
+            .households
+            .select(|h| h.id == household_id)?
+            .pop()
+            .ok_or(AppError::Household {
+                reason: HouseholdError::HouseholdGone,
+            })?;
+        debug!("{:?} joins household {:?}", &user, &household.id);
+        self.insert_member(
+            Member::new(household.id.clone(), user, member_name, Role::Member),
+            actor,
+        )?;
+        Ok(household)
+    }
+
+    pub fn leave_household(&self, user: UserId, actor: &Actor) -> Result<Household, FailureError> {
+        let (household, members) = self.household_of(user.clone())?.ok_or(not_in_household())?;
+        debug!("{:?} leaves household {:?}", &user, &household.id);
+        self.delete_members(|m| m.user_id == user, actor)?;
+        let remaining: Vec<&Member> = members.iter().filter(|m| m.user_id != user).collect();
+        if remaining.is_empty() {
+            let deleted = self.households.delete(|h| h.id == household.id)?;
+            for household in deleted {
+                self.record(
+                    actor,
+                    AuditAction::Delete,
+                    "households",
+                    household.id.0.clone(),
+                    Some(household),
+                    None,
+                )?;
+            }
+        } else if !remaining.iter().any(|m| m.role == Role::Owner) {
+            let successor = remaining[0].id.clone();
+            let changes = self
+                .members
+                .update(|m| m.id == successor, |m| m.role = Role::Owner)?;
+            for (before, after) in changes {
+                self.record(
+                    actor,
+                    AuditAction::Update,
+                    "members",
+                    after.id.0.clone(),
+                    Some(before),
+                    Some(after),
+                )?;
+            }
+        }
+        Ok(household)
+    }
+
+    pub fn remove_member(
+        &self,
+        owner: UserId,
+        member: &MemberId,
+        actor: &Actor,
+    ) -> Result<Option<Member>, FailureError> {
+        let (household, members) = self.household_of(owner.clone())?.ok_or(not_in_household())?;
+        if !members
+            .iter()
+            .any(|m| m.user_id == owner && m.role == Role::Owner)
+        {
+            return Err(AppError::Household {
+                reason: HouseholdError::OnlyOwnerRemoves,
+            }.into());
+        }
+        let mut removed = self.delete_members(
+            |m| m.household_id == household.id && m.id == *member && m.user_id != owner,
+            actor,
+        )?;
+        Ok(removed.pop())
+    }
+
+    pub fn split_entry(
+        &self,
+        user: UserId,
+        entry: &Entry,
+        payer: &str,
+        shares: Vec<Share>,
+        actor: &Actor,
+    ) -> Result<Split, FailureError> {
+        debug!("splitting entry {:?} among {:?}", &entry.id, &shares);
+        let replaced = self.splits.delete(|s| s.entry_id == entry.id)?;
+        for split in replaced {
+            self.record(
+                actor,
+                AuditAction::Delete,
+                "splits",
+                split.id.0.clone(),
+                Some(split),
+                None,
+            )?;
+        }
+        let split = Split::new(user, entry, payer, shares);
+        self.splits.insert(split.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "splits",
+            split.id.0.clone(),
+            None,
+            Some(split.clone()),
+        )?;
+        Ok(split)
+    }
+
+    pub fn add_settlement(
+        &self,
+        settlement: Settlement,
+        actor: &Actor,
+    ) -> Result<(), FailureError> {
+        debug!("adding settlement {:?}", &settlement);
+        self.settlements.insert(settlement.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "settlements",
+            settlement.id.0.clone(),
+            None,
+            Some(settlement),
+        )
+    }
+
+    pub fn settle_up(&self, user: UserId) -> Result<SettleUp, FailureError> {
+        debug!("settling up debts of {:?}", &user);
+        let ledger = self.ledger(user)?;
+        let splits = self.splits.select(|s| ledger.contains(&s.user_id))?;
+        let settlements = self.settlements.select(|s| ledger.contains(&s.user_id))?;
+        Ok(SettleUp::new(&splits, &settlements))
+    }
+
+    pub fn add_sms(
+        &self,
+        sms_list: Vec<NewSms>,
+        actor: &Actor,
+    ) -> Result<Vec<SmsStatus>, FailureError> {
         debug!("Adding {} sms", sms_list.len());
-        for sms in sms_list {
-            self.sms.insert(sms.into())?;
+        let mut known: HashMap<(UserId, String), SmsId> =
+            self.sms.fold(HashMap::new(), |mut known, sms| {
+                known.insert((sms.user.clone(), sms.dedup_key()), sms.id);
+                known
+            })?;
+        let mut statuses = Vec::with_capacity(sms_list.len());
+        for new_sms in sms_list {
+            let key = (new_sms.user.clone(), new_sms.dedup_key());
+            if let Some(existing) = known.get(&key) {
+                debug!("skipping duplicate sms {:?}", &key.1);
+                statuses.push(SmsStatus::Duplicate(existing.clone()));
+                continue;
+            }
+            let sms: Sms = new_sms.into();
+            self.sms.insert(sms.clone())?;
+            self.record(
+                actor,
+                AuditAction::Insert,
+                "sms",
+                sms.id.0.clone(),
+                None,
+                Some(sms.clone()),
+            )?;
+            known.insert(key, sms.id.clone());
+            statuses.push(SmsStatus::Stored(sms.id));
         }
-        Ok(())
+        Ok(statuses)
+    }
+
+    pub fn add_receipt(
+        &self,
+        user: UserId,
+        code: FiscalCode,
+        name: String,
+        actor: &Actor,
+    ) -> Result<ReceiptStatus, FailureError> {
+        debug!("adding receipt {:?} for {:?}", &code, &user);
+        let mut known = self
+            .receipts
+            .select(|r| r.user_id == user && r.code.same_document(&code))?;
+        if let Some(existing) = known.pop() {
+            debug!("skipping duplicate receipt {:?}", &existing.id);
+            return Ok(ReceiptStatus::Duplicate(existing));
+        }
+        let entry = code.entry(user.clone(), name)?;
+        self.add_entry(entry.clone(), actor)?;
+        let receipt = Receipt::new(user, entry.id.clone(), code);
+        self.receipts.insert(receipt.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "receipts",
+            receipt.id.0.clone(),
+            None,
+            Some(receipt),
+        )?;
+        Ok(ReceiptStatus::Stored(entry))
     }
 
     pub fn get_sms_list(&self, user: UserId) -> Result<Vec<Sms>, FailureError> {
         debug!("searching sms for {}", user);
         self.sms.select(|sms| sms.user == user)
     }
+
+    pub fn issue_token(
+        &self,
+        user: UserId,
+        name: String,
+        scopes: Vec<Scope>,
+        actor: &Actor,
+    ) -> Result<(ApiToken, String), FailureError> {
+        debug!("issuing token \"{}\" for {:?} with {:?}", &name, &user, &scopes);
+        let (token, secret) = ApiToken::issue(user, name, scopes);
+        self.insert_token(token.clone(), actor)?;
+        Ok((token, secret))
+    }
+
+    pub fn tokens(&self, user: UserId) -> Result<Vec<ApiToken>, FailureError> {
+        debug!("listing tokens for {:?}", &user);
+        self.tokens.select(|t| t.user_id == user)
+    }
+
+    pub fn revoke_token(
+        &self,
+        user: UserId,
+        token_id: TokenId,
+        actor: &Actor,
+    ) -> Result<Option<ApiToken>, FailureError> {
+        debug!("revoking token {:?} of {:?}", &token_id, &user);
+        let changes = self.tokens.update(
+            |t| t.id == token_id && t.user_id == user && !t.revoked,
+            |t| t.revoked = true,
+        )?;
+        let mut revoked = None;
+        for (before, after) in changes {
+            self.record(
+                actor,
+                AuditAction::Update,
+                "tokens",
+                after.id.0.clone(),
+                Some(RawToken::from(before)),
+                Some(RawToken::from(after.clone())),
+            )?;
+            revoked = Some(after);
+        }
+        Ok(revoked)
+    }
+
+    pub fn authenticate(&self, secret: &str) -> Result<Option<(User, ApiToken)>, FailureError> {
+        debug!("authenticating token");
+        let token = match self.tokens.select(|t| t.verify(secret))?.pop() {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+        let user_id = token.user_id.clone();
+        Ok(self
+            .find_user(|u| u.id == user_id)?
+            .map(|user| (user, token)))
+    }
+
+    pub fn touch_token(&self, token_id: &TokenId) -> Result<(), FailureError> {
+        debug!("marking token {:?} as used", token_id);
+        let now = ::chrono::offset::Local::now().naive_local();
+        self.tokens
+            .update(|t| t.id == *token_id, |t| t.last_used = Some(now))?;
+        Ok(())
+    }
+
+    pub fn issue_login(
+        &self,
+        user: UserId,
+        lifetime: ::chrono::Duration,
+        actor: &Actor,
+    ) -> Result<String, FailureError> {
+        debug!("issuing login code for {:?}", &user);
+        let (login, code) = LoginCode::issue(user, lifetime);
+        let login_id = login.id.0.clone();
+        self.logins.insert(login.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "logins",
+            login_id,
+            None,
+            Some(RawLoginCode::from(login)),
+        )?;
+        Ok(code)
+    }
+
+    pub fn redeem_login(&self, code: &str, actor: &Actor) -> Result<Option<UserId>, FailureError> {
+        debug!("redeeming login code");
+        let now = ::chrono::offset::Local::now().naive_local();
+        let changes = self
+            .logins
+            .update(|l| l.redeemable(code, now), |l| l.used = true)?;
+        let mut user = None;
+        for (before, after) in changes {
+            self.record(
+                actor,
+                AuditAction::Update,
+                "logins",
+                after.id.0.clone(),
+                Some(RawLoginCode::from(before)),
+                Some(RawLoginCode::from(after.clone())),
+            )?;
+            user = Some(after.user_id);
+        }
+        Ok(user)
+    }
+
+    pub fn conversation(
+        &self,
+        chat_id: i64,
+        user: UserId,
+    ) -> Result<Option<Conversation>, FailureError> {
+        let now = ::chrono::offset::Local::now().naive_local();
+        let mut conversations = self.conversations.select(|c| {
+            c.chat_id == chat_id && c.user_id == user && c.is_active(now)
+        })?;
+        Ok(conversations.pop())
+    }
+
+    pub fn start_conversation(&self, conversation: Conversation) -> Result<(), FailureError> {
+        debug!("starting {:?}", &conversation);
+        let now = ::chrono::offset::Local::now().naive_local();
+        self.conversations.delete(|c| {
+            (c.chat_id == conversation.chat_id && c.user_id == conversation.user_id)
+                || !c.is_active(now)
+        })?;
+        self.conversations.insert(conversation)
+    }
+
+    pub fn end_conversation(
+        &self,
+        chat_id: i64,
+        user: UserId,
+    ) -> Result<Option<Conversation>, FailureError> {
+        debug!("ending conversation in chat {} of {:?}", chat_id, &user);
+        let now = ::chrono::offset::Local::now().naive_local();
+        let mut ended = self
+            .conversations
+            .delete(|c| c.chat_id == chat_id && c.user_id == user)?;
+        ended.retain(|c| c.is_active(now));
+        Ok(ended.pop())
+    }
+
+    pub fn migrate_legacy_tokens(&self, actor: &Actor) -> Result<usize, FailureError> {
+        let legacy_users = self.users.select(|u| u.android_auth_token.is_some())?;
+        for user in &legacy_users {
+            info!("hashing legacy android token of {:?}", &user.id);
+            if let Some(ref legacy) = user.android_auth_token {
+                let token = ApiToken::with_secret(
+                    user.id.clone(),
+                    "android".to_owned(),
+                    Scope::all(),
+                    &legacy.0,
+                );
+                self.insert_token(token, actor)?;
+            }
+            self.update_user(user.id.clone(), |u| u.android_auth_token = None, actor)?;
+        }
+        Ok(legacy_users.len())
+    }
+
+    pub fn search_sms(&self, user: &User, filter: &SmsFilter) -> Result<Vec<Sms>, FailureError> {
+        debug!("searching sms for {} with {:?}", user.id, filter);
+        let mut sms_list: Vec<Sms> = self
+            .get_sms_list(user.id.clone())?
+            .into_iter()
+            .filter(|sms| filter.matches(sms, user))
+            .collect();
+        sms_list.sort_by(|a, b| b.time.cmp(&a.time));
+        Ok(sms_list)
+    }
+
+    pub fn latest_sms(&self, user: UserId) -> Result<Option<Sms>, FailureError> {
+        debug!("searching latest sms for {}", user);
+        self.sms.fold(None, |latest: Option<Sms>, sms| {
+            if sms.user != user {
+                return latest;
+            }
+            match latest {
+                Some(ref latest) if latest.time > sms.time => Some(latest.clone()),
+                _ => Some(sms),
+            }
+        })
+    }
+
+    pub fn last_received_sms(&self, user: UserId) -> Result<Option<Sms>, FailureError> {
+        debug!("searching last received sms for {}", user);
+        self.sms.fold(None, |last: Option<Sms>, sms| {
+            if sms.user == user {
+                Some(sms)
+            } else {
+                last
+            }
+        })
+    }
+
+    pub fn audit_log(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>, FailureError> {
+        debug!("querying audit log with {:?}", filter);
+        self.audit.select(|record| filter.matches(record))
+    }
+
+    fn insert_token(&self, token: ApiToken, actor: &Actor) -> Result<(), FailureError> {
+        let token_id = token.id.0.clone();
+        self.tokens.insert(token.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "tokens",
+            token_id,
+            None,
+            Some(RawToken::from(token)),
+        )
+    }
+
+    fn insert_member(&self, member: Member, actor: &Actor) -> Result<(), FailureError> {
+        self.members.insert(member.clone())?;
+        self.record(
+            actor,
+            AuditAction::Insert,
+            "members",
+            member.id.0.clone(),
+            None,
+            Some(member),
+        )
+    }
+
+    fn delete_members<F: Fn(&Member) -> bool>(
+        &self,
+        predicate: F,
+        actor: &Actor,
+    ) -> Result<Vec<Member>, FailureError> {
+        let deleted = self.members.delete(predicate)?;
+        for member in &deleted {
+            self.record(
+                actor,
+                AuditAction::Delete,
+                "members",
+                member.id.0.clone(),
+                Some(member.clone()),
+                None,
+            )?;
+        }
+        Ok(deleted)
+    }
+
+    fn record<P: Serialize>(
+        &self,
+        actor: &Actor,
+        action: AuditAction,
+        table: &str,
+        entity_id: String,
+        before: Option<P>,
+        after: Option<P>,
+    ) -> Result<(), FailureError> {
+        let before = match before {
+            Some(value) => Some(::serde_json::to_string(&value)?),
+            None => None,
+        };
+        let after = match after {
+            Some(value) => Some(::serde_json::to_string(&value)?),
+            None => None,
+        };
+        self.audit.insert(AuditRecord::new(
+            actor.clone(),
+            action,
+            table,
+            entity_id,
+            before,
+            after,
+        ))
+    }
+}
+
+fn audited_user(user: User) -> RawUser {
+    RawUser::from(User {
+        android_auth_token: user
+            .android_auth_token
+            .map(|_| AndroidAuth("redacted".to_owned())),
+        ..user
+    })
+}
+
+fn not_in_household() -> FailureError {
+    AppError::Household {
+        reason: HouseholdError::NotAMember,
+    }.into()
 }
 
 fn table<P: Serialize + DeserializeOwned + Debug + Into<R> + From<R>, R: Debug>(
     base_path: PathBuf,
     table_name: &str,
+    format: Format,
 ) -> Result<Table<P, R>, FailureError> {
     let table: Table<P, R> = if ::persistence::exist_with_name(&base_path, table_name) {
         Table::load(base_path, table_name)?
     } else {
-        Table::create(base_path, table_name)?
+        Table::create(base_path, table_name, format)?
     };
     Ok(table)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::*;
+    use testing::{temporary_registry, TemporaryDir};
+
+    pub fn temporary() -> (Registry, TemporaryDir) {
+        temporary_registry(Format::Json)
+    }
+
+    fn price(snapshot: &Option<String>) -> Option<i64> {
+        snapshot.as_ref().map(|json| {
+            let value: ::serde_json::Value = ::serde_json::from_str(json).unwrap();
+            value["price"].as_i64().unwrap()
+        })
+    }
+
+    #[test]
+    fn audits_entry_changes_with_snapshots() {
+        let (registry, _dir) = temporary();
+        let actor = Actor::Telegram(1);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap();
+        let entry = Entry::new(user.id.clone(), "хлеб 50".parse().unwrap());
+        registry.add_entry(entry.clone(), &actor).unwrap();
+        registry
+            .update_entry(user.id.clone(), entry.id.clone(), |e| e.product.price = 60, &actor)
+            .unwrap();
+        registry
+            .delete_entry(user.id.clone(), entry.id.clone(), &actor)
+            .unwrap();
+
+        let filter = AuditFilter {
+            entity_id: Some(entry.id.0.clone()),
+            ..AuditFilter::default()
+        };
+        let log = registry.audit_log(&filter).unwrap();
+        let changes: Vec<(AuditAction, Option<i64>, Option<i64>)> = log
+            .iter()
+            .map(|r| (r.action, price(&r.before), price(&r.after)))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (AuditAction::Insert, None, Some(50)),
+                (AuditAction::Update, Some(50), Some(60)),
+                (AuditAction::Delete, Some(60), None),
+            ]
+        );
+        assert!(log.iter().all(|r| r.table == "entries" && r.actor == actor));
+    }
+
+    #[test]
+    fn keeps_legacy_tokens_out_of_audit() {
+        let (registry, path) = temporary();
+        let actor = Actor::Cli;
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap();
+        let secret = "legacy-android-secret";
+        registry
+            .update_user(
+                user.id.clone(),
+                |u| u.android_auth_token = Some(AndroidAuth(secret.to_owned())),
+                &actor,
+            ).unwrap();
+
+        assert_eq!(registry.migrate_legacy_tokens(&actor).unwrap(), 1);
+
+        assert!(registry.authenticate(secret).unwrap().is_some());
+        let log = registry.audit_log(&AuditFilter::default()).unwrap();
+        assert!(log.iter().any(|r| r.table == "users"));
+        for record in log {
+            let snapshots = format!("{:?} {:?}", record.before, record.after);
+            assert!(!snapshots.contains(secret), "{:?}", record);
+        }
+        let mut audit_path = path.clone();
+        audit_path.push("audit.table");
+        assert!(!::std::fs::read_to_string(&audit_path).unwrap().contains(secret));
+    }
+
+    #[test]
+    fn refuses_to_migrate_bincode_entries() {
+        let (registry, _dir) = temporary_registry(Format::Bincode);
+        let error = registry
+            .migrate_entries(Migration::remove("tags".to_owned()), &Actor::Cli)
+            .unwrap_err();
+        match error.downcast::<AppError>() {
+            Ok(AppError::MigrationNeedsJson) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(registry.audit_log(&AuditFilter::default()).unwrap().is_empty());
+    }
+
+    #[derive(Serialize)]
+    struct SmsBeforeClientId {
+        id: String,
+        user: String,
+        from: String,
+        text: String,
+        time: NaiveDateTime,
+    }
+
+    #[test]
+    fn upgrades_bincode_sms_without_client_id() {
+        let path = TemporaryDir::new();
+        {
+            let legacy = SmsBeforeClientId {
+                id: "legacy".to_owned(),
+                user: "user".to_owned(),
+                from: "bank".to_owned(),
+                text: "purchase 100".to_owned(),
+                time: NaiveDate::from_ymd(2018, 10, 1).and_hms(12, 0, 0),
+            };
+            let format = Format::Bincode;
+            let mut file = ::std::fs::File::create(path.join("sms.table")).unwrap();
+            format.write_header(&mut file).unwrap();
+            format
+                .write_record(&mut file, &format.encode(&legacy).unwrap())
+                .unwrap();
+        }
+        let user = UserId::new("user".to_owned());
+        let registry = Registry::new(path.clone(), Format::Bincode).unwrap();
+        let sms_list = registry.get_sms_list(user.clone()).unwrap();
+        assert_eq!(sms_list.len(), 1);
+        assert_eq!(sms_list[0].id, SmsId::new("legacy".to_owned()));
+        assert_eq!(sms_list[0].time, Utc.ymd(2018, 10, 1).and_hms(12, 0, 0));
+        assert_eq!(sms_list[0].client_id, None);
+
+        let new_sms = NewSms {
+            user: user.clone(),
+            from: "bank".to_owned(),
+            text: "purchase 200".to_owned(),
+            time: Utc.ymd(2018, 10, 2).and_hms(12, 0, 0),
+            client_id: Some("42".to_owned()),
+        };
+        registry.add_sms(vec![new_sms], &Actor::Cli).unwrap();
+        let registry = Registry::new(path.clone(), Format::Bincode).unwrap();
+        let client_ids: Vec<Option<String>> = registry
+            .get_sms_list(user)
+            .unwrap()
+            .into_iter()
+            .map(|sms| sms.client_id)
+            .collect();
+        assert_eq!(client_ids, vec![None, Some("42".to_owned())]);
+    }
+
+    #[derive(Serialize)]
+    struct UserBeforeLocale {
+        id: String,
+        telegram_id: Option<i64>,
+        offset: Option<NaiveDateTime>,
+        android_auth_token: Option<AndroidAuth>,
+    }
+
+    #[test]
+    fn upgrades_bincode_users_without_locale() {
+        let path = TemporaryDir::new();
+        {
+            let legacy = UserBeforeLocale {
+                id: "legacy".to_owned(),
+                telegram_id: Some(1),
+                offset: None,
+                android_auth_token: None,
+            };
+            let format = Format::Bincode;
+            let mut file = ::std::fs::File::create(path.join("users.table")).unwrap();
+            format.write_header(&mut file).unwrap();
+            format
+                .write_record(&mut file, &format.encode(&legacy).unwrap())
+                .unwrap();
+        }
+        let actor = Actor::Cli;
+        let registry = Registry::new(path.clone(), Format::Bincode).unwrap();
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap();
+        assert_eq!(user.id, UserId::new("legacy".to_owned()));
+        assert_eq!(registry.locale(&user.id).unwrap(), Locale::Ru);
+
+        registry
+            .set_locale(user.id.clone(), Locale::En, &actor)
+            .unwrap();
+        let registry = Registry::new(path.clone(), Format::Bincode).unwrap();
+        assert_eq!(registry.locale(&user.id).unwrap(), Locale::En);
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap();
+        assert_eq!(user.locale, Some(Locale::En));
+    }
+
+    #[test]
+    fn adds_receipt_again_after_its_entry_is_deleted() {
+        let (registry, _dir) = temporary();
+        let actor = Actor::Cli;
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let code = || FiscalCode::parse("t=20181020T1015&s=120.5&fn=1&i=2&fp=3").unwrap();
+        let entry = match registry
+            .add_receipt(user.clone(), code(), "продукты".to_owned(), &actor)
+            .unwrap()
+        {
+            ReceiptStatus::Stored(entry) => entry,
+            other => panic!("unexpected status {:?}", other),
+        };
+        match registry
+            .add_receipt(user.clone(), code(), "продукты".to_owned(), &actor)
+            .unwrap()
+        {
+            ReceiptStatus::Duplicate(_) => {}
+            other => panic!("unexpected status {:?}", other),
+        }
+
+        registry.delete_entry(user.clone(), entry.id, &actor).unwrap();
+        match registry
+            .add_receipt(user.clone(), code(), "продукты".to_owned(), &actor)
+            .unwrap()
+        {
+            ReceiptStatus::Stored(_) => {}
+            other => panic!("unexpected status {:?}", other),
+        }
+        assert_eq!(registry.list(user).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn shares_entries_within_a_household() {
+        let (registry, _dir) = temporary();
+        let actor = Actor::Cli;
+        let users: Vec<UserId> = (1..4)
+            .map(|id| registry.find_or_create(TelegramId(id), &actor).unwrap().id)
+            .collect();
+        registry
+            .create_household(users[0].clone(), "дом".to_owned(), "Аня".to_owned(), &actor)
+            .unwrap();
+        let code = registry
+            .invite_to_household(users[0].clone(), ::chrono::Duration::hours(1), &actor)
+            .unwrap();
+        registry
+            .join_household(users[1].clone(), &code, "Боря".to_owned(), &actor)
+            .unwrap();
+        for (user, raw) in users.iter().zip(&["хлеб 50", "молоко 70", "сыр 300"]) {
+            let entry = Entry::new(user.clone(), raw.parse().unwrap());
+            registry.add_entry(entry, &actor).unwrap();
+        }
+
+        assert_eq!(registry.visible_entries(users[0].clone()).unwrap().len(), 2);
+        assert_eq!(registry.visible_entries(users[1].clone()).unwrap().len(), 2);
+        assert_eq!(registry.visible_entries(users[2].clone()).unwrap().len(), 1);
+        let statistics = registry
+            .statistics_for(users[1].clone(), &TimePeriod::ThisMonth)
+            .unwrap();
+        let report = statistics.report(TimePeriod::ThisMonth).unwrap().unwrap();
+        assert_eq!(report.total_spent, 120);
+
+        let partner_entry = registry.list(users[0].clone()).unwrap().pop().unwrap();
+        let reprice = |e: &mut Entry| e.product.price = 1;
+        let error = registry
+            .update_entry(users[1].clone(), partner_entry.id.clone(), reprice, &actor)
+            .unwrap_err();
+        match error.downcast_ref::<AppError>() {
+            Some(AppError::NotYourEntry) => {}
+            _ => panic!("unexpected error {}", error),
+        }
+        assert!(registry
+            .delete_entry(users[1].clone(), partner_entry.id.clone(), &actor)
+            .is_err());
+        let stranger = registry
+            .delete_entry(users[2].clone(), partner_entry.id.clone(), &actor)
+            .unwrap();
+        assert!(stranger.is_none());
+        assert_eq!(registry.list(users[0].clone()).unwrap()[0].product.price, 50);
+    }
+
+    #[test]
+    fn follows_entry_price_and_deletion_in_splits() {
+        let (registry, _dir) = temporary();
+        let actor = Actor::Cli;
+        let user = registry.find_or_create(TelegramId(1), &actor).unwrap().id;
+        let entry = Entry::new(user.clone(), "ужин 300".parse().unwrap());
+        registry.add_entry(entry.clone(), &actor).unwrap();
+        let shares = Share::parse_list(&["я", "Петя"]).unwrap();
+        registry
+            .split_entry(user.clone(), &entry, "я", shares, &actor)
+            .unwrap();
+
+        let reprice = |e: &mut Entry| e.product.price = 500;
+        registry
+            .update_entry(user.clone(), entry.id.clone(), reprice, &actor)
+            .unwrap();
+        let balances = registry.settle_up(user.clone()).unwrap().balances;
+        assert_eq!(balances[0].amount, 250);
+
+        registry
+            .delete_entry(user.clone(), entry.id, &actor)
+            .unwrap();
+        assert!(registry.settle_up(user).unwrap().balances.is_empty());
+    }
+}
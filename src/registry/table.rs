@@ -1,8 +1,11 @@
 use chrono::prelude::*;
 
 use accounting::{
-    AndroidAuth, Category, CategoryId, Entry, EntryId, Product, Tag, Tags, TelegramId, User, UserId,
+    Actor, AndroidAuth, ApiToken, AuditAction, AuditId, AuditRecord, Category, CategoryId, Entry,
+    EntryId, LoginCode, LoginId, Product, Scope, Sms, SmsId, Tag, Tags, TelegramId, TokenId, User,
+    UserId,
 };
+use i18n::Locale;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RawEntry {
@@ -14,6 +17,15 @@ pub struct RawEntry {
     tags: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawSmsV1 {
+    id: String,
+    user: String,
+    from: String,
+    text: String,
+    time: NaiveDateTime,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RawCategory {
     id: String,
@@ -28,6 +40,50 @@ pub struct RawUser {
     telegram_id: Option<i64>,
     offset: Option<NaiveDateTime>,
     android_auth_token: Option<AndroidAuth>,
+    #[serde(default)]
+    locale: Option<Locale>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawUserV1 {
+    id: String,
+    telegram_id: Option<i64>,
+    offset: Option<NaiveDateTime>,
+    android_auth_token: Option<AndroidAuth>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawAuditRecord {
+    id: String,
+    time: NaiveDateTime,
+    actor: Actor,
+    action: AuditAction,
+    table: String,
+    entity_id: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawToken {
+    id: String,
+    user_id: String,
+    name: String,
+    salt: String,
+    hash: String,
+    scopes: Vec<Scope>,
+    created: NaiveDateTime,
+    last_used: Option<NaiveDateTime>,
+    revoked: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawLoginCode {
+    id: String,
+    user_id: String,
+    hash: String,
+    expires: NaiveDateTime,
+    used: bool,
 }
 
 impl Into<User> for RawUser {
@@ -37,6 +93,19 @@ impl Into<User> for RawUser {
             telegram_id: self.telegram_id.map(|val| TelegramId(val)),
             offset: self.offset,
             android_auth_token: self.android_auth_token,
+            locale: self.locale,
+        }
+    }
+}
+
+impl Into<User> for RawUserV1 {
+    fn into(self) -> User {
+        User {
+            id: UserId::new(self.id),
+            telegram_id: self.telegram_id.map(|val| TelegramId(val)),
+            offset: self.offset,
+            android_auth_token: self.android_auth_token,
+            locale: None,
         }
     }
 }
@@ -48,6 +117,7 @@ impl From<User> for RawUser {
             telegram_id: user.telegram_id.map(|id| id.0),
             offset: user.offset,
             android_auth_token: user.android_auth_token,
+            locale: user.locale,
         }
     }
 }
@@ -103,3 +173,102 @@ impl From<Entry> for RawEntry {
         }
     }
 }
+
+impl Into<Sms> for RawSmsV1 {
+    fn into(self) -> Sms {
+        Sms {
+            id: SmsId::new(self.id),
+            user: UserId::new(self.user),
+            from: self.from,
+            text: self.text,
+            time: DateTime::from_utc(self.time, Utc),
+            client_id: None,
+        }
+    }
+}
+
+impl Into<AuditRecord> for RawAuditRecord {
+    fn into(self) -> AuditRecord {
+        AuditRecord {
+            id: AuditId::new(self.id),
+            time: self.time,
+            actor: self.actor,
+            action: self.action,
+            table: self.table,
+            entity_id: self.entity_id,
+            before: self.before,
+            after: self.after,
+        }
+    }
+}
+
+impl From<AuditRecord> for RawAuditRecord {
+    fn from(record: AuditRecord) -> RawAuditRecord {
+        RawAuditRecord {
+            id: record.id.0,
+            time: record.time,
+            actor: record.actor,
+            action: record.action,
+            table: record.table,
+            entity_id: record.entity_id,
+            before: record.before,
+            after: record.after,
+        }
+    }
+}
+
+impl Into<ApiToken> for RawToken {
+    fn into(self) -> ApiToken {
+        ApiToken {
+            id: TokenId::new(self.id),
+            user_id: UserId::new(self.user_id),
+            name: self.name,
+            salt: self.salt,
+            hash: self.hash,
+            scopes: self.scopes,
+            created: self.created,
+            last_used: self.last_used,
+            revoked: self.revoked,
+        }
+    }
+}
+
+impl From<ApiToken> for RawToken {
+    fn from(token: ApiToken) -> RawToken {
+        RawToken {
+            id: token.id.0,
+            user_id: token.user_id.0,
+            name: token.name,
+            salt: token.salt,
+            hash: token.hash,
+            scopes: token.scopes,
+            created: token.created,
+            last_used: token.last_used,
+            revoked: token.revoked,
+        }
+    }
+}
+
+impl Into<LoginCode> for RawLoginCode {
+    fn into(self) -> LoginCode {
+        LoginCode {
+            id: LoginId::new(self.id),
+            user_id: UserId::new(self.user_id),
+            hash: self.hash,
+            expires: self.expires,
+            used: self.used,
+        }
+    }
+}
+
+impl From<LoginCode> for RawLoginCode {
+    fn from(login: LoginCode) -> RawLoginCode {
+        RawLoginCode {
+            id: login.id.0,
+            user_id: login.user_id.0,
+            hash: login.hash,
+            expires: login.expires,
+            used: login.used,
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::fmt;
+
+use accounting::AuditRecord;
+
+pub struct AuditRepresentation(AuditRecord);
+
+impl From<AuditRecord> for AuditRepresentation {
+    fn from(record: AuditRecord) -> AuditRepresentation {
+        AuditRepresentation(record)
+    }
+}
+
+impl fmt::Display for AuditRepresentation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let record = &self.0;
+        write!(
+            f,
+            "{} {} {:?} {}/{}",
+            record.time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            record.actor,
+            record.action,
+            record.table,
+            record.entity_id
+        )?;
+        if let Some(ref before) = record.before {
+            write!(f, "\n  before: {}", before)?;
+        }
+        if let Some(ref after) = record.after {
+            write!(f, "\n  after:  {}", after)?;
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,20 @@
 use std::fmt;
 
-use accounting::Entry;
+use accounting::{Batch, Entry};
+use error::AppError;
+use i18n::Locale;
 
-pub struct EntryRepresentation(Entry);
+pub struct EntryRepresentation(Entry, Locale);
 
 impl From<Entry> for EntryRepresentation {
     fn from(entry: Entry) -> EntryRepresentation {
-        EntryRepresentation(entry)
+        EntryRepresentation(entry, Locale::default())
+    }
+}
+
+impl EntryRepresentation {
+    pub fn with_locale(self, locale: Locale) -> EntryRepresentation {
+        EntryRepresentation(self.0, locale)
     }
 }
 
@@ -14,10 +22,66 @@ impl fmt::Display for EntryRepresentation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "{}, {} руб, {}",
+            "{}, {}, {}",
             &self.0.product.name,
-            self.0.product.price,
-            self.0.time.format("%Y-%m-%d %H:%M").to_string()
+            self.1.amount(self.0.product.price),
+            self.1.date_time(self.0.time)
         )
     }
 }
+
+pub struct BatchRepresentation<'a>(&'a Batch, Locale);
+
+impl<'a> From<&'a Batch> for BatchRepresentation<'a> {
+    fn from(batch: &'a Batch) -> BatchRepresentation<'a> {
+        BatchRepresentation(batch, Locale::default())
+    }
+}
+
+impl<'a> BatchRepresentation<'a> {
+    pub fn with_locale(self, locale: Locale) -> BatchRepresentation<'a> {
+        BatchRepresentation(self.0, locale)
+    }
+}
+
+impl<'a> fmt::Display for BatchRepresentation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let locale = self.1;
+        let text = locale.catalogue();
+        let width = self
+            .0
+            .entries
+            .iter()
+            .map(|entry| entry.product.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        writeln!(f, "{}: {}", text.added, self.0.entries.len())?;
+        for entry in &self.0.entries {
+            writeln!(
+                f,
+                "{:width$} {:>12}",
+                entry.product.name,
+                locale.amount(entry.product.price),
+                width = width
+            )?;
+        }
+        write!(f, "{}: {}", text.total, locale.amount(self.0.total()))?;
+        if !self.0.errors.is_empty() {
+            write!(f, "\n{}: {}", text.not_added, self.0.errors.len())?;
+            let missing = AppError::ProductAndPriceRequired.to_string();
+            for error in &self.0.errors {
+                let reason = if error.reason == missing {
+                    text.product_and_price_required
+                } else {
+                    error.reason.as_str()
+                };
+                write!(
+                    f,
+                    "\n{} {}: \"{}\" - {}",
+                    text.line, error.line, error.text, reason
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
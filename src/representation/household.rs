@@ -0,0 +1,95 @@
+use std::fmt;
+
+use accounting::statistics::Report;
+use accounting::{Household, Member};
+use i18n::Locale;
+
+use super::BotReportRepresentation;
+
+pub struct HouseholdRepresentation<'h>(&'h Household, &'h [Member], Locale);
+
+pub struct HouseholdReportRepresentation<'r> {
+    report: Report<'r>,
+    members: &'r [Member],
+    locale: Locale,
+}
+
+impl<'h> HouseholdRepresentation<'h> {
+    pub fn new(household: &'h Household, members: &'h [Member]) -> HouseholdRepresentation<'h> {
+        HouseholdRepresentation(household, members, Locale::default())
+    }
+
+    pub fn with_locale(self, locale: Locale) -> HouseholdRepresentation<'h> {
+        HouseholdRepresentation(self.0, self.1, locale)
+    }
+}
+
+impl<'h> fmt::Display for HouseholdRepresentation<'h> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.name)?;
+        for (index, member) in self.1.iter().enumerate() {
+            write!(
+                f,
+                "\n{}. {} ({}), {} {}",
+                index + 1,
+                member.name,
+                member.role,
+                self.2.catalogue().member_since,
+                self.2.date(member.joined)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<'r> HouseholdReportRepresentation<'r> {
+    pub fn new(report: Report<'r>, members: &'r [Member]) -> HouseholdReportRepresentation<'r> {
+        HouseholdReportRepresentation {
+            report,
+            members,
+            locale: Locale::default(),
+        }
+    }
+
+    pub fn with_locale(self, locale: Locale) -> HouseholdReportRepresentation<'r> {
+        HouseholdReportRepresentation { locale, ..self }
+    }
+}
+
+impl<'r> fmt::Display for HouseholdReportRepresentation<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = self.locale.catalogue();
+        write!(
+            f,
+            "{}",
+            BotReportRepresentation::from(self.report.clone()).with_locale(self.locale)
+        )?;
+        writeln!(f, "{}", text.by_member)?;
+        for member in self.members {
+            let (spent, products) = self
+                .report
+                .by_category
+                .iter()
+                .flat_map(|category| category.entries.iter())
+                .filter(|entry| entry.user_id == member.user_id)
+                .fold((0, 0), |(spent, products), entry| {
+                    (spent + entry.product.price, products + 1)
+                });
+            let persent = if self.report.total_spent == 0 {
+                0
+            } else {
+                (spent as f32 / self.report.total_spent as f32 * 100.) as i32
+            };
+            writeln!(
+                f,
+                "{} - {} ({}%), {} {}",
+                member.name,
+                self.locale.amount(spent),
+                persent,
+                products,
+                text.units
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,15 @@
+mod audit;
 mod entry;
+mod household;
+mod sms;
+mod split;
 mod statistics;
+mod token;
 
+pub use self::audit::*;
 pub use self::entry::*;
+pub use self::household::*;
+pub use self::sms::*;
+pub use self::split::*;
 pub use self::statistics::*;
+pub use self::token::*;
@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+
+use std::fmt;
+
+use accounting::{Sms, User};
+
+pub struct SmsRepresentation {
+    sms: Sms,
+    local_time: NaiveDateTime,
+}
+
+impl SmsRepresentation {
+    pub fn new(sms: Sms, user: &User) -> SmsRepresentation {
+        SmsRepresentation {
+            local_time: user.local_time(sms.time),
+            sms,
+        }
+    }
+}
+
+impl fmt::Display for SmsRepresentation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            self.local_time.format("%Y-%m-%d %H:%M:%S"),
+            self.sms.from,
+            self.sms.text
+        )
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use accounting::{user_of_key, Entry, SettleUp, Split, UserId};
+use i18n::Locale;
+
+#[derive(Debug, Clone, Default)]
+pub struct Participants {
+    viewer: Option<UserId>,
+    members: HashMap<UserId, String>,
+}
+
+pub struct SplitRepresentation<'s>(&'s Entry, &'s Split, Locale, Participants);
+
+pub struct SettleUpRepresentation<'s>(&'s SettleUp, Locale, Participants);
+
+impl Participants {
+    pub fn new(viewer: UserId, members: HashMap<UserId, String>) -> Participants {
+        Participants {
+            viewer: Some(viewer),
+            members,
+        }
+    }
+
+    fn name(&self, key: &str, locale: Locale) -> String {
+        match user_of_key(key) {
+            Some(ref user) if self.viewer.as_ref() == Some(user) => {
+                locale.catalogue().me.to_owned()
+            }
+            Some(ref user) => self
+                .members
+                .get(user)
+                .cloned()
+                .unwrap_or_else(|| key.to_owned()),
+            None => key.to_owned(),
+        }
+    }
+}
+
+impl<'s> SplitRepresentation<'s> {
+    pub fn new(entry: &'s Entry, split: &'s Split) -> SplitRepresentation<'s> {
+        SplitRepresentation(entry, split, Locale::default(), Participants::default())
+    }
+
+    pub fn with_locale(self, locale: Locale) -> SplitRepresentation<'s> {
+        SplitRepresentation(self.0, self.1, locale, self.3)
+    }
+
+    pub fn with_participants(self, participants: Participants) -> SplitRepresentation<'s> {
+        SplitRepresentation(self.0, self.1, self.2, participants)
+    }
+}
+
+impl<'s> fmt::Display for SplitRepresentation<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let locale = self.2;
+        write!(
+            f,
+            "{} - {}, {} {}:",
+            self.0.product.name,
+            locale.amount(self.1.amount),
+            locale.catalogue().paid_by,
+            self.3.name(&self.1.payer, locale)
+        )?;
+        for (participant, part) in self.1.parts() {
+            write!(
+                f,
+                "\n{} - {}",
+                self.3.name(participant, locale),
+                locale.amount(part)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<'s> From<&'s SettleUp> for SettleUpRepresentation<'s> {
+    fn from(settle_up: &'s SettleUp) -> SettleUpRepresentation<'s> {
+        SettleUpRepresentation(settle_up, Locale::default(), Participants::default())
+    }
+}
+
+impl<'s> SettleUpRepresentation<'s> {
+    pub fn with_locale(self, locale: Locale) -> SettleUpRepresentation<'s> {
+        SettleUpRepresentation(self.0, locale, self.2)
+    }
+
+    pub fn with_participants(self, participants: Participants) -> SettleUpRepresentation<'s> {
+        SettleUpRepresentation(self.0, self.1, participants)
+    }
+}
+
+impl<'s> fmt::Display for SettleUpRepresentation<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let locale = self.1;
+        let text = locale.catalogue();
+        if self.0.balances.is_empty() {
+            return write!(f, "{}", text.no_debts);
+        }
+        writeln!(f, "{}", text.balances)?;
+        for balance in &self.0.balances {
+            let sign = if balance.amount > 0 { "+" } else { "" };
+            writeln!(
+                f,
+                "{} {}{}",
+                self.2.name(&balance.participant, locale),
+                sign,
+                locale.amount(balance.amount)
+            )?;
+        }
+        write!(f, "{}", text.transfers)?;
+        for transfer in &self.0.transfers {
+            write!(
+                f,
+                "\n{} → {}: {}",
+                self.2.name(&transfer.from, locale),
+                self.2.name(&transfer.to, locale),
+                locale.amount(transfer.amount)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,39 +1,51 @@
 use std::fmt;
 
 use accounting::statistics::Report;
+use i18n::Locale;
 
 #[derive(Debug)]
-pub struct BotReportRepresentation<'r>(Report<'r>);
+pub struct BotReportRepresentation<'r>(Report<'r>, Locale);
 
 impl<'r> From<Report<'r>> for BotReportRepresentation<'r> {
     fn from(report: Report) -> BotReportRepresentation {
-        BotReportRepresentation(report)
+        BotReportRepresentation(report, Locale::default())
+    }
+}
+
+impl<'r> BotReportRepresentation<'r> {
+    pub fn with_locale(self, locale: Locale) -> BotReportRepresentation<'r> {
+        BotReportRepresentation(self.0, locale)
     }
 }
 
 impl<'r> fmt::Display for BotReportRepresentation<'r> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let stats = &self.0;
-        let format = "%Y-%m-%d %H:%M";
+        let locale = self.1;
+        let text = locale.catalogue();
         writeln!(
             f,
             "{} - {}",
-            stats.period.0.format(format).to_string(),
-            stats.period.1.format(format).to_string()
+            locale.date_time(stats.period.0),
+            locale.date_time(stats.period.1)
         )?;
         writeln!(
             f,
-            "Всего потрачено: {}. Вего записей: {}\n",
-            stats.total_spent, stats.total_products
+            "{}: {}. {}: {}\n",
+            text.total_spent,
+            locale.amount(stats.total_spent),
+            text.total_entries,
+            stats.total_products
         )?;
         for category in &stats.by_category {
             writeln!(
                 f,
-                "{} - {} ({}%), {} ед.",
+                "{} - {} ({}%), {} {}",
                 category.category,
-                category.total_spent,
+                locale.amount(category.total_spent),
                 category.persent as i32,
-                category.total_products
+                category.total_products,
+                text.units
             )?;
         }
         writeln!(f, "")
@@ -1,32 +1,43 @@
 use std::fmt;
 
 use accounting::statistics::Report;
+use i18n::Locale;
 
 #[derive(Debug)]
-pub struct ReportRepresentation<'r>(Report<'r>);
+pub struct ReportRepresentation<'r>(Report<'r>, Locale);
 
 impl<'r> From<Report<'r>> for ReportRepresentation<'r> {
     fn from(report: Report) -> ReportRepresentation {
-        ReportRepresentation(report)
+        ReportRepresentation(report, Locale::default())
+    }
+}
+
+impl<'r> ReportRepresentation<'r> {
+    pub fn with_locale(self, locale: Locale) -> ReportRepresentation<'r> {
+        ReportRepresentation(self.0, locale)
     }
 }
 
 impl<'r> fmt::Display for ReportRepresentation<'r> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let stats = &self.0;
-        let format = "%Y-%m-%d %H:%M";
+        let locale = self.1;
+        let text = locale.catalogue();
         writeln!(
             f,
             "{} - {}",
-            stats.period.0.format(format).to_string(),
-            stats.period.1.format(format).to_string()
+            locale.date_time(stats.period.0),
+            locale.date_time(stats.period.1)
         )?;
         writeln!(
             f,
-            "Всего потрачено: {}. Вего записей: {}\n",
-            stats.total_spent, stats.total_products
+            "{}: {}. {}: {}\n",
+            text.total_spent,
+            locale.amount(stats.total_spent),
+            text.total_entries,
+            stats.total_products
         )?;
-        writeln!(f, "По категориям:                      потрачено (руб),   %,  записей")?;
+        writeln!(f, "{}", text.by_category_header)?;
         for category in &stats.by_category {
             writeln!(
                 f,
@@ -0,0 +1,86 @@
+use std::fmt;
+
+use accounting::statistics::Report;
+
+#[derive(Debug)]
+pub struct CsvReportRepresentation<'r>(Report<'r>);
+
+impl<'r> From<Report<'r>> for CsvReportRepresentation<'r> {
+    fn from(report: Report) -> CsvReportRepresentation {
+        CsvReportRepresentation(report)
+    }
+}
+
+impl<'r> fmt::Display for CsvReportRepresentation<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "time,product,category,price")?;
+        for category in &self.0.by_category {
+            for entry in &category.entries {
+                writeln!(
+                    f,
+                    "{},{},{},{}",
+                    entry.time.format("%Y-%m-%d %H:%M:%S"),
+                    escape(&entry.product.name),
+                    escape(category.category),
+                    entry.product.price
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn escape(field: &str) -> String {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use accounting::statistics::{Statistics, TimePeriod};
+    use accounting::{Category, Entry, UserId};
+    use chrono::prelude::*;
+
+    #[test]
+    fn escapes_separators_quotes_and_line_breaks() {
+        let user = UserId::new("user".to_owned());
+        let time = NaiveDate::from_ymd(2018, 10, 1).and_hms(12, 30, 0);
+        let entry = |raw: &str| Entry {
+            time,
+            ..Entry::new(user.clone(), raw.parse().unwrap())
+        };
+        let entries = vec![
+            entry("100 сок \"Добрый\""),
+            entry("50 хлеб, батон"),
+            entry("30 молоко\nпастеризованное"),
+        ];
+        let category = "еда, напитки".to_owned();
+        let categories = entries
+            .iter()
+            .map(|e| Category::new(user.clone(), e.product.name.clone(), category.clone()))
+            .collect();
+        let statistics = Statistics::at(entries, categories, time);
+        let day = time.date();
+        let report = statistics
+            .report(TimePeriod::Any(day, day))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            format!("{}", CsvReportRepresentation::from(report)),
+            "time,product,category,price\n\
+             2018-10-01 12:30:00,\"сок \"\"Добрый\"\"\",\"еда, напитки\",100\n\
+             2018-10-01 12:30:00,\"хлеб, батон\",\"еда, напитки\",50\n\
+             2018-10-01 12:30:00,\"молоко\nпастеризованное\",\"еда, напитки\",30\n"
+        );
+    }
+
+    #[test]
+    fn keeps_plain_fields_unquoted() {
+        assert_eq!(escape("хлеб"), "хлеб");
+        assert_eq!(escape("a\rb"), "\"a\rb\"");
+    }
+}
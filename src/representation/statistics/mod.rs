@@ -1,7 +1,9 @@
 mod bot;
 mod cli;
+mod csv;
 mod react;
 
 pub use self::bot::*;
 pub use self::cli::*;
+pub use self::csv::*;
 pub use self::react::*;
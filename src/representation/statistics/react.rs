@@ -1,13 +1,15 @@
 use chrono::prelude::*;
+use failure::Error as FailureError;
 
 use std::fmt;
 
 use accounting::statistics::ByCategory;
 use accounting::statistics::Report as DomainReport;
 use accounting::Entry as DomainEntry;
+use i18n::Locale;
 
 #[derive(Debug)]
-pub struct ReactReportRepresentation<'r>(DomainReport<'r>);
+pub struct ReactReportRepresentation<'r>(DomainReport<'r>, Locale);
 
 #[derive(Debug, Serialize)]
 #[allow(non_snake_case)]
@@ -41,36 +43,46 @@ struct TimePeriod {
 
 impl<'r> From<DomainReport<'r>> for ReactReportRepresentation<'r> {
     fn from(report: DomainReport) -> ReactReportRepresentation {
-        ReactReportRepresentation(report)
+        ReactReportRepresentation(report, Locale::default())
     }
 }
 
-impl<'r> fmt::Display for ReactReportRepresentation<'r> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let head = include_str!("head.html");
-        let tail = include_str!("tail.html");
-        let mut main_report = Report::from(&self.0);
-        main_report.title = "Всего".to_owned();
+impl<'r> ReactReportRepresentation<'r> {
+    pub fn with_locale(self, locale: Locale) -> ReactReportRepresentation<'r> {
+        ReactReportRepresentation(self.0, locale)
+    }
+
+    pub fn app_data(&self) -> Result<String, FailureError> {
+        let mut main_report = Report::new(&self.0, self.1);
+        main_report.title = self.1.catalogue().total.to_owned();
         let mut reports = vec![main_report];
-        if let Some(subreports) = self.0.subreports().map_err(|_| fmt::Error)? {
+        if let Some(subreports) = self.0.subreports()? {
             subreports
                 .iter()
-                .map(Report::from)
+                .map(|r| Report::new(r, self.1))
                 .for_each(|r| reports.push(r));
         }
-        let app_data = ::serde_json::to_string(&reports).map_err(|_| fmt::Error)?;
+        Ok(::serde_json::to_string(&reports)?)
+    }
+}
+
+impl<'r> fmt::Display for ReactReportRepresentation<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let head = include_str!("head.html");
+        let tail = include_str!("tail.html");
+        let app_data = self.app_data().map_err(|_| fmt::Error)?;
         write!(f, "{}{}{}", head, app_data, tail)
     }
 }
 
-impl<'a, 'r> From<&'a DomainReport<'r>> for Report {
-    fn from(report: &'a DomainReport<'r>) -> Report {
+impl Report {
+    fn new(report: &DomainReport, locale: Locale) -> Report {
         let time_period = TimePeriod {
             from: report.period.0,
             to: report.period.1,
         };
         Report {
-            title: report_name(time_period),
+            title: report_name(time_period, locale),
             timePeriod: time_period,
             main: report.by_category.iter().map(ReportEntry::from).collect(),
             entries: report
@@ -104,15 +116,15 @@ impl<'r> From<&'r DomainEntry> for Entry {
     }
 }
 
-fn report_name(time_period: TimePeriod) -> String {
+fn report_name(time_period: TimePeriod, locale: Locale) -> String {
     if time_period.from.month() != time_period.to.month()
         && time_period.from.year() == time_period.to.year()
     {
         return format!(
             "{}: {} - {}",
             time_period.from.year(),
-            month_name(time_period.from),
-            month_name(time_period.to)
+            locale.month_name(time_period.from.month()),
+            locale.month_name(time_period.to.month())
         );
     }
     if time_period.from.month() != time_period.to.month()
@@ -120,33 +132,15 @@ fn report_name(time_period: TimePeriod) -> String {
     {
         return format!(
             "{} {} - {} {}",
-            month_name(time_period.from),
+            locale.month_name(time_period.from.month()),
             time_period.from.year(),
-            month_name(time_period.to),
+            locale.month_name(time_period.to.month()),
             time_period.to.year()
         );
     }
     format!(
         "{} {}",
-        month_name(time_period.from),
+        locale.month_name(time_period.from.month()),
         time_period.from.year()
     )
 }
-
-fn month_name(date: NaiveDateTime) -> &'static str {
-    match date.month() {
-        1 => "январь",
-        2 => "февраль",
-        3 => "март",
-        4 => "апрель",
-        5 => "май",
-        6 => "июнь",
-        7 => "июль",
-        8 => "август",
-        9 => "сентябрь",
-        10 => "октябрь",
-        11 => "ноябрь",
-        12 => "декабрь",
-        _ => unreachable!(),
-    }
-}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+use accounting::ApiToken;
+
+pub struct TokenRepresentation(ApiToken);
+
+impl From<ApiToken> for TokenRepresentation {
+    fn from(token: ApiToken) -> TokenRepresentation {
+        TokenRepresentation(token)
+    }
+}
+
+impl fmt::Display for TokenRepresentation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let token = &self.0;
+        let scopes: Vec<String> = token.scopes.iter().map(|scope| scope.to_string()).collect();
+        write!(
+            f,
+            "{} {} [{}] created {}",
+            token.id,
+            token.name,
+            scopes.join(","),
+            token.created.format("%Y-%m-%d %H:%M")
+        )?;
+        match token.last_used {
+            Some(last_used) => write!(f, ", last used {}", last_used.format("%Y-%m-%d %H:%M"))?,
+            None => write!(f, ", never used")?,
+        }
+        if token.revoked {
+            write!(f, ", revoked")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::PathBuf;
+
+use persistence::Format;
+use registry::Registry;
+
+pub struct TemporaryDir {
+    path: PathBuf,
+}
+
+impl TemporaryDir {
+    pub fn new() -> TemporaryDir {
+        let mut path = ::std::env::temp_dir();
+        path.push(format!("accounting-test-{}", ::uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path).unwrap();
+        TemporaryDir { path }
+    }
+}
+
+impl Deref for TemporaryDir {
+    type Target = PathBuf;
+    fn deref(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+impl Drop for TemporaryDir {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.path) {
+            warn!("could not remove {:?}: {}", &self.path, e);
+        }
+    }
+}
+
+pub fn temporary_registry(format: Format) -> (Registry, TemporaryDir) {
+    let dir = TemporaryDir::new();
+    let registry = Registry::new(dir.to_path_buf(), format).unwrap();
+    (registry, dir)
+}
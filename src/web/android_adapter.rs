@@ -1,10 +1,13 @@
 use actix_web::{Json, State};
-use chrono::naive::NaiveDateTime;
 use failure::Error as FailureError;
 
-use super::auth::AndroidAuth;
+use super::api::error::ApiError;
+use super::api::{authenticate, Session};
+use super::auth::TokenAuth;
 use super::AppState;
-use accounting::NewSms;
+use accounting::{NewSms, Scope, SmsStatus};
+
+const MAX_BATCH_SIZE: usize = 500;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sms {
@@ -13,63 +16,280 @@ pub struct Sms {
     pub text: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct SmsBatch {
+    pub items: Vec<SmsItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SmsItem {
+    pub client_id: Option<String>,
+    pub time: String,
+    pub from: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmsBatchResponse {
+    pub items: Vec<SmsItemStatus>,
+    pub cursor: Option<SmsCursor>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmsItemStatus {
+    pub index: usize,
+    pub client_id: Option<String>,
+    pub status: &'static str,
+    pub id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CursorResponse {
+    pub cursor: Option<SmsCursor>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmsCursor {
+    pub time: i64,
+    pub client_id: Option<String>,
+    pub id: String,
+}
+
 pub fn post_sms(
     state: State<AppState>,
     smses: Json<Vec<Sms>>,
-    auth: AndroidAuth,
+    auth: TokenAuth,
 ) -> Result<String, FailureError> {
-    let registry = state
-        .registry
-        .lock()
-        .map_err(|e| format_err!("Failed to get registry: {}", e))?;
-    let request_auth_token = auth
-        .token
-        .ok_or(format_err!("Auth token is not provided"))?;
-    let user = registry
-        .find_user(|u| match u.android_auth_token {
-            Some(ref user_auth_token) => user_auth_token.0 == request_auth_token,
-            None => false,
-        })?.ok_or(format_err!("User with auth token not found"))?;
+    let Session { user, actor } = authenticate(&state, &auth, Scope::SmsUpload)?;
+    let registry = state.registry_mut()?;
     let smses = smses
         .into_inner();
     debug!("Received {} sms for user {}", smses.len(), user.id);
     let new_sms_list: Result<Vec<NewSms>, FailureError> = smses
         .into_iter()
         .map(|sms| {
-            let timestamp_millis: i64 = sms.time.parse()?;
-            let time = NaiveDateTime::from_timestamp_opt(timestamp_millis / 1000, 0)
-                .ok_or(format_err!("NaiveDateTime::from_timestamp_opt failed"))?;
             Ok(NewSms {
                 user: user.id.clone(),
                 from: sms.from,
                 text: sms.text,
-                time,
+                time: NewSms::parse_timestamp_millis(&sms.time)?,
+                client_id: None,
             })
         }).collect();
-    registry.add_sms(new_sms_list?)?;
+    registry.add_sms(new_sms_list?, &actor)?;
     Ok("Ok".to_string())
 }
 
-pub fn get_sms_latest(state: State<AppState>, auth: AndroidAuth) -> Result<String, FailureError> {
-    let registry = state
-        .registry
-        .lock()
-        .map_err(|e| format_err!("Failed to get registry: {}", e))?;
-    let request_auth_token = auth
-        .token
-        .ok_or(format_err!("Auth token is not provided"))?;
-    let user = registry
-        .find_user(|u| match u.android_auth_token {
-            Some(ref user_auth_token) => user_auth_token.0 == request_auth_token,
-            None => false,
-        })?.ok_or(format_err!("User with auth token not found"))?;
-    let sms_list = registry.get_sms_list(user.id)?;
-    let latest_sms_date = sms_list
-        .into_iter()
-        .map(|sms| sms.time)
-        .max()
-        .map(|time| format!("{}", time.timestamp_millis()))
+pub fn get_sms_latest(state: State<AppState>, auth: TokenAuth) -> Result<String, FailureError> {
+    let user = authenticate(&state, &auth, Scope::SmsUpload)?.user;
+    let registry = state.registry()?;
+    let latest_sms_date = registry
+        .latest_sms(user.id)?
+        .map(|sms| format!("{}", sms.time.timestamp_millis()))
         .unwrap_or("0".to_string());
     debug!("latest_sms_date = {}", latest_sms_date);
     Ok(latest_sms_date)
 }
+
+pub fn post_sms_batch(
+    state: State<AppState>,
+    batch: Json<SmsBatch>,
+    auth: TokenAuth,
+) -> Result<Json<SmsBatchResponse>, ApiError> {
+    let Session { user, actor } = authenticate(&state, &auth, Scope::SmsUpload)?;
+    let items = batch.into_inner().items;
+    if items.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::bad_request(format!(
+            "batch must contain at most {} items",
+            MAX_BATCH_SIZE
+        )));
+    }
+    debug!("Received batch of {} sms for user {}", items.len(), user.id);
+
+    let mut statuses = Vec::with_capacity(items.len());
+    let mut accepted = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        match NewSms::parse_timestamp_millis(&item.time) {
+            Ok(time) => accepted.push((
+                index,
+                NewSms {
+                    user: user.id.clone(),
+                    from: item.from,
+                    text: item.text,
+                    time,
+                    client_id: item.client_id,
+                },
+            )),
+            Err(e) => statuses.push(SmsItemStatus {
+                index,
+                client_id: item.client_id,
+                status: "invalid",
+                id: None,
+                error: Some(format!("{}", e)),
+            }),
+        }
+    }
+
+    let registry = state.registry_mut()?;
+    let received: Vec<(usize, i64, Option<String>)> = accepted
+        .iter()
+        .map(|&(index, ref sms)| (index, sms.time.timestamp_millis(), sms.client_id.clone()))
+        .collect();
+    let results = registry.add_sms(accepted.into_iter().map(|(_, sms)| sms).collect(), &actor)?;
+    let mut acknowledged = None;
+    for ((index, time, client_id), result) in received.into_iter().zip(results) {
+        let (status, id) = match result {
+            SmsStatus::Stored(id) => ("stored", id),
+            SmsStatus::Duplicate(id) => ("duplicate", id),
+        };
+        acknowledged = Some(SmsCursor {
+            time,
+            client_id: client_id.clone(),
+            id: id.0.clone(),
+        });
+        statuses.push(SmsItemStatus {
+            index,
+            client_id,
+            status,
+            id: Some(id.0),
+            error: None,
+        });
+    }
+    statuses.sort_by_key(|status| status.index);
+
+    let cursor = match acknowledged {
+        Some(acknowledged) => Some(acknowledged),
+        None => cursor(&registry.last_received_sms(user.id)?),
+    };
+    Ok(Json(SmsBatchResponse {
+        items: statuses,
+        cursor,
+    }))
+}
+
+pub fn get_sms_cursor(
+    state: State<AppState>,
+    auth: TokenAuth,
+) -> Result<Json<CursorResponse>, ApiError> {
+    let user = authenticate(&state, &auth, Scope::SmsUpload)?.user;
+    let registry = state.registry()?;
+    Ok(Json(CursorResponse {
+        cursor: cursor(&registry.last_received_sms(user.id)?),
+    }))
+}
+
+fn cursor(latest: &Option<::accounting::Sms>) -> Option<SmsCursor> {
+    latest.as_ref().map(|sms| SmsCursor {
+        time: sms.time.timestamp_millis(),
+        client_id: sms.client_id.clone(),
+        id: sms.id.0.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{header, Method, StatusCode};
+    use actix_web::test::TestServer;
+    use actix_web::{App, HttpMessage};
+    use chrono::prelude::*;
+    use chrono::Duration;
+    use serde_json::Value;
+
+    use std::sync::Arc;
+
+    use super::*;
+    use accounting::{Actor, TelegramId};
+    use persistence::Format;
+    use testing::temporary_registry;
+    use web::state::AppState as InnerAppState;
+
+    fn send(server: &mut TestServer, secret: &str, body: Option<&str>) -> Value {
+        let path = match body {
+            Some(_) => "/android/v2/sms",
+            None => "/android/v2/sms/cursor",
+        };
+        let method = match body {
+            Some(_) => Method::POST,
+            None => Method::GET,
+        };
+        let mut request = server.client(method, path);
+        request.header(header::AUTHORIZATION, format!("Bearer {}", secret));
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body.to_owned())
+                .unwrap(),
+            None => request.finish().unwrap(),
+        };
+        let response = server.execute(request.send()).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = server.execute(response.body()).unwrap();
+        ::serde_json::from_slice(&body).unwrap()
+    }
+
+    #[test]
+    fn resumes_after_the_last_received_sms_of_a_partial_batch() {
+        let (registry, _dir) = temporary_registry(Format::Json);
+        let user = registry.find_or_create(TelegramId(1), &Actor::Cli).unwrap();
+        let (_, secret) = registry
+            .issue_token(
+                user.id.clone(),
+                "phone".to_owned(),
+                vec![Scope::SmsUpload],
+                &Actor::Cli,
+            )
+            .unwrap();
+        registry
+            .add_sms(
+                vec![NewSms {
+                    user: user.id.clone(),
+                    from: "bank".to_owned(),
+                    text: "third".to_owned(),
+                    time: Utc.timestamp_millis(3000),
+                    client_id: Some("3".to_owned()),
+                }],
+                &Actor::Cli,
+            ).unwrap();
+        let state = Arc::new(InnerAppState::new(registry, Duration::hours(1)));
+        let mut server = TestServer::with_factory(move || {
+            App::with_state(state.clone())
+                .resource("/android/v2/sms/cursor", |r| {
+                    r.method(Method::GET).with(get_sms_cursor)
+                }).resource("/android/v2/sms", |r| {
+                    r.method(Method::POST).with(post_sms_batch)
+                })
+        });
+
+        let batch = send(
+            &mut server,
+            &secret,
+            Some(
+                r#"{"items": [
+                    {"client_id": "4", "time": "1000", "from": "bank", "text": "fourth"},
+                    {"client_id": "5", "time": "2000", "from": "bank", "text": "fifth"}
+                ]}"#,
+            ),
+        );
+        assert_eq!(batch["cursor"]["client_id"], "5");
+        assert_eq!(batch["cursor"]["time"], 2000);
+
+        let cursor = send(&mut server, &secret, None);
+        assert_eq!(cursor["cursor"]["client_id"], "5");
+
+        let resumed = send(
+            &mut server,
+            &secret,
+            Some(
+                r#"{"items": [
+                    {"client_id": "5", "time": "2000", "from": "bank", "text": "fifth"},
+                    {"client_id": "6", "time": "1500", "from": "bank", "text": "sixth"}
+                ]}"#,
+            ),
+        );
+        assert_eq!(resumed["items"][0]["status"], "duplicate");
+        assert_eq!(resumed["items"][1]["status"], "stored");
+        assert_eq!(resumed["cursor"]["client_id"], "6");
+        assert_eq!(send(&mut server, &secret, None)["cursor"]["client_id"], "6");
+    }
+}
@@ -0,0 +1,68 @@
+use actix_web::{HttpResponse, Json, Path, State};
+
+use super::error::ApiError;
+use super::{authenticate, Session};
+use accounting::{Category, CategoryId, Scope};
+use web::auth::TokenAuth;
+use web::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct NewCategoryRequest {
+    product: String,
+    category: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryDto {
+    id: String,
+    product: String,
+    category: String,
+}
+
+pub fn list(state: State<AppState>, auth: TokenAuth) -> Result<Json<Vec<CategoryDto>>, ApiError> {
+    let user = authenticate(&state, &auth, Scope::Read)?.user;
+    let registry = state.registry()?;
+    let categories = registry.categories(user.id)?;
+    Ok(Json(categories.into_iter().map(CategoryDto::from).collect()))
+}
+
+pub fn create(
+    state: State<AppState>,
+    auth: TokenAuth,
+    request: Json<NewCategoryRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let Session { user, actor } = authenticate(&state, &auth, Scope::Write)?;
+    let registry = state.registry_mut()?;
+    let request = request.into_inner();
+    if request.product.trim().is_empty() || request.category.trim().is_empty() {
+        return Err(ApiError::bad_request(
+            "\"product\" and \"category\" must not be empty",
+        ));
+    }
+    let category =
+        registry.add_category(user.id, request.product, request.category, &actor)?;
+    Ok(HttpResponse::Created().json(CategoryDto::from(category)))
+}
+
+pub fn delete(
+    state: State<AppState>,
+    auth: TokenAuth,
+    id: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let Session { user, actor } = authenticate(&state, &auth, Scope::Write)?;
+    let registry = state.registry_mut()?;
+    registry
+        .delete_category(user.id, CategoryId::new(id.into_inner()), &actor)?
+        .ok_or(ApiError::not_found("category not found"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+impl From<Category> for CategoryDto {
+    fn from(category: Category) -> CategoryDto {
+        CategoryDto {
+            id: category.id.0,
+            product: category.product,
+            category: category.category,
+        }
+    }
+}
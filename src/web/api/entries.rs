@@ -0,0 +1,202 @@
+use actix_web::{HttpResponse, Json, Path, Query, State};
+use chrono::prelude::*;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::error::ApiError;
+use super::{authenticate, paginate, parse_date, Page, Session};
+use accounting::{Entry, EntryId, Product, Scope, Tag, Tags, UserId};
+use dates::{end_of_day, start_of_day};
+use web::auth::TokenAuth;
+use web::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct EntriesQuery {
+    from: Option<String>,
+    till: Option<String>,
+    product: Option<String>,
+    offset: Option<String>,
+    limit: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum NewEntryRequest {
+    Text {
+        text: String,
+    },
+    Structured {
+        product: String,
+        price: i32,
+        time: Option<NaiveDateTime>,
+        tags: Option<Vec<String>>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateEntryRequest {
+    product: Option<String>,
+    price: Option<i32>,
+    time: Option<NaiveDateTime>,
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntryDto {
+    id: String,
+    owner: String,
+    member: Option<String>,
+    product: String,
+    price: i32,
+    time: NaiveDateTime,
+    tags: Vec<String>,
+}
+
+pub fn list(
+    state: State<AppState>,
+    auth: TokenAuth,
+    query: Query<EntriesQuery>,
+) -> Result<Json<Page<EntryDto>>, ApiError> {
+    let user = authenticate(&state, &auth, Scope::Read)?.user;
+    let registry = state.registry()?;
+    let from = parse_date("from", &query.from)?.map(|date| date.and_time(start_of_day()));
+    let till = parse_date("till", &query.till)?.map(|date| date.and_time(end_of_day()));
+    let members = registry.member_names(user.id.clone())?;
+    let mut entries: Vec<Entry> = registry
+        .visible_entries(user.id)?
+        .into_iter()
+        .filter(|e| from.map_or(true, |from| e.time >= from))
+        .filter(|e| till.map_or(true, |till| e.time <= till))
+        .filter(|e| {
+            query
+                .product
+                .as_ref()
+                .map_or(true, |product| e.product.name == *product)
+        }).collect();
+    entries.sort_by(|a, b| b.time.cmp(&a.time));
+    let page = paginate(entries, &query.offset, &query.limit)?;
+    Ok(Json(Page {
+        total: page.total,
+        offset: page.offset,
+        limit: page.limit,
+        items: page
+            .items
+            .into_iter()
+            .map(|entry| EntryDto::new(entry, &members))
+            .collect(),
+    }))
+}
+
+pub fn create(
+    state: State<AppState>,
+    auth: TokenAuth,
+    request: Json<NewEntryRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let Session { user, actor } = authenticate(&state, &auth, Scope::Write)?;
+    let registry = state.registry_mut()?;
+    let entry = match request.into_inner() {
+        NewEntryRequest::Text { text } => {
+            let product =
+                Product::from_str(&text).map_err(|e| ApiError::bad_request(format!("{}", e)))?;
+            Entry::new(user.id, product)
+        }
+        NewEntryRequest::Structured {
+            product,
+            price,
+            time,
+            tags,
+        } => {
+            if product.trim().is_empty() {
+                return Err(ApiError::bad_request("\"product\" must not be empty"));
+            }
+            let mut entry = Entry::new(
+                user.id,
+                Product {
+                    name: product,
+                    price,
+                },
+            );
+            if let Some(time) = time {
+                entry.time = time;
+            }
+            if let Some(tags) = tags {
+                entry.tags = tags_from(tags);
+            }
+            entry
+        }
+    };
+    registry.add_entry(entry.clone(), &actor)?;
+    let members = registry.member_names(entry.user_id.clone())?;
+    Ok(HttpResponse::Created().json(EntryDto::new(entry, &members)))
+}
+
+pub fn update(
+    state: State<AppState>,
+    auth: TokenAuth,
+    id: Path<String>,
+    request: Json<UpdateEntryRequest>,
+) -> Result<Json<EntryDto>, ApiError> {
+    let Session { user, actor } = authenticate(&state, &auth, Scope::Write)?;
+    let registry = state.registry_mut()?;
+    let request = request.into_inner();
+    if request.product.as_ref().map_or(false, |p| p.trim().is_empty()) {
+        return Err(ApiError::bad_request("\"product\" must not be empty"));
+    }
+    let members = registry.member_names(user.id.clone())?;
+    let updated = registry.update_entry(
+        user.id,
+        EntryId::new(id.into_inner()),
+        |entry| {
+            if let Some(ref product) = request.product {
+                entry.product.name = product.clone();
+            }
+            if let Some(price) = request.price {
+                entry.product.price = price;
+            }
+            if let Some(time) = request.time {
+                entry.time = time;
+            }
+            if let Some(ref tags) = request.tags {
+                entry.tags = tags_from(tags.clone());
+            }
+        },
+        &actor,
+    )?;
+    updated
+        .map(|entry| Json(EntryDto::new(entry, &members)))
+        .ok_or(ApiError::not_found("entry not found"))
+}
+
+pub fn delete(
+    state: State<AppState>,
+    auth: TokenAuth,
+    id: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let Session { user, actor } = authenticate(&state, &auth, Scope::Write)?;
+    let registry = state.registry_mut()?;
+    registry
+        .delete_entry(user.id, EntryId::new(id.into_inner()), &actor)?
+        .ok_or(ApiError::not_found("entry not found"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn tags_from(tags: Vec<String>) -> Tags {
+    Tags {
+        tags: tags.into_iter().map(|value| Tag { value }).collect(),
+    }
+}
+
+impl EntryDto {
+    fn new(entry: Entry, members: &HashMap<UserId, String>) -> EntryDto {
+        EntryDto {
+            id: entry.id.0,
+            member: members.get(&entry.user_id).cloned(),
+            owner: entry.user_id.0,
+            product: entry.product.name,
+            price: entry.product.price,
+            time: entry.time,
+            tags: entry.tags.tags.into_iter().map(|tag| tag.value).collect(),
+        }
+    }
+}
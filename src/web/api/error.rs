@@ -0,0 +1,134 @@
+use actix_web::error::JsonPayloadError;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use failure::Error as FailureError;
+
+use error::AppError;
+use web::AppState;
+
+#[derive(Debug, Fail)]
+pub enum ApiError {
+    #[fail(display = "{}", message)]
+    BadRequest { message: String },
+    #[fail(display = "{}", message)]
+    Unauthorized { message: String },
+    #[fail(display = "{}", message)]
+    Forbidden { message: String },
+    #[fail(display = "{}", message)]
+    NotFound { message: String },
+    #[fail(display = "{}", message)]
+    Internal { message: String },
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: ErrorDetails,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorDetails {
+    code: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    pub fn bad_request<S: Into<String>>(message: S) -> ApiError {
+        ApiError::BadRequest {
+            message: message.into(),
+        }
+    }
+
+    pub fn unauthorized<S: Into<String>>(message: S) -> ApiError {
+        ApiError::Unauthorized {
+            message: message.into(),
+        }
+    }
+
+    pub fn forbidden<S: Into<String>>(message: S) -> ApiError {
+        ApiError::Forbidden {
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found<S: Into<String>>(message: S) -> ApiError {
+        ApiError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match *self {
+            ApiError::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            ApiError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match *self {
+            ApiError::BadRequest { .. } => "bad_request",
+            ApiError::Unauthorized { .. } => "unauthorized",
+            ApiError::Forbidden { .. } => "forbidden",
+            ApiError::NotFound { .. } => "not_found",
+            ApiError::Internal { .. } => "internal",
+        }
+    }
+
+    fn public_message(&self) -> String {
+        match *self {
+            ApiError::Internal { .. } => "internal error".to_owned(),
+            _ => format!("{}", self),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status()).json(ErrorBody {
+            error: ErrorDetails {
+                code: self.code(),
+                message: self.public_message(),
+            },
+        })
+    }
+}
+
+impl From<FailureError> for ApiError {
+    fn from(error: FailureError) -> ApiError {
+        let message = format!("{}", error);
+        match error.downcast_ref::<AppError>() {
+            Some(AppError::NoDataForPeriod) => ApiError::NotFound { message },
+            Some(AppError::NotYourEntry) => ApiError::Forbidden { message },
+            Some(AppError::ProductAndPriceRequired)
+            | Some(AppError::InvalidDate)
+            | Some(AppError::InvalidEnumVariant)
+            | Some(AppError::ProductAlreadyHasCategory { .. })
+            | Some(AppError::Calculation { .. }) => ApiError::BadRequest { message },
+            _ => {
+                error!("api request failed: {}", &message);
+                ApiError::Internal { message }
+            }
+        }
+    }
+}
+
+pub fn json_error(error: JsonPayloadError, _req: &HttpRequest<AppState>) -> ::actix_web::Error {
+    ApiError::bad_request(format!("invalid request body: {}", error)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hides_internal_messages_from_clients() {
+        let error = ApiError::from(format_err!("could not open /data/users.table"));
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.public_message(), "internal error");
+
+        let error = ApiError::from(FailureError::from(AppError::InvalidDate));
+        assert_eq!(error.public_message(), "invalid date provided");
+    }
+}
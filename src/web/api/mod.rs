@@ -0,0 +1,310 @@
+use actix_web::{http, App};
+use chrono::prelude::*;
+
+mod categories;
+mod entries;
+pub mod error;
+pub mod reports;
+mod sms;
+mod users;
+
+use self::error::{json_error, ApiError};
+use super::auth::TokenAuth;
+use super::settings::WebSettings;
+use super::AppState;
+use accounting::{Actor, Scope, User, UserId};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug)]
+pub struct Session {
+    pub user: User,
+    pub actor: Actor,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<T>,
+}
+
+pub fn routes(app: App<AppState>, settings: &WebSettings) -> App<AppState> {
+    let json_limit = settings.json_limit;
+    app.scope("/api/v1", move |scope| {
+        scope
+            .resource("/entries", move |r| {
+                r.method(http::Method::GET).with(entries::list);
+                r.method(http::Method::POST)
+                    .with_config(entries::create, move |cfg| {
+                        (cfg.2).limit(json_limit).error_handler(json_error);
+                    });
+            }).resource("/entries/{id}", move |r| {
+                r.method(http::Method::PUT)
+                    .with_config(entries::update, move |cfg| {
+                        (cfg.3).limit(json_limit).error_handler(json_error);
+                    });
+                r.method(http::Method::DELETE).with(entries::delete);
+            }).resource("/categories", move |r| {
+                r.method(http::Method::GET).with(categories::list);
+                r.method(http::Method::POST)
+                    .with_config(categories::create, move |cfg| {
+                        (cfg.2).limit(json_limit).error_handler(json_error);
+                    });
+            }).resource("/categories/{id}", |r| {
+                r.method(http::Method::DELETE).with(categories::delete);
+            }).resource("/user", move |r| {
+                r.method(http::Method::GET).with(users::get);
+                r.method(http::Method::PUT)
+                    .with_config(users::update, move |cfg| {
+                        (cfg.2).limit(json_limit).error_handler(json_error);
+                    });
+            }).resource("/sms", |r| {
+                r.method(http::Method::GET).with(sms::list);
+            }).resource("/reports", |r| {
+                r.method(http::Method::GET).with(reports::get);
+            })
+    })
+}
+
+pub fn authenticate(
+    state: &AppState,
+    auth: &TokenAuth,
+    scope: Scope,
+) -> Result<Session, ApiError> {
+    match (&auth.token, &auth.session) {
+        (&Some(ref request_auth_token), _) => {
+            let (user, token) = state
+                .registry()?
+                .authenticate(request_auth_token)?
+                .ok_or(ApiError::unauthorized("Auth token is unknown or revoked"))?;
+            if token.needs_touch(::chrono::offset::Local::now().naive_local()) {
+                state.registry_mut()?.touch_token(&token.id)?;
+            }
+            if !token.allows(scope) {
+                return Err(ApiError::forbidden(format!(
+                    "Token \"{}\" has no \"{}\" scope",
+                    token.name, scope
+                )));
+            }
+            Ok(Session {
+                user,
+                actor: Actor::Web(token.name),
+            })
+        }
+        (&None, &Some(ref session)) => {
+            if session.is_expired() {
+                return Err(ApiError::unauthorized("Session has expired"));
+            }
+            if scope == Scope::SmsUpload {
+                return Err(ApiError::forbidden("Sms upload requires an auth token"));
+            }
+            let user_id = UserId::new(session.user_id.clone());
+            let user = state
+                .registry()?
+                .find_user(|u| u.id == user_id)?
+                .ok_or(ApiError::unauthorized("Session user not found"))?;
+            Ok(Session {
+                user,
+                actor: Actor::Web("browser".to_owned()),
+            })
+        }
+        (&None, &None) => Err(ApiError::unauthorized("Auth token is not provided")),
+    }
+}
+
+fn parse_date(name: &str, value: &Option<String>) -> Result<Option<NaiveDate>, ApiError> {
+    match *value {
+        Some(ref value) => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| ApiError::bad_request(format!("\"{}\" must be a YYYY-MM-DD date", name))),
+        None => Ok(None),
+    }
+}
+
+fn parse_number(name: &str, value: &Option<String>, default: usize) -> Result<usize, ApiError> {
+    match *value {
+        Some(ref value) => value
+            .parse()
+            .map_err(|_| ApiError::bad_request(format!("\"{}\" must be a number", name))),
+        None => Ok(default),
+    }
+}
+
+fn paginate<T>(
+    items: Vec<T>,
+    offset: &Option<String>,
+    limit: &Option<String>,
+) -> Result<Page<T>, ApiError> {
+    let offset = parse_number("offset", offset, 0)?;
+    let limit = parse_number("limit", limit, DEFAULT_PAGE_SIZE)?;
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(ApiError::bad_request(format!(
+            "\"limit\" must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+    let total = items.len();
+    let items = items.into_iter().skip(offset).take(limit).collect();
+    Ok(Page {
+        total,
+        offset,
+        limit,
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{header, Method, StatusCode};
+    use actix_web::test::TestServer;
+    use actix_web::{App, HttpMessage};
+    use chrono::Duration;
+    use serde_json::Value;
+
+    use std::sync::Arc;
+
+    use accounting::{Actor, Scope, TelegramId};
+    use config::Config;
+    use persistence::Format;
+    use web::settings::WebSettings;
+    use testing::{temporary_registry, TemporaryDir};
+    use web::state::AppState as InnerAppState;
+
+    fn server() -> (TestServer, String, TemporaryDir) {
+        let (registry, path) = temporary_registry(Format::Json);
+        let user = registry.find_or_create(TelegramId(1), &Actor::Cli).unwrap();
+        let (_, secret) = registry
+            .issue_token(user.id, "test".to_owned(), Scope::all(), &Actor::Cli)
+            .unwrap();
+        let config: Config = ::serde_yaml::from_str(&format!(
+            "data_path: {:?}\ntelegram_user_id: 1\nallowed_telegram_users: []\nmax_number_of_tries: 1\n",
+            *path
+        )).unwrap();
+        let settings = WebSettings::from_config(&config).unwrap();
+        let state = Arc::new(InnerAppState::new(registry, Duration::hours(1)));
+        let server = TestServer::with_factory(move || {
+            super::routes(App::with_state(state.clone()), &settings)
+        });
+        (server, secret, path)
+    }
+
+    fn send(
+        server: &mut TestServer,
+        method: Method,
+        path: &str,
+        secret: Option<&str>,
+        body: Option<&str>,
+    ) -> (StatusCode, Value) {
+        let mut request = server.client(method, path);
+        if let Some(secret) = secret {
+            request.header(header::AUTHORIZATION, format!("Bearer {}", secret));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body.to_owned())
+                .unwrap(),
+            None => request.finish().unwrap(),
+        };
+        let response = server.execute(request.send()).unwrap();
+        let status = response.status();
+        let body = server.execute(response.body()).unwrap();
+        if body.is_empty() {
+            return (status, Value::Null);
+        }
+        (status, ::serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn rejects_missing_and_unknown_tokens() {
+        let (mut server, _, _dir) = server();
+
+        let (status, body) = send(&mut server, Method::GET, "/api/v1/entries", None, None);
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"]["code"], "unauthorized");
+        assert_eq!(body["error"]["message"], "Auth token is not provided");
+
+        let (status, body) = send(
+            &mut server,
+            Method::GET,
+            "/api/v1/entries",
+            Some("unknown"),
+            None,
+        );
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"]["message"], "Auth token is unknown or revoked");
+    }
+
+    #[test]
+    fn creates_updates_and_deletes_entry() {
+        let (mut server, secret, _dir) = server();
+        let secret = Some(secret.as_str());
+
+        let (status, created) = send(
+            &mut server,
+            Method::POST,
+            "/api/v1/entries",
+            secret,
+            Some(r#"{"text": "хлеб 50"}"#),
+        );
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["product"], "хлеб");
+        assert_eq!(created["price"], 50);
+        assert!(created["owner"].is_string());
+        assert!(created["member"].is_null());
+        let entry_path = format!("/api/v1/entries/{}", created["id"].as_str().unwrap());
+
+        let (status, updated) = send(
+            &mut server,
+            Method::PUT,
+            &entry_path,
+            secret,
+            Some(r#"{"price": 60}"#),
+        );
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["product"], "хлеб");
+        assert_eq!(updated["price"], 60);
+
+        let (status, _) = send(&mut server, Method::DELETE, &entry_path, secret, None);
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, page) = send(&mut server, Method::GET, "/api/v1/entries", secret, None);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total"], 0);
+
+        let (status, body) = send(&mut server, Method::DELETE, &entry_path, secret, None);
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "not_found");
+    }
+
+    #[test]
+    fn reports_bad_requests_as_json() {
+        let (mut server, secret, _dir) = server();
+        let secret = Some(secret.as_str());
+
+        let (status, body) = send(&mut server, Method::POST, "/api/v1/entries", secret, Some("{"));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "bad_request");
+        assert!(
+            body["error"]["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("invalid request body")
+        );
+
+        let (status, body) = send(
+            &mut server,
+            Method::POST,
+            "/api/v1/entries",
+            secret,
+            Some(r#"{"text": "хлеб"}"#),
+        );
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"]["message"],
+            "a line must contain a product and a price"
+        );
+    }
+}
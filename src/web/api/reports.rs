@@ -0,0 +1,94 @@
+use actix_web::{Json, Query, State};
+use chrono::NaiveDateTime;
+
+use std::str::FromStr;
+
+use super::error::ApiError;
+use super::{authenticate, parse_date};
+use accounting::statistics::{ByCategory, Report, TimePeriod};
+use accounting::Scope;
+use error::AppError;
+use web::auth::TokenAuth;
+use web::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    period: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportDto {
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    total_spent: i32,
+    total_products: i32,
+    by_category: Vec<ByCategoryDto>,
+    subreports: Vec<ReportDto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ByCategoryDto {
+    category: String,
+    total_spent: i32,
+    total_products: i32,
+    persent: f32,
+}
+
+pub fn get(
+    state: State<AppState>,
+    auth: TokenAuth,
+    query: Query<ReportQuery>,
+) -> Result<Json<ReportDto>, ApiError> {
+    let user = authenticate(&state, &auth, Scope::Read)?.user;
+    let registry = state.registry()?;
+    let time_period = time_period(&query)?;
+    let statistics = registry.statistics_for(user.id, &time_period)?;
+    let no_data = ApiError::not_found(format!("{}", AppError::NoDataForPeriod));
+    let report = statistics.report(time_period)?.ok_or(no_data)?;
+    let subreports = report.subreports()?.unwrap_or_default();
+    let mut report_dto = ReportDto::from(&report);
+    report_dto.subreports = subreports.iter().map(ReportDto::from).collect();
+    Ok(Json(report_dto))
+}
+
+pub fn time_period(query: &ReportQuery) -> Result<TimePeriod, ApiError> {
+    let from = parse_date("from", &query.from)?;
+    let to = parse_date("to", &query.to)?;
+    match (&query.period, from, to) {
+        (&Some(ref period), None, None) => TimePeriod::from_str(period).map_err(|_| {
+            ApiError::bad_request("\"period\" must be one of day, week, month, year, year-YYYY")
+        }),
+        (&None, Some(from), Some(to)) if from <= to => Ok(TimePeriod::Any(from, to)),
+        (&None, Some(_), Some(_)) => Err(ApiError::bad_request("\"from\" must not be after \"to\"")),
+        (&None, None, None) => Ok(TimePeriod::ThisMonth),
+        _ => Err(ApiError::bad_request(
+            "either \"period\" or both \"from\" and \"to\" must be provided",
+        )),
+    }
+}
+
+impl<'a, 'r> From<&'a Report<'r>> for ReportDto {
+    fn from(report: &'a Report<'r>) -> ReportDto {
+        ReportDto {
+            from: report.period.0,
+            to: report.period.1,
+            total_spent: report.total_spent,
+            total_products: report.total_products,
+            by_category: report.by_category.iter().map(ByCategoryDto::from).collect(),
+            subreports: Vec::new(),
+        }
+    }
+}
+
+impl<'a, 'r> From<&'a ByCategory<'r>> for ByCategoryDto {
+    fn from(by_category: &'a ByCategory<'r>) -> ByCategoryDto {
+        ByCategoryDto {
+            category: by_category.category.to_owned(),
+            total_spent: by_category.total_spent,
+            total_products: by_category.total_products,
+            persent: by_category.persent,
+        }
+    }
+}
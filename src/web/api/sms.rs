@@ -0,0 +1,69 @@
+use actix_web::{Json, Query, State};
+use chrono::prelude::*;
+
+use super::error::ApiError;
+use super::{authenticate, paginate, parse_date, Page};
+use accounting::{Scope, Sms, SmsFilter, User};
+use dates::{end_of_day, start_of_day};
+use web::auth::TokenAuth;
+use web::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct SmsQuery {
+    sender: Option<String>,
+    q: Option<String>,
+    from: Option<String>,
+    till: Option<String>,
+    offset: Option<String>,
+    limit: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmsDto {
+    id: String,
+    sender: String,
+    text: String,
+    time: DateTime<Utc>,
+    local_time: NaiveDateTime,
+    client_id: Option<String>,
+}
+
+pub fn list(
+    state: State<AppState>,
+    auth: TokenAuth,
+    query: Query<SmsQuery>,
+) -> Result<Json<Page<SmsDto>>, ApiError> {
+    let user = authenticate(&state, &auth, Scope::Read)?.user;
+    let registry = state.registry()?;
+    let filter = SmsFilter {
+        sender: query.sender.clone(),
+        text: query.q.clone(),
+        from: parse_date("from", &query.from)?.map(|date| date.and_time(start_of_day())),
+        till: parse_date("till", &query.till)?.map(|date| date.and_time(end_of_day())),
+    };
+    let sms_list = registry.search_sms(&user, &filter)?;
+    let page = paginate(sms_list, &query.offset, &query.limit)?;
+    Ok(Json(Page {
+        total: page.total,
+        offset: page.offset,
+        limit: page.limit,
+        items: page
+            .items
+            .into_iter()
+            .map(|sms| SmsDto::new(sms, &user))
+            .collect(),
+    }))
+}
+
+impl SmsDto {
+    fn new(sms: Sms, user: &User) -> SmsDto {
+        SmsDto {
+            local_time: user.local_time(sms.time),
+            id: sms.id.0,
+            sender: sms.from,
+            text: sms.text,
+            time: sms.time,
+            client_id: sms.client_id,
+        }
+    }
+}
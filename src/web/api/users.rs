@@ -0,0 +1,60 @@
+use actix_web::{Json, State};
+use chrono::NaiveDateTime;
+
+use super::error::ApiError;
+use super::{authenticate, Session};
+use accounting::{Scope, User};
+use web::auth::TokenAuth;
+use web::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSettingsRequest {
+    timezone_offset_minutes: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserSettingsDto {
+    id: String,
+    telegram_id: Option<i64>,
+    timezone_offset_minutes: Option<i64>,
+}
+
+pub fn get(state: State<AppState>, auth: TokenAuth) -> Result<Json<UserSettingsDto>, ApiError> {
+    let user = authenticate(&state, &auth, Scope::Read)?.user;
+    Ok(Json(UserSettingsDto::from(user)))
+}
+
+pub fn update(
+    state: State<AppState>,
+    auth: TokenAuth,
+    request: Json<UpdateSettingsRequest>,
+) -> Result<Json<UserSettingsDto>, ApiError> {
+    let Session { user, actor } = authenticate(&state, &auth, Scope::Write)?;
+    let registry = state.registry_mut()?;
+    let offset_in_minutes = request.timezone_offset_minutes;
+    if offset_in_minutes.abs() > 14 * 60 {
+        return Err(ApiError::bad_request(
+            "\"timezone_offset_minutes\" must be within 14 hours",
+        ));
+    }
+    let offset = NaiveDateTime::from_timestamp(offset_in_minutes as i64 * 60, 0);
+    registry.update_user(
+        user.id.clone(),
+        |ref mut u| u.offset = Some(offset),
+        &actor,
+    )?;
+    let user = registry
+        .find_user(|u| u.id == user.id)?
+        .ok_or(ApiError::not_found("user not found"))?;
+    Ok(Json(UserSettingsDto::from(user)))
+}
+
+impl From<User> for UserSettingsDto {
+    fn from(user: User) -> UserSettingsDto {
+        UserSettingsDto {
+            id: user.id.0,
+            telegram_id: user.telegram_id.map(|id| id.0),
+            timezone_offset_minutes: user.offset.map(|offset| offset.timestamp() / 60),
+        }
+    }
+}
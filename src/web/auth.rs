@@ -1,25 +1,39 @@
+use actix_web::http::header::AUTHORIZATION;
 use actix_web::{FromRequest, HttpRequest};
 use failure::Error as FailureError;
 
+use super::session::SessionUser;
 use super::AppState;
 
 #[derive(Debug, Clone)]
-pub struct AndroidAuth {
+pub struct TokenAuth {
     pub token: Option<String>,
+    pub session: Option<SessionUser>,
 }
 
 const ANDROID_AUTH_TOKEN_HEADER: &'static str = "android-token";
+const BEARER_PREFIX: &'static str = "Bearer ";
 
-impl FromRequest<AppState> for AndroidAuth {
+impl FromRequest<AppState> for TokenAuth {
     type Config = ();
-    type Result = Result<AndroidAuth, FailureError>;
+    type Result = Result<TokenAuth, FailureError>;
     fn from_request(req: &HttpRequest<AppState>, _cfg: &Self::Config) -> Self::Result {
-        let token = if let Some(token_header) = req.headers().get(ANDROID_AUTH_TOKEN_HEADER) {
+        let token = if let Some(authorization) = req.headers().get(AUTHORIZATION) {
+            let authorization = authorization.to_str()?;
+            if authorization.starts_with(BEARER_PREFIX) {
+                Some(authorization[BEARER_PREFIX.len()..].trim().to_string())
+            } else {
+                None
+            }
+        } else if let Some(token_header) = req.headers().get(ANDROID_AUTH_TOKEN_HEADER) {
             Some(token_header.to_str()?.to_string())
         } else {
             None
         };
 
-        Ok(AndroidAuth { token })
+        Ok(TokenAuth {
+            token,
+            session: SessionUser::from_request(req),
+        })
     }
 }
@@ -4,11 +4,20 @@ use actix_web::{http, middleware, server, App};
 use failure::Error as FailureError;
 
 mod android_adapter;
+mod api;
 mod app;
 mod auth;
+mod report;
+mod session;
+mod settings;
 mod state;
+mod telegram;
 
 use self::app::App as WebApp;
+use self::session::SessionSettings;
+use self::settings::WebSettings;
+use self::telegram::TelegramWebhook;
+use accounting::Actor;
 use error::AppError;
 use registry::Registry;
 
@@ -41,29 +50,80 @@ fn start_web(app: WebApp) -> Result<(), FailureError> {
     let config = crate::config::config(&app.config_path)?;
     let config_without_passwords = crate::config::Config {
         email_smtp_credential_password: None,
+        session_secret: None,
+        telegram_webhook_secret: None,
         ..config.clone()
     };
     info!("config: {:?}", &config_without_passwords);
-    let registry = Registry::new(config.data_path.clone().into())?;
+    let registry = Registry::new(
+        config.data_path.clone().into(),
+        config.storage_format.unwrap_or_default(),
+    )?;
     info!("registry created");
+    let migrated = registry.migrate_legacy_tokens(&Actor::Web("migration".to_owned()))?;
+    if migrated > 0 {
+        info!("hashed {} legacy android tokens", migrated);
+    }
+    let settings = WebSettings::from_config(&config)?;
     let sys = actix::System::new("accounting-web");
-    let state = Arc::new(InnerAppState::new(registry));
-    server::new(move || {
-        App::<AppState>::with_state(state.clone())
+    let session_settings = SessionSettings::from_config(&config);
+    let telegram = TelegramWebhook::from_config(&config)?;
+    if telegram.is_some() {
+        info!("receiving telegram updates at /telegram/webhook");
+    }
+    let state = Arc::new(
+        InnerAppState::new(registry, session_settings.lifetime).with_telegram(telegram),
+    );
+    let app_settings = settings.clone();
+    let mut server = server::new(move || {
+        let sms_limit = app_settings.sms_limit;
+        let app = App::<AppState>::with_state(state.clone())
             .middleware(middleware::Logger::default())
+            .middleware(session_settings.storage())
             .resource("/android/v1/sms/latest", |r| {
                 r.method(http::Method::GET)
                     .with(android_adapter::get_sms_latest)
-            }).resource("/android/v1/sms", |r| {
-                r.method(http::Method::POST).with_config(android_adapter::post_sms, |cfg| { cfg.1.limit(2 * 1024 * 1024); })
-            })
-    }).bind("0.0.0.0:8080")
-    .unwrap()
-    .shutdown_timeout(1)
-    .workers(1)
-    .start();
+            }).resource("/android/v1/sms", move |r| {
+                r.method(http::Method::POST)
+                    .with_config(android_adapter::post_sms, move |cfg| {
+                        cfg.1.limit(sms_limit);
+                    })
+            }).resource("/android/v2/sms/cursor", |r| {
+                r.method(http::Method::GET)
+                    .with(android_adapter::get_sms_cursor)
+            }).resource("/android/v2/sms", move |r| {
+                r.method(http::Method::POST)
+                    .with_config(android_adapter::post_sms_batch, move |cfg| {
+                        (cfg.1).limit(sms_limit).error_handler(api::error::json_error);
+                    })
+            });
+        telegram::routes(session::routes(report::routes(api::routes(
+            app,
+            &app_settings,
+        ))))
+    });
+    for address in &settings.bind {
+        let bound = match settings.tls {
+            Some(ref tls) => server.bind_ssl(address, tls.acceptor()?),
+            None => server.bind(address),
+        };
+        server = bound.map_err(|e| AppError::WebBind {
+            address: address.clone(),
+            reason: format!("{}", e),
+        })?;
+    }
+    server
+        .workers(settings.workers)
+        .keep_alive(settings.keep_alive_seconds)
+        .client_timeout(settings.client_timeout_ms)
+        .shutdown_timeout(settings.shutdown_timeout_seconds)
+        .start();
 
-    info!("Started http server: 0.0.0.0:8080");
+    info!(
+        "Started http{} server: {}",
+        if settings.tls.is_some() { "s" } else { "" },
+        settings.bind.join(", ")
+    );
     match sys.run() {
         0 => Ok(()),
         code @ _ => Err(AppError::ActixError { code }.into()),
@@ -0,0 +1,88 @@
+use actix_web::{http, App, HttpRequest, HttpResponse, Query, State};
+
+use super::api::error::ApiError;
+use super::api::reports::{time_period, ReportQuery};
+use super::api::authenticate;
+use super::auth::TokenAuth;
+use super::AppState;
+use accounting::Scope;
+use error::AppError;
+use representation::ReactReportRepresentation;
+
+const PERIOD_SWITCHER: &'static str = include_str!("../../report/d3ia/public/period-switcher.js");
+const MANIFEST: &'static str = include_str!("../../report/d3ia/public/manifest.json");
+const FAVICON: &'static [u8] = include_bytes!("../../report/d3ia/public/favicon.ico");
+
+pub fn routes(app: App<AppState>) -> App<AppState> {
+    app.resource("/report", |r| {
+        r.method(http::Method::GET).with(page);
+    }).resource("/report/data", |r| {
+        r.method(http::Method::GET).with(data);
+    }).resource("/report/static/period-switcher.js", |r| {
+        r.method(http::Method::GET).f(period_switcher);
+    }).resource("/manifest.json", |r| {
+        r.method(http::Method::GET).f(manifest);
+    }).resource("/favicon.ico", |r| {
+        r.method(http::Method::GET).f(favicon);
+    })
+}
+
+pub fn page(
+    state: State<AppState>,
+    auth: TokenAuth,
+    query: Query<ReportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user = authenticate(&state, &auth, Scope::Read)?.user;
+    let registry = state.registry()?;
+    let time_period = time_period(&query)?;
+    let locale = registry.locale(&user.id)?;
+    let statistics = registry.statistics_for(user.id, &time_period)?;
+    let no_data = ApiError::not_found(format!("{}", AppError::NoDataForPeriod));
+    let report = statistics.report(time_period)?.ok_or(no_data)?;
+    let page = format!(
+        "{}",
+        ReactReportRepresentation::from(report).with_locale(locale)
+    );
+    let switcher = "<script src=\"/report/static/period-switcher.js\"></script></body>";
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(page.replacen("</body>", switcher, 1)))
+}
+
+pub fn data(
+    state: State<AppState>,
+    auth: TokenAuth,
+    query: Query<ReportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user = authenticate(&state, &auth, Scope::Read)?.user;
+    let registry = state.registry()?;
+    let time_period = time_period(&query)?;
+    let locale = registry.locale(&user.id)?;
+    let statistics = registry.statistics_for(user.id, &time_period)?;
+    let no_data = ApiError::not_found(format!("{}", AppError::NoDataForPeriod));
+    let report = statistics.report(time_period)?.ok_or(no_data)?;
+    let app_data = ReactReportRepresentation::from(report)
+        .with_locale(locale)
+        .app_data()?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(app_data))
+}
+
+fn period_switcher(_req: &HttpRequest<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/javascript; charset=utf-8")
+        .body(PERIOD_SWITCHER)
+}
+
+fn manifest(_req: &HttpRequest<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/manifest+json")
+        .body(MANIFEST)
+}
+
+fn favicon(_req: &HttpRequest<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("image/x-icon")
+        .body(FAVICON)
+}
@@ -0,0 +1,192 @@
+use actix_web::middleware::session::{CookieSessionBackend, RequestSession, SessionStorage};
+use actix_web::{http, App, HttpRequest, HttpResponse, Path};
+use chrono::Duration;
+use uuid::Uuid;
+
+use super::api::error::ApiError;
+use super::AppState;
+use accounting::Actor;
+use config::Config;
+
+const SESSION_COOKIE: &'static str = "accounting-session";
+const SESSION_USER: &'static str = "user";
+const DEFAULT_LIFETIME_HOURS: i64 = 24;
+const MIN_SECRET_LENGTH: usize = 32;
+const LOGIN_PAGE: &'static str = "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"><title>Log in</title></head>\
+<body><form method=\"post\"><button type=\"submit\">Log in</button></form></body></html>";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionUser {
+    pub user_id: String,
+    pub expires: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionSettings {
+    key: Vec<u8>,
+    secure: bool,
+    pub lifetime: Duration,
+}
+
+impl SessionUser {
+    pub fn from_request(req: &HttpRequest<AppState>) -> Option<SessionUser> {
+        match req.session().get::<SessionUser>(SESSION_USER) {
+            Ok(user) => user,
+            Err(e) => {
+                warn!("ignoring unreadable session: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        ::chrono::offset::Local::now().naive_local().timestamp() >= self.expires
+    }
+}
+
+impl SessionSettings {
+    pub fn from_config(config: &Config) -> SessionSettings {
+        let key = match config.session_secret {
+            Some(ref secret) if secret.len() >= MIN_SECRET_LENGTH => secret.as_bytes().to_vec(),
+            Some(_) => {
+                warn!(
+                    "\"session_secret\" is shorter than {} characters, using a random one",
+                    MIN_SECRET_LENGTH
+                );
+                random_key()
+            }
+            None => {
+                info!("\"session_secret\" is not set, sessions will not survive a restart");
+                random_key()
+            }
+        };
+        let secure = config
+            .web_public_url
+            .as_ref()
+            .map(|url| url.starts_with("https://"))
+            .unwrap_or(false);
+        SessionSettings {
+            key,
+            secure,
+            lifetime: Duration::hours(
+                config
+                    .session_lifetime_hours
+                    .unwrap_or(DEFAULT_LIFETIME_HOURS),
+            ),
+        }
+    }
+
+    pub fn storage(&self) -> SessionStorage<CookieSessionBackend, AppState> {
+        SessionStorage::new(
+            CookieSessionBackend::signed(&self.key)
+                .name(SESSION_COOKIE)
+                .path("/")
+                .http_only(true)
+                .secure(self.secure)
+                .max_age(self.lifetime),
+        )
+    }
+}
+
+pub fn routes(app: App<AppState>) -> App<AppState> {
+    app.resource("/login/{code}", |r| {
+        r.method(http::Method::GET).f(login_page);
+        r.method(http::Method::POST).with(login);
+    }).resource("/logout", |r| {
+        r.method(http::Method::GET).f(logout);
+        r.method(http::Method::POST).f(logout);
+    })
+}
+
+fn login_page(_req: &HttpRequest<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(LOGIN_PAGE)
+}
+
+pub fn login(req: HttpRequest<AppState>, code: Path<String>) -> Result<HttpResponse, ApiError> {
+    let user_id = {
+        let registry = req.state().registry_mut()?;
+        registry
+            .redeem_login(&code, &Actor::Web("login".to_owned()))?
+            .ok_or(ApiError::unauthorized("Login link is invalid, used or expired"))?
+    };
+    let expires = ::chrono::offset::Local::now().naive_local() + req.state().session_lifetime;
+    req.session()
+        .set(
+            SESSION_USER,
+            SessionUser {
+                user_id: user_id.0,
+                expires: expires.timestamp(),
+            },
+        ).map_err(|e| format_err!("Failed to start session: {}", e))?;
+    Ok(HttpResponse::SeeOther()
+        .header(http::header::LOCATION, "/report")
+        .finish())
+}
+
+fn logout(req: &HttpRequest<AppState>) -> HttpResponse {
+    req.session().clear();
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body("logged out")
+}
+
+fn random_key() -> Vec<u8> {
+    let mut key = Vec::with_capacity(MIN_SECRET_LENGTH);
+    key.extend_from_slice(Uuid::new_v4().as_bytes());
+    key.extend_from_slice(Uuid::new_v4().as_bytes());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{header, Method, StatusCode};
+    use actix_web::test::TestServer;
+    use actix_web::{App, HttpMessage};
+
+    use std::sync::Arc;
+
+    use super::*;
+    use accounting::TelegramId;
+    use persistence::Format;
+    use testing::temporary_registry;
+    use web::state::AppState as InnerAppState;
+
+    #[test]
+    fn redeems_login_code_only_on_post() {
+        let (registry, path) = temporary_registry(Format::Json);
+        let user = registry.find_or_create(TelegramId(1), &Actor::Cli).unwrap();
+        let code = registry
+            .issue_login(user.id, Duration::minutes(10), &Actor::Cli)
+            .unwrap();
+        let config: Config = ::serde_yaml::from_str(&format!(
+            "data_path: {:?}\ntelegram_user_id: 1\nallowed_telegram_users: []\nmax_number_of_tries: 1\n",
+            *path
+        )).unwrap();
+        let settings = SessionSettings::from_config(&config);
+        let state = Arc::new(InnerAppState::new(registry, settings.lifetime));
+        let mut server = TestServer::with_factory(move || {
+            routes(App::with_state(state.clone()).middleware(settings.storage()))
+        });
+        let login_path = format!("/login/{}", code);
+
+        for _ in 0..2 {
+            let request = server.client(Method::GET, &login_path).finish().unwrap();
+            let response = server.execute(request.send()).unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let page = server.execute(response.body()).unwrap();
+            assert!(String::from_utf8_lossy(&page).contains("method=\"post\""));
+        }
+
+        let request = server.client(Method::POST, &login_path).finish().unwrap();
+        let response = server.execute(request.send()).unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[header::LOCATION], "/report");
+
+        let request = server.client(Method::POST, &login_path).finish().unwrap();
+        let response = server.execute(request.send()).unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
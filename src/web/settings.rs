@@ -0,0 +1,98 @@
+use failure::Error as FailureError;
+use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod};
+
+use std::path::PathBuf;
+
+use config::Config;
+use error::AppError;
+
+const DEFAULT_BIND: &'static str = "0.0.0.0:8080";
+const DEFAULT_WORKERS: usize = 1;
+const DEFAULT_JSON_LIMIT: usize = 256 * 1024;
+const DEFAULT_SMS_LIMIT: usize = 2 * 1024 * 1024;
+const DEFAULT_KEEP_ALIVE_SECONDS: usize = 5;
+const DEFAULT_CLIENT_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_SHUTDOWN_TIMEOUT_SECONDS: u16 = 1;
+
+#[derive(Debug, Clone)]
+pub struct WebSettings {
+    pub bind: Vec<String>,
+    pub workers: usize,
+    pub json_limit: usize,
+    pub sms_limit: usize,
+    pub tls: Option<Tls>,
+    pub keep_alive_seconds: usize,
+    pub client_timeout_ms: u64,
+    pub shutdown_timeout_seconds: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tls {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+impl WebSettings {
+    pub fn from_config(config: &Config) -> Result<WebSettings, FailureError> {
+        let bind = config
+            .web_bind
+            .clone()
+            .unwrap_or_else(|| vec![DEFAULT_BIND.to_owned()]);
+        if bind.is_empty() {
+            return Err(AppError::InvalidWebConfig {
+                reason: "\"web_bind\" must contain at least one address".to_owned(),
+            }.into());
+        }
+        let workers = config.web_workers.unwrap_or(DEFAULT_WORKERS);
+        if workers == 0 {
+            return Err(AppError::InvalidWebConfig {
+                reason: "\"web_workers\" must be positive".to_owned(),
+            }.into());
+        }
+        let tls = match (&config.web_tls_cert, &config.web_tls_key) {
+            (&Some(ref cert), &Some(ref key)) => Some(Tls {
+                cert: PathBuf::from(cert),
+                key: PathBuf::from(key),
+            }),
+            (&None, &None) => None,
+            _ => {
+                return Err(AppError::InvalidWebConfig {
+                    reason: "\"web_tls_cert\" and \"web_tls_key\" must be set together".to_owned(),
+                }.into())
+            }
+        };
+        Ok(WebSettings {
+            bind,
+            workers,
+            json_limit: config.web_json_limit_bytes.unwrap_or(DEFAULT_JSON_LIMIT),
+            sms_limit: config.web_sms_limit_bytes.unwrap_or(DEFAULT_SMS_LIMIT),
+            tls,
+            keep_alive_seconds: config
+                .web_keep_alive_seconds
+                .unwrap_or(DEFAULT_KEEP_ALIVE_SECONDS),
+            client_timeout_ms: config
+                .web_client_timeout_ms
+                .unwrap_or(DEFAULT_CLIENT_TIMEOUT_MS),
+            shutdown_timeout_seconds: config
+                .web_shutdown_timeout_seconds
+                .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECONDS),
+        })
+    }
+}
+
+impl Tls {
+    pub fn acceptor(&self) -> Result<SslAcceptorBuilder, FailureError> {
+        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+        builder
+            .set_private_key_file(&self.key, SslFiletype::PEM)
+            .map_err(|e| AppError::InvalidWebConfig {
+                reason: format!("could not load {:?}: {}", &self.key, e),
+            })?;
+        builder
+            .set_certificate_chain_file(&self.cert)
+            .map_err(|e| AppError::InvalidWebConfig {
+                reason: format!("could not load {:?}: {}", &self.cert, e),
+            })?;
+        Ok(builder)
+    }
+}
@@ -1,16 +1,107 @@
-use std::sync::Mutex;
+use chrono::Duration;
+use failure::Error as FailureError;
 
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::telegram::TelegramWebhook;
 use registry::Registry;
 
 #[derive(Debug)]
 pub struct AppState {
-    pub registry: Mutex<Registry>,
+    registry: RwLock<Registry>,
+    pub session_lifetime: Duration,
+    pub telegram: Option<TelegramWebhook>,
 }
 
 impl AppState {
-    pub fn new(registry: Registry) -> AppState {
+    pub fn new(registry: Registry, session_lifetime: Duration) -> AppState {
         AppState {
-            registry: Mutex::new(registry),
+            registry: RwLock::new(registry),
+            session_lifetime,
+            telegram: None,
         }
     }
+
+    pub fn with_telegram(self, telegram: Option<TelegramWebhook>) -> AppState {
+        AppState { telegram, ..self }
+    }
+
+    pub fn registry(&self) -> Result<RwLockReadGuard<Registry>, FailureError> {
+        self.registry
+            .read()
+            .map_err(|e| format_err!("Failed to get registry: {}", e))
+    }
+
+    pub fn registry_mut(&self) -> Result<RwLockWriteGuard<Registry>, FailureError> {
+        self.registry
+            .write()
+            .map_err(|e| format_err!("Failed to get registry: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use accounting::statistics::TimePeriod;
+    use accounting::{Actor, NewSms, TelegramId};
+    use persistence::Format;
+    use testing::temporary_registry;
+
+    #[test]
+    fn serves_concurrent_sms_uploads_and_reports() {
+        let (registry, _dir) = temporary_registry(Format::Json);
+        let user = registry.find_or_create(TelegramId(1), &Actor::Cli).unwrap();
+        let state = Arc::new(AppState::new(registry, Duration::hours(1)));
+
+        let requests: Vec<_> = (0..16)
+            .map(|n| {
+                let state = state.clone();
+                let user = user.id.clone();
+                thread::spawn(move || {
+                    if n % 2 == 0 {
+                        let sms = NewSms {
+                            user,
+                            from: "bank".to_owned(),
+                            text: format!("sms {}", n),
+                            time: ::chrono::Utc::now(),
+                            client_id: Some(format!("{}", n)),
+                        };
+                        state
+                            .registry_mut()
+                            .unwrap()
+                            .add_sms(vec![sms], &Actor::Web("test".to_owned()))
+                            .unwrap();
+                    } else {
+                        state
+                            .registry()
+                            .unwrap()
+                            .statistics_for(user, &TimePeriod::ThisMonth)
+                            .unwrap();
+                    }
+                })
+            }).collect();
+        for request in requests {
+            request.join().unwrap();
+        }
+
+        let sms_list = state.registry().unwrap().get_sms_list(user.id).unwrap();
+        assert_eq!(sms_list.len(), 8);
+    }
+
+    #[test]
+    fn allows_parallel_readers() {
+        let (registry, _dir) = temporary_registry(Format::Json);
+        let state = AppState::new(registry, Duration::hours(1));
+
+        let first = state.registry().unwrap();
+        let second = state.registry().unwrap();
+        assert_eq!(first.list_users().unwrap().len(), second.list_users().unwrap().len());
+        drop(first);
+        drop(second);
+        assert!(state.registry_mut().is_ok());
+    }
 }
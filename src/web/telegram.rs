@@ -0,0 +1,157 @@
+use actix_web::{http, App, HttpRequest, HttpResponse, Json};
+use failure::Error as FailureError;
+
+use super::api::error::ApiError;
+use super::AppState;
+use accounting::constant_time_eq;
+use backup::DailyBackup;
+use bot::webhook::{deliver, dispatch, photo, Update};
+use bot::{is_allowed, scan_photo};
+use config::{Config, TelegramMode};
+use error::AppError;
+
+const SECRET_TOKEN_HEADER: &'static str = "X-Telegram-Bot-Api-Secret-Token";
+
+#[derive(Debug)]
+pub struct TelegramWebhook {
+    config: Config,
+    secret: String,
+    daily_backup: Option<DailyBackup>,
+}
+
+impl TelegramWebhook {
+    pub fn from_config(config: &Config) -> Result<Option<TelegramWebhook>, FailureError> {
+        if config.telegram_mode.unwrap_or_default() != TelegramMode::Webhook {
+            return Ok(None);
+        }
+        let secret = config
+            .telegram_webhook_secret
+            .clone()
+            .ok_or(AppError::InvalidWebConfig {
+                reason: "\"telegram_webhook_secret\" is required in webhook mode".to_owned(),
+            })?;
+        Ok(Some(TelegramWebhook {
+            config: config.clone(),
+            secret,
+            daily_backup: DailyBackup::from_config(config),
+        }))
+    }
+
+    fn is_authorized(&self, req: &HttpRequest<AppState>) -> bool {
+        match req.headers().get(SECRET_TOKEN_HEADER) {
+            Some(token) => constant_time_eq(token.as_bytes(), self.secret.as_bytes()),
+            None => false,
+        }
+    }
+}
+
+pub fn routes(app: App<AppState>) -> App<AppState> {
+    app.resource("/telegram/webhook", |r| {
+        r.method(http::Method::POST).with(webhook);
+    })
+}
+
+pub fn webhook(req: HttpRequest<AppState>, update: Json<Update>) -> Result<HttpResponse, ApiError> {
+    let telegram = req
+        .state()
+        .telegram
+        .as_ref()
+        .ok_or(ApiError::not_found("webhook mode is disabled"))?;
+    if !telegram.is_authorized(&req) {
+        return Err(ApiError::unauthorized("Secret token does not match"));
+    }
+    if let Some(ref daily_backup) = telegram.daily_backup {
+        let _registry = req.state().registry()?;
+        if let Err(e) = daily_backup.run_if_due() {
+            error!("daily backup failed: {}", e);
+        }
+    }
+    let update = update.into_inner();
+    let scanned = match photo(&update) {
+        Some((author_id, file_id)) => {
+            let allowed = {
+                let registry = req.state().registry()?;
+                is_allowed(&telegram.config, &registry, author_id)?
+            };
+            if allowed {
+                Some(scan_photo(file_id))
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+    let outcome = {
+        let registry = req.state().registry_mut()?;
+        dispatch(update, scanned, &telegram.config, &registry)?
+    };
+    match outcome.and_then(|outcome| deliver(outcome, &telegram.config)) {
+        Some(reply) => Ok(HttpResponse::Ok().json(reply)),
+        None => Ok(HttpResponse::Ok().finish()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{header, Method, StatusCode};
+    use actix_web::test::TestServer;
+    use actix_web::HttpMessage;
+    use chrono::Duration;
+
+    use std::sync::Arc;
+
+    use super::*;
+    use persistence::Format;
+    use testing::temporary_registry;
+    use web::state::AppState as InnerAppState;
+
+    const RECORDED_UPDATE: &'static str = r#"{
+        "update_id": 10000,
+        "message": {
+            "message_id": 1365,
+            "date": 1441645532,
+            "chat": {"id": 1111111, "type": "private", "first_name": "Test"},
+            "from": {"id": 1111111, "is_bot": false, "first_name": "Test"},
+            "text": "/help"
+        }
+    }"#;
+
+    #[test]
+    fn dispatches_updates_with_matching_secret() {
+        let (registry, path) = temporary_registry(Format::Json);
+        let config: Config = ::serde_yaml::from_str(&format!(
+            "data_path: {:?}\ntelegram_user_id: 1111111\nallowed_telegram_users: [1111111]\nmax_number_of_tries: 1\ntelegram_mode: webhook\ntelegram_webhook_secret: s3cret\n",
+            *path
+        )).unwrap();
+        let telegram = TelegramWebhook::from_config(&config).unwrap();
+        let state =
+            Arc::new(InnerAppState::new(registry, Duration::days(1)).with_telegram(telegram));
+        let mut server = TestServer::with_factory(move || routes(App::with_state(state.clone())));
+
+        for secret in vec![None, Some("wrong"), Some("s3cre")] {
+            let mut request = server.client(Method::POST, "/telegram/webhook");
+            request.header(header::CONTENT_TYPE, "application/json");
+            if let Some(secret) = secret {
+                request.header(SECRET_TOKEN_HEADER, secret);
+            }
+            let request = request.body(RECORDED_UPDATE.to_owned()).unwrap();
+            let response = server.execute(request.send()).unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        let request = server
+            .client(Method::POST, "/telegram/webhook")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(SECRET_TOKEN_HEADER, "s3cret")
+            .body(RECORDED_UPDATE.to_owned())
+            .unwrap();
+        let response = server.execute(request.send()).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = server.execute(response.body()).unwrap();
+        let reply: ::serde_json::Value = ::serde_json::from_slice(&body).unwrap();
+        assert_eq!(reply["method"], "sendMessage");
+        assert_eq!(reply["chat_id"], 1111111);
+        assert_eq!(reply["reply_to_message_id"], 1365);
+        assert!(!reply["text"].as_str().unwrap().is_empty());
+    }
+}